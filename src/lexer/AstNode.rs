@@ -1,34 +1,960 @@
+#[derive(Debug, PartialEq, Clone)]
 pub enum AstNode {
     NumberLiteral(f64),
     StringLiteral(String),
-    Identifier(String),
+    BooleanLiteral(bool),
+    NullLiteral,
+    // `type_annotation` and `optional` are only set on bindings in
+    // TypeScript and Flow code, as in `(x?: number)`, here and on the other
+    // patterns
+    Identifier {
+        name: String,
+        type_annotation: Option<Box<AstNode>>,
+        optional: bool,
+    },
+    ThisExpression,
+    Super,
+    // `#name`, as a class member key, member property or left of `in`
+    PrivateIdentifier(String),
+    // `None` elements are holes, as in `[1, , 3]`
+    ArrayExpression {
+        elements: Vec<Option<AstNode>>,
+    },
+    ObjectExpression {
+        properties: Vec<AstNode>,
+    },
+    // `kind` is "init" for data properties and methods, or "get"/"set" for
+    // accessors
+    Property {
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+        kind: String,
+        computed: bool,
+        shorthand: bool,
+        method: bool,
+    },
+    SpreadElement {
+        argument: Box<AstNode>,
+    },
+    // Destructuring targets. Object patterns hold `Property` and
+    // `RestElement` nodes; `None` array pattern elements are holes.
+    ObjectPattern {
+        properties: Vec<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+        optional: bool,
+    },
+    ArrayPattern {
+        elements: Vec<Option<AstNode>>,
+        type_annotation: Option<Box<AstNode>>,
+        optional: bool,
+    },
+    // A target with a default value, as in `[a = 1] = xs`
+    AssignmentPattern {
+        left: Box<AstNode>,
+        right: Box<AstNode>,
+    },
+    RestElement {
+        argument: Box<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+    },
+    // `type_parameters` and `return_type` are only set by TypeScript code,
+    // here and on the other function nodes
+    FunctionExpression {
+        id: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        body: Box<AstNode>,
+        generator: bool,
+        r#async: bool,
+        type_parameters: Option<Box<AstNode>>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // `expression` is set when the body is an expression rather than a block
+    ArrowFunctionExpression {
+        params: Vec<AstNode>,
+        body: Box<AstNode>,
+        expression: bool,
+        r#async: bool,
+        type_parameters: Option<Box<AstNode>>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // `type_arguments` holds TypeScript's `f<T>()`
     CallExpression {
         callee: Box<AstNode>,
         arguments: Vec<AstNode>,
+        optional: bool,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    NewExpression {
+        callee: Box<AstNode>,
+        arguments: Vec<AstNode>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    MemberExpression {
+        object: Box<AstNode>,
+        property: Box<AstNode>,
+        computed: bool,
+        optional: bool,
+    },
+    // Wraps an expression containing `?.`, delimiting how far a nullish
+    // short-circuit skips
+    ChainExpression {
+        expression: Box<AstNode>,
+    },
+    // `import(source)` or `import(source, options)`; `phase` is "defer" or
+    // "source" for `import.defer(source)` and `import.source(source)`
+    ImportExpression {
+        source: Box<AstNode>,
+        options: Option<Box<AstNode>>,
+        phase: Option<String>,
+    },
+    // `new.target` or `import.meta`
+    MetaProperty {
+        meta: Box<AstNode>,
+        property: Box<AstNode>,
+    },
+    UnaryExpression {
+        operator: String,
+        argument: Box<AstNode>,
+    },
+    UpdateExpression {
+        operator: String,
+        prefix: bool,
+        argument: Box<AstNode>,
+    },
+    AwaitExpression {
+        argument: Box<AstNode>,
     },
+    // `delegate` is set for `yield*`
+    YieldExpression {
+        argument: Option<Box<AstNode>>,
+        delegate: bool,
+    },
+    // `operator` is "|>" for a pipeline, whose right side refers to the
+    // left one as `TopicReference`
     BinaryExpression {
         operator: String,
         left: Box<AstNode>,
         right: Box<AstNode>,
     },
+    // `%` in the body of a pipeline
+    TopicReference,
+    // `do { ... }` as an expression, whose value is that of the last
+    // statement in the block
+    DoExpression {
+        body: Box<AstNode>,
+    },
+    ThrowExpression {
+        argument: Box<AstNode>,
+    },
+    LogicalExpression {
+        operator: String,
+        left: Box<AstNode>,
+        right: Box<AstNode>,
+    },
+    TernaryExpression {
+        left: Box<AstNode>,
+        middle: Box<AstNode>,
+        right: Box<AstNode>,
+    },
+    SequenceExpression {
+        expressions: Vec<AstNode>,
+    },
     AssignmentExpression {
         operator: String,
         left: Box<AstNode>,
         right: Box<AstNode>,
     },
+    // `kind` is "var", "let", "const", "using" or "await using"; `declare` is
+    // set by TypeScript's `declare let x: T`
     VariableDeclaration {
+        kind: String,
+        declarations: Vec<AstNode>,
+        declare: bool,
+    },
+    // `definite` is set by TypeScript's `let x!: T`
+    VariableDeclarator {
         id: Box<AstNode>,
-        init: Box<AstNode>
+        init: Option<Box<AstNode>>,
+        definite: bool,
+    },
+    // `directive` holds the raw text of a directive prologue entry such as
+    // `"use strict"`, without its quotes.
+    ExpressionStatement {
+        expression: Box<AstNode>,
+        directive: Option<String>,
     },
     BlockStatement {
         body: Vec<AstNode>
     },
+    IfStatement {
+        test: Box<AstNode>,
+        consequent: Box<AstNode>,
+        alternate: Option<Box<AstNode>>
+    },
+    ReturnStatement {
+        argument: Option<Box<AstNode>>
+    },
+    EmptyStatement,
+    DebuggerStatement,
+    WhileStatement {
+        test: Box<AstNode>,
+        body: Box<AstNode>,
+    },
+    DoWhileStatement {
+        body: Box<AstNode>,
+        test: Box<AstNode>,
+    },
+    ForStatement {
+        init: Option<Box<AstNode>>,
+        test: Option<Box<AstNode>>,
+        update: Option<Box<AstNode>>,
+        body: Box<AstNode>,
+    },
+    ForInStatement {
+        left: Box<AstNode>,
+        right: Box<AstNode>,
+        body: Box<AstNode>,
+    },
+    // `await` is set for `for await (... of ...)`
+    ForOfStatement {
+        left: Box<AstNode>,
+        right: Box<AstNode>,
+        body: Box<AstNode>,
+        r#await: bool,
+    },
+    SwitchStatement {
+        discriminant: Box<AstNode>,
+        cases: Vec<AstNode>,
+    },
+    // `test` is `None` for the `default` clause
+    SwitchCase {
+        test: Option<Box<AstNode>>,
+        consequent: Vec<AstNode>,
+    },
+    TryStatement {
+        block: Box<AstNode>,
+        handler: Option<Box<AstNode>>,
+        finalizer: Option<Box<AstNode>>,
+    },
+    CatchClause {
+        param: Option<Box<AstNode>>,
+        body: Box<AstNode>,
+    },
+    ThrowStatement {
+        argument: Box<AstNode>,
+    },
+    LabeledStatement {
+        label: Box<AstNode>,
+        body: Box<AstNode>,
+    },
+    BreakStatement {
+        label: Option<Box<AstNode>>,
+    },
+    ContinueStatement {
+        label: Option<Box<AstNode>>,
+    },
+    WithStatement {
+        object: Box<AstNode>,
+        body: Box<AstNode>,
+    },
     FunctionDeclaration {
         id: Box<AstNode>,
         params: Vec<AstNode>,
-        body: Box<AstNode>
+        body: Box<AstNode>,
+        generator: bool,
+        r#async: bool,
+        type_parameters: Option<Box<AstNode>>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // The fields after `decorators` are only set by TypeScript code, as in
+    // `declare abstract class A<T> extends B<T> implements C {}`
+    ClassDeclaration {
+        id: Box<AstNode>,
+        super_class: Option<Box<AstNode>>,
+        body: Box<AstNode>,
+        decorators: Vec<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        super_type_arguments: Option<Box<AstNode>>,
+        implements: Vec<AstNode>,
+        r#abstract: bool,
+        declare: bool,
+    },
+    ClassExpression {
+        id: Option<Box<AstNode>>,
+        super_class: Option<Box<AstNode>>,
+        body: Box<AstNode>,
+        decorators: Vec<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        super_type_arguments: Option<Box<AstNode>>,
+        implements: Vec<AstNode>,
+    },
+    ClassBody {
+        body: Vec<AstNode>,
+    },
+    // `kind` is "constructor", "method", "get" or "set". `accessibility` is
+    // TypeScript's "public", "private" or "protected", and `optional` marks
+    // `m?() {}`.
+    MethodDefinition {
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+        kind: String,
+        computed: bool,
+        r#static: bool,
+        decorators: Vec<AstNode>,
+        accessibility: Option<String>,
+        optional: bool,
+        r#override: bool,
+    },
+    // A class field, with `value` as its optional initialiser. The fields
+    // after `decorators` hold TypeScript's annotation and modifiers, as in
+    // `private declare readonly x?: T` or `x!: T`.
+    PropertyDefinition {
+        key: Box<AstNode>,
+        value: Option<Box<AstNode>>,
+        computed: bool,
+        r#static: bool,
+        decorators: Vec<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+        accessibility: Option<String>,
+        optional: bool,
+        definite: bool,
+        readonly: bool,
+        declare: bool,
+        r#override: bool,
+        // Flow's `+` or `-` before the key
+        variance: Option<Box<AstNode>>,
+    },
+    // `accessor x = 1`, a field with generated getter and setter
+    AccessorProperty {
+        key: Box<AstNode>,
+        value: Option<Box<AstNode>>,
+        computed: bool,
+        r#static: bool,
+        decorators: Vec<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+        accessibility: Option<String>,
+        r#override: bool,
+    },
+    // `@expression` before a class or class member
+    Decorator {
+        expression: Box<AstNode>,
+    },
+    StaticBlock {
+        body: Vec<AstNode>,
+    },
+    // `import x, { a as b } from "m" with { type: "json" }`; `specifiers`
+    // is empty for side-effect imports. `import_kind` is "type" for
+    // `import type`, and "value" otherwise. `phase` is "defer" or "source"
+    // for `import defer` and `import source`.
+    ImportDeclaration {
+        specifiers: Vec<AstNode>,
+        source: Box<AstNode>,
+        attributes: Vec<AstNode>,
+        import_kind: String,
+        phase: Option<String>,
+    },
+    ImportSpecifier {
+        imported: Box<AstNode>,
+        local: Box<AstNode>,
+        import_kind: String,
+    },
+    ImportDefaultSpecifier {
+        local: Box<AstNode>,
+    },
+    ImportNamespaceSpecifier {
+        local: Box<AstNode>,
+    },
+    ImportAttribute {
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+    },
+    // Either exports a declaration, or lists specifiers with an optional
+    // `source` to re-export from. `export_kind` is "type" for `export type`,
+    // and "value" otherwise.
+    ExportNamedDeclaration {
+        declaration: Option<Box<AstNode>>,
+        specifiers: Vec<AstNode>,
+        source: Option<Box<AstNode>>,
+        attributes: Vec<AstNode>,
+        export_kind: String,
+    },
+    ExportSpecifier {
+        local: Box<AstNode>,
+        exported: Box<AstNode>,
+        export_kind: String,
+    },
+    ExportDefaultDeclaration {
+        declaration: Box<AstNode>,
+    },
+    // `export * from "m"`, or `export * as ns from "m"` with `exported` set
+    ExportAllDeclaration {
+        exported: Option<Box<AstNode>>,
+        source: Box<AstNode>,
+        attributes: Vec<AstNode>,
+    },
+    // `<a b="c">text</a>`; `closing_element` is `None` when the opening
+    // element is self-closing
+    JSXElement {
+        opening_element: Box<AstNode>,
+        children: Vec<AstNode>,
+        closing_element: Option<Box<AstNode>>,
+    },
+    JSXOpeningElement {
+        name: Box<AstNode>,
+        attributes: Vec<AstNode>,
+        self_closing: bool,
+    },
+    JSXClosingElement {
+        name: Box<AstNode>,
+    },
+    // `<>children</>`
+    JSXFragment {
+        children: Vec<AstNode>,
+    },
+    JSXIdentifier(String),
+    // `svg:rect`
+    JSXNamespacedName {
+        namespace: Box<AstNode>,
+        name: Box<AstNode>,
+    },
+    // `Foo.Bar`, as a tag name
+    JSXMemberExpression {
+        object: Box<AstNode>,
+        property: Box<AstNode>,
+    },
+    // `value` is `None` for a bare attribute such as `disabled`
+    JSXAttribute {
+        name: Box<AstNode>,
+        value: Option<Box<AstNode>>,
+    },
+    JSXSpreadAttribute {
+        argument: Box<AstNode>,
+    },
+    JSXExpressionContainer {
+        expression: Box<AstNode>,
+    },
+    // The missing expression of an empty `{}` child
+    JSXEmptyExpression,
+    // `{...children}` as a child
+    JSXSpreadChild {
+        expression: Box<AstNode>,
+    },
+    // Text between tags, with HTML entities decoded
+    JSXText(String),
+    // The raw text of a template literal before, between or after its
+    // substitutions; `tail` is set on the last one
+    TemplateElement {
+        raw: String,
+        tail: bool,
+    },
+    // TypeScript. Type annotations, type parameters and type arguments are
+    // `TSTypeAnnotation`, `TSTypeParameterDeclaration` and
+    // `TSTypeParameterInstantiation` nodes. Shapes follow typescript-estree.
+    TSTypeAnnotation {
+        type_annotation: Box<AstNode>,
+    },
+    TSTypeParameterDeclaration {
+        params: Vec<AstNode>,
+    },
+    // `const in out T extends U = V`
+    TSTypeParameter {
+        name: Box<AstNode>,
+        constraint: Option<Box<AstNode>>,
+        default: Option<Box<AstNode>>,
+        r#in: bool,
+        out: bool,
+        r#const: bool,
+    },
+    TSTypeParameterInstantiation {
+        params: Vec<AstNode>,
+    },
+    TSAnyKeyword,
+    TSUnknownKeyword,
+    TSNumberKeyword,
+    TSStringKeyword,
+    TSBooleanKeyword,
+    TSBigIntKeyword,
+    TSSymbolKeyword,
+    TSObjectKeyword,
+    TSNeverKeyword,
+    TSVoidKeyword,
+    TSUndefinedKeyword,
+    TSNullKeyword,
+    TSThisType,
+    // `Name<T>`, where `type_name` is an `Identifier` or `TSQualifiedName`
+    TSTypeReference {
+        type_name: Box<AstNode>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    // `A.B`
+    TSQualifiedName {
+        left: Box<AstNode>,
+        right: Box<AstNode>,
+    },
+    // `"a"`, `1`, `-1` or `true` as a type
+    TSLiteralType {
+        literal: Box<AstNode>,
+    },
+    // `` `a${B}c` `` as a type; `quasis` holds one more `TemplateElement`
+    // than there are `types`
+    TSTemplateLiteralType {
+        quasis: Vec<AstNode>,
+        types: Vec<AstNode>,
+    },
+    TSArrayType {
+        element_type: Box<AstNode>,
+    },
+    TSTupleType {
+        element_types: Vec<AstNode>,
+    },
+    // `[name?: T]` in a tuple
+    TSNamedTupleMember {
+        label: Box<AstNode>,
+        element_type: Box<AstNode>,
+        optional: bool,
+    },
+    // `[T?]` and `[...T]` in a tuple
+    TSOptionalType {
+        type_annotation: Box<AstNode>,
+    },
+    TSRestType {
+        type_annotation: Box<AstNode>,
+    },
+    TSUnionType {
+        types: Vec<AstNode>,
+    },
+    TSIntersectionType {
+        types: Vec<AstNode>,
+    },
+    // `<T>(x: T) => U`
+    TSFunctionType {
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        return_type: Box<AstNode>,
+    },
+    // `abstract new (x: T) => U`
+    TSConstructorType {
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        return_type: Box<AstNode>,
+        r#abstract: bool,
+    },
+    // `{ a: T; b(): U }`
+    TSTypeLiteral {
+        members: Vec<AstNode>,
+    },
+    TSPropertySignature {
+        key: Box<AstNode>,
+        computed: bool,
+        optional: bool,
+        readonly: bool,
+        type_annotation: Option<Box<AstNode>>,
+    },
+    // `kind` is "method", "get" or "set"
+    TSMethodSignature {
+        key: Box<AstNode>,
+        computed: bool,
+        optional: bool,
+        kind: String,
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // `[key: string]: T`, in type literals, interfaces and classes
+    TSIndexSignature {
+        parameters: Vec<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+        readonly: bool,
+        r#static: bool,
+    },
+    // `(x: T): U` and `new (x: T): U` members
+    TSCallSignatureDeclaration {
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        return_type: Option<Box<AstNode>>,
+    },
+    TSConstructSignatureDeclaration {
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // `operator` is "keyof", "unique" or "readonly"
+    TSTypeOperator {
+        operator: String,
+        type_annotation: Box<AstNode>,
+    },
+    // `T[K]`
+    TSIndexedAccessType {
+        object_type: Box<AstNode>,
+        index_type: Box<AstNode>,
+    },
+    // `T extends U ? X : Y`
+    TSConditionalType {
+        check_type: Box<AstNode>,
+        extends_type: Box<AstNode>,
+        true_type: Box<AstNode>,
+        false_type: Box<AstNode>,
+    },
+    // `infer U`, holding a `TSTypeParameter`
+    TSInferType {
+        type_parameter: Box<AstNode>,
+    },
+    // `{ readonly [K in T as N]?: V }`. `readonly` and `optional` are "+",
+    // "-" or "true" when the modifier is present.
+    TSMappedType {
+        key: Box<AstNode>,
+        constraint: Box<AstNode>,
+        name_type: Option<Box<AstNode>>,
+        type_annotation: Option<Box<AstNode>>,
+        readonly: Option<String>,
+        optional: Option<String>,
+    },
+    // `typeof x.y`
+    TSTypeQuery {
+        expr_name: Box<AstNode>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    // `x is T`, `asserts x` or `asserts x is T` as a return type
+    TSTypePredicate {
+        parameter_name: Box<AstNode>,
+        type_annotation: Option<Box<AstNode>>,
+        asserts: bool,
+    },
+    // `import("m").A<T>`
+    TSImportType {
+        argument: Box<AstNode>,
+        qualifier: Option<Box<AstNode>>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    TSInterfaceDeclaration {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        extends: Vec<AstNode>,
+        body: Box<AstNode>,
+        declare: bool,
+    },
+    TSInterfaceBody {
+        body: Vec<AstNode>,
+    },
+    // An entry of an interface's `extends` list
+    TSInterfaceHeritage {
+        expression: Box<AstNode>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    // An entry of a class's `implements` list
+    TSClassImplements {
+        expression: Box<AstNode>,
+        type_arguments: Option<Box<AstNode>>,
+    },
+    TSTypeAliasDeclaration {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        type_annotation: Box<AstNode>,
+        declare: bool,
+    },
+    TSEnumDeclaration {
+        id: Box<AstNode>,
+        members: Vec<AstNode>,
+        r#const: bool,
+        declare: bool,
+    },
+    TSEnumMember {
+        id: Box<AstNode>,
+        initializer: Option<Box<AstNode>>,
+    },
+    // `kind` is "namespace", "module" or "global"; `body` is `None` for
+    // `declare module "m";`
+    TSModuleDeclaration {
+        id: Box<AstNode>,
+        body: Option<Box<AstNode>>,
+        kind: String,
+        declare: bool,
+    },
+    TSModuleBlock {
+        body: Vec<AstNode>,
+    },
+    // A function without a body: an overload signature or `declare function`
+    TSDeclareFunction {
+        id: Box<AstNode>,
+        params: Vec<AstNode>,
+        generator: bool,
+        r#async: bool,
+        type_parameters: Option<Box<AstNode>>,
+        return_type: Option<Box<AstNode>>,
+        declare: bool,
+    },
+    // The value of a class method without a body
+    TSEmptyBodyFunctionExpression {
+        params: Vec<AstNode>,
+        generator: bool,
+        r#async: bool,
+        type_parameters: Option<Box<AstNode>>,
+        return_type: Option<Box<AstNode>>,
+    },
+    // `constructor(private readonly x: T)`
+    TSParameterProperty {
+        parameter: Box<AstNode>,
+        accessibility: Option<String>,
+        readonly: bool,
+        r#override: bool,
+    },
+    TSAbstractPropertyDefinition {
+        key: Box<AstNode>,
+        computed: bool,
+        r#static: bool,
+        type_annotation: Option<Box<AstNode>>,
+        accessibility: Option<String>,
+        optional: bool,
+        readonly: bool,
+        r#override: bool,
+    },
+    // `value` is a `TSEmptyBodyFunctionExpression`
+    TSAbstractMethodDefinition {
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+        kind: String,
+        computed: bool,
+        r#static: bool,
+        accessibility: Option<String>,
+        optional: bool,
+        r#override: bool,
+    },
+    // `import a = require("m")` or `import a = B.c`
+    TSImportEqualsDeclaration {
+        id: Box<AstNode>,
+        module_reference: Box<AstNode>,
+        import_kind: String,
+    },
+    TSExternalModuleReference {
+        expression: Box<AstNode>,
+    },
+    // `export = x`
+    TSExportAssignment {
+        expression: Box<AstNode>,
+    },
+    // `export as namespace X`
+    TSNamespaceExportDeclaration {
+        id: Box<AstNode>,
+    },
+    TSAsExpression {
+        expression: Box<AstNode>,
+        type_annotation: Box<AstNode>,
+    },
+    TSSatisfiesExpression {
+        expression: Box<AstNode>,
+        type_annotation: Box<AstNode>,
+    },
+    // `x!`
+    TSNonNullExpression {
+        expression: Box<AstNode>,
+    },
+    // `f<T>`, type arguments without a call
+    TSInstantiationExpression {
+        expression: Box<AstNode>,
+        type_arguments: Box<AstNode>,
+    },
+    // `<T>x`
+    TSTypeAssertion {
+        type_annotation: Box<AstNode>,
+        expression: Box<AstNode>,
+    },
+    // Flow. Type annotations, type parameters and type arguments are
+    // `TypeAnnotation`, `TypeParameterDeclaration` and
+    // `TypeParameterInstantiation` nodes. Shapes follow Babel.
+    TypeAnnotation {
+        type_annotation: Box<AstNode>,
+    },
+    TypeParameterDeclaration {
+        params: Vec<AstNode>,
+    },
+    // `+T: Bound = Default`; `bound` is a `TypeAnnotation`
+    TypeParameter {
+        name: String,
+        bound: Option<Box<AstNode>>,
+        variance: Option<Box<AstNode>>,
+        default: Option<Box<AstNode>>,
+    },
+    TypeParameterInstantiation {
+        params: Vec<AstNode>,
+    },
+    // `+` or `-`, with kind "plus" or "minus"
+    Variance {
+        kind: String,
+    },
+    AnyTypeAnnotation,
+    MixedTypeAnnotation,
+    EmptyTypeAnnotation,
+    NumberTypeAnnotation,
+    StringTypeAnnotation,
+    BooleanTypeAnnotation,
+    SymbolTypeAnnotation,
+    BigIntTypeAnnotation,
+    VoidTypeAnnotation,
+    NullLiteralTypeAnnotation,
+    // `*`
+    ExistsTypeAnnotation,
+    StringLiteralTypeAnnotation(String),
+    NumberLiteralTypeAnnotation(f64),
+    BooleanLiteralTypeAnnotation(bool),
+    // `?T`
+    NullableTypeAnnotation {
+        type_annotation: Box<AstNode>,
+    },
+    ArrayTypeAnnotation {
+        element_type: Box<AstNode>,
+    },
+    TupleTypeAnnotation {
+        types: Vec<AstNode>,
+    },
+    UnionTypeAnnotation {
+        types: Vec<AstNode>,
+    },
+    IntersectionTypeAnnotation {
+        types: Vec<AstNode>,
+    },
+    TypeofTypeAnnotation {
+        argument: Box<AstNode>,
+    },
+    // A named type such as `Array<T>`; `id` may be a `QualifiedTypeIdentifier`
+    GenericTypeAnnotation {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+    },
+    // `A.B`
+    QualifiedTypeIdentifier {
+        qualification: Box<AstNode>,
+        id: Box<AstNode>,
+    },
+    FunctionTypeAnnotation {
+        type_parameters: Option<Box<AstNode>>,
+        params: Vec<AstNode>,
+        rest: Option<Box<AstNode>>,
+        return_type: Box<AstNode>,
+    },
+    // `x?: T`, or just `T` with no name
+    FunctionTypeParam {
+        name: Option<Box<AstNode>>,
+        type_annotation: Box<AstNode>,
+        optional: bool,
+    },
+    // `{| |}` is exact; a trailing `...` makes it explicitly inexact
+    ObjectTypeAnnotation {
+        properties: Vec<AstNode>,
+        indexers: Vec<AstNode>,
+        call_properties: Vec<AstNode>,
+        exact: bool,
+        inexact: bool,
+    },
+    // `static` is only set in the body of a declared class
+    ObjectTypeProperty {
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+        optional: bool,
+        method: bool,
+        variance: Option<Box<AstNode>>,
+        r#static: bool,
+    },
+    // `...T`
+    ObjectTypeSpreadProperty {
+        argument: Box<AstNode>,
+    },
+    // `[K]: V` or `[name: K]: V`
+    ObjectTypeIndexer {
+        id: Option<Box<AstNode>>,
+        key: Box<AstNode>,
+        value: Box<AstNode>,
+        variance: Option<Box<AstNode>>,
+    },
+    // `(x: T): U`
+    ObjectTypeCallProperty {
+        value: Box<AstNode>,
+    },
+    // `type A = T`
+    TypeAlias {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        right: Box<AstNode>,
+    },
+    // `opaque type A: Super = T`
+    OpaqueType {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        supertype: Option<Box<AstNode>>,
+        impltype: Box<AstNode>,
+    },
+    // `declare var x: T;`, where `kind` is "var", "let" or "const"
+    DeclareVariable {
+        id: Box<AstNode>,
+        kind: String,
+    },
+    // `declare function f(x: T): U;`; the function type annotates `id`
+    DeclareFunction {
+        id: Box<AstNode>,
+    },
+    // `declare class C<T> extends B<T> { ... }`, with an object type as body
+    DeclareClass {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        extends: Vec<AstNode>,
+        body: Box<AstNode>,
+    },
+    // `B<T>` in the `extends` clause of a declared class
+    InterfaceExtends {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+    },
+    DeclareTypeAlias {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        right: Box<AstNode>,
+    },
+    // `declare opaque type A: Super;`, which hides the underlying type
+    DeclareOpaqueType {
+        id: Box<AstNode>,
+        type_parameters: Option<Box<AstNode>>,
+        supertype: Option<Box<AstNode>>,
+    },
+    // `declare module 'm' { ... }`. `kind` is "ES" when the body uses
+    // `declare export` and "CommonJS" otherwise.
+    DeclareModule {
+        id: Box<AstNode>,
+        body: Box<AstNode>,
+        kind: String,
+    },
+    // `declare module.exports: T;`
+    DeclareModuleExports {
+        type_annotation: Box<AstNode>,
+    },
+    // `declare export ...`; `default` marks `declare export default`
+    DeclareExportDeclaration {
+        declaration: Option<Box<AstNode>>,
+        specifiers: Vec<AstNode>,
+        source: Option<Box<AstNode>>,
+        default: bool,
+    },
+    // `declare export * from 'm'`
+    DeclareExportAllDeclaration {
+        source: Box<AstNode>,
+    },
+    // `(x: T)`
+    TypeCastExpression {
+        expression: Box<AstNode>,
+        type_annotation: Box<AstNode>,
     },
     Program {
         body: Vec<AstNode>
+    },
+    // Placeholder for a statement that could not be parsed
+    Error
+}
+
+impl AstNode {
+    /// An `Identifier` without a type annotation.
+    pub fn identifier(name: String) -> AstNode {
+        AstNode::Identifier {
+            name,
+            type_annotation: None,
+            optional: false,
+        }
     }
 }
@@ -0,0 +1,368 @@
+use std::fmt::Write;
+
+use crate::lexer::ParseError::{ErrorCode, ParseError};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders parse errors as text with the offending source line, an
+/// underline below the error span and a hint where one helps, in the style
+/// of rustc:
+///
+/// ```text
+/// error[E0001]: Expect ')' after arguments.
+///  --> app.js:1:9
+///   |
+/// 1 | add(1, 2;
+///   |         ^ expected ')', found ';'
+///   |
+///   = help: insert ')' before this token
+/// ```
+pub struct DiagnosticRenderer<'a> {
+    file_name: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(file_name: &'a str, source: &'a str) -> Self {
+        Self {
+            file_name,
+            source,
+            color: false,
+        }
+    }
+
+    /// Enables ANSI colour codes in the output.
+    pub fn colored(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Renders every error, separated by blank lines.
+    pub fn render_all(&self, errors: &[ParseError]) -> String {
+        errors
+            .iter()
+            .map(|error| self.render(error))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn render(&self, error: &ParseError) -> String {
+        let start = error.span.start.min(self.source.len());
+        let line_start = self.source[..start]
+            .char_indices()
+            .rev()
+            .find(|&(_, ch)| is_line_terminator(ch))
+            .map_or(0, |(index, ch)| index + ch.len_utf8());
+        let line_end = self.source[start..]
+            .find(is_line_terminator)
+            .map_or(self.source.len(), |index| start + index);
+        let line_text = &self.source[line_start..line_end];
+        // A "\r\n" pair ends a single line.
+        let before = &self.source[..line_start];
+        let line_number =
+            before.matches(is_line_terminator).count() - before.matches("\r\n").count() + 1;
+        let column = self.source[line_start..start].chars().count() + 1;
+
+        // Spans running past the end of the line are underlined up to it.
+        let end = error.span.end.min(line_end).max(start);
+        let underline = "^".repeat(self.source[start..end].chars().count().max(1));
+
+        let gutter = " ".repeat(line_number.to_string().len());
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(RED, &format!("error[{}]", error.code)),
+            self.paint(BOLD, &error.message)
+        );
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            gutter,
+            self.paint(BLUE, "-->"),
+            self.file_name,
+            line_number,
+            column
+        );
+        let _ = writeln!(out, "{} {}", gutter, self.paint(BLUE, "|"));
+        let source_line = format!(
+            "{} {} {}",
+            self.paint(BLUE, &line_number.to_string()),
+            self.paint(BLUE, "|"),
+            line_text
+        );
+        let _ = writeln!(out, "{}", source_line.trim_end());
+        let _ = writeln!(
+            out,
+            "{} {} {}{} {}",
+            gutter,
+            self.paint(BLUE, "|"),
+            " ".repeat(column - 1),
+            self.paint(RED, &underline),
+            self.paint(
+                RED,
+                &format!(
+                    "expected {}, found {}",
+                    error.expected,
+                    error.found_description()
+                )
+            )
+        );
+        if let Some(help) = help(error) {
+            let _ = writeln!(out, "{} {}", gutter, self.paint(BLUE, "|"));
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                gutter,
+                self.paint(BLUE, "="),
+                self.paint(BOLD, &format!("help: {}", help))
+            );
+        }
+        out
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// The characters that end a line in JavaScript source.
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn help(error: &ParseError) -> Option<String> {
+    // Syntax that is well-formed but not allowed where it appears names the
+    // kind of construct that was expected, which there is no point in
+    // inserting, and its message already says what is wrong.
+    if error.code == ErrorCode::UnexpectedToken && !error.expected.starts_with('\'') {
+        return None;
+    }
+    let help = match error.code {
+        ErrorCode::UnexpectedToken => format!("insert {} before this token", error.expected),
+        ErrorCode::ExpectedIdentifier => {
+            "names start with a letter, '_' or '$' and cannot be keywords".to_string()
+        }
+        ErrorCode::ExpectedExpression => "insert an expression before this token".to_string(),
+        ErrorCode::MissingSemicolon => "separate statements with ';' or a line break".to_string(),
+        ErrorCode::InvalidCharacter => "remove this character".to_string(),
+        ErrorCode::MixedCoalesce => "wrap one side of the '??' in parentheses".to_string(),
+        ErrorCode::StrictDelete => {
+            "only properties with public names can be deleted in strict mode".to_string()
+        }
+        ErrorCode::InvalidUpdateTarget => {
+            "'++' and '--' need a variable or property to update".to_string()
+        }
+        ErrorCode::UnaryBeforeExponent => {
+            "wrap the unary expression in parentheses".to_string()
+        }
+        ErrorCode::InvalidOptionalChain => {
+            "wrap the optional chain in parentheses before calling 'new'".to_string()
+        }
+        ErrorCode::InvalidAccessorParameters => {
+            "getters take no parameters and setters take exactly one".to_string()
+        }
+        ErrorCode::StrictWith => "use a local variable instead of 'with'".to_string(),
+        ErrorCode::NewlineAfterThrow => {
+            "start the thrown expression on the same line as 'throw'".to_string()
+        }
+        ErrorCode::DuplicateDefault => "merge the 'default' clauses into one".to_string(),
+        ErrorCode::InvalidAssignmentTarget => {
+            "assign to a variable or property instead".to_string()
+        }
+        ErrorCode::MissingInitializer => "give the constant a value with '='".to_string(),
+        ErrorCode::LetBinding => "choose a different name than 'let'".to_string(),
+        ErrorCode::InvalidForDeclaration => {
+            "declare a single variable without an initialiser".to_string()
+        }
+        ErrorCode::ShorthandInitializer => {
+            "use ':' to give the property a value, or destructure with '='".to_string()
+        }
+        ErrorCode::NewlineBeforeArrow => {
+            "move '=>' to the line of the parameters".to_string()
+        }
+        ErrorCode::DuplicateConstructor => "merge the constructors into one".to_string(),
+        ErrorCode::InvalidSuper => {
+            "call 'super()' in derived class constructors and use 'super.x' in methods".to_string()
+        }
+        ErrorCode::UndeclaredPrivateName => {
+            "declare the private name in the enclosing class body".to_string()
+        }
+        ErrorCode::MisplacedImportExport => {
+            "move the declaration to the top level of the module".to_string()
+        }
+        ErrorCode::UnsupportedSyntax => {
+            "raise ecma_version or rewrite this with older syntax".to_string()
+        }
+        ErrorCode::Redeclaration | ErrorCode::DuplicateParameter => {
+            "rename one of the declarations".to_string()
+        }
+        ErrorCode::IllegalReturn => "move the statement into a function".to_string(),
+        ErrorCode::InvalidJump => {
+            "jump to a label of an enclosing statement, or move it into a loop".to_string()
+        }
+        ErrorCode::InvalidYieldOrAwait => "use a different name".to_string(),
+        ErrorCode::LegacyOctal => "remove the leading zero".to_string(),
+        ErrorCode::StrictEvalArguments => "use a different name".to_string(),
+        ErrorCode::FeatureNotEnabled => "enable the feature in ParseOptions".to_string(),
+        ErrorCode::MismatchedClosingTag => {
+            "rename the closing tag to match its opening tag".to_string()
+        }
+        ErrorCode::InvalidNumber => {
+            "use at most one '.' and separate digits with single '_' characters".to_string()
+        }
+        ErrorCode::DuplicateExport => "rename one of the exports with 'as'".to_string(),
+        ErrorCode::UndeclaredExport => {
+            "declare the binding, or re-export it with 'from'".to_string()
+        }
+        ErrorCode::ReservedMemberName => "rename the member".to_string(),
+        ErrorCode::IllegalNewTarget => "move the expression into a function".to_string(),
+    };
+    Some(help)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer;
+    use crate::lexer::ParseOptions::{DecoratorSyntax, ParseOptions};
+    use crate::lexer::Parser::Parser;
+
+    fn render(source: &str) -> String {
+        let (_, errors) = Parser::new(lexer::tokenize(source)).parse_recovering();
+        DiagnosticRenderer::new("app.js", source).render_all(&errors)
+    }
+
+    #[test]
+    fn test_render_missing_paren() {
+        assert_eq!(
+            render("add(1, 2;"),
+            "\
+error[E0001]: Expect ')' after arguments.
+ --> app.js:1:9
+  |
+1 | add(1, 2;
+  |         ^ expected ')', found ';'
+  |
+  = help: insert ')' before this token
+"
+        );
+    }
+
+    #[test]
+    fn test_render_multiple_errors_on_later_lines() {
+        let source = "var a = 1;\nvar b = 2;\nvar c = 3;\nvar d = 4;\nvar e = 5;\n\
+                      var f = 6;\nvar g = 7;\nvar h = 8;\nvar i = 9;\n  total = price *;\nvar 10;";
+        assert_eq!(
+            render(source),
+            "\
+error[E0003]: Expect expression.
+  --> app.js:10:18
+   |
+10 |   total = price *;
+   |                  ^ expected expression, found ';'
+   |
+   = help: insert an expression before this token
+
+error[E0002]: Expect variable name.
+  --> app.js:11:5
+   |
+11 | var 10;
+   |     ^^ expected identifier, found '10'
+   |
+   = help: names start with a letter, '_' or '$' and cannot be keywords
+"
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        assert_eq!(
+            render("if (x) {\n  y = 1;\n"),
+            "\
+error[E0001]: Expect '}' after block.
+ --> app.js:3:1
+  |
+3 |
+  | ^ expected '}', found end of input
+  |
+  = help: insert '}' before this token
+"
+        );
+    }
+
+    #[test]
+    fn test_render_other_line_terminators() {
+        assert_eq!(
+            render("foo(\r"),
+            "\
+error[E0003]: Expect expression.
+ --> app.js:2:1
+  |
+2 |
+  | ^ expected expression, found end of input
+  |
+  = help: insert an expression before this token
+"
+        );
+        for source in [
+            "a = 1;\rb = (;\r",
+            "a = 1;\r\nb = (;\r\n",
+            "a = 1;\u{2028}b = (;\u{2029}",
+        ] {
+            let output = render(source);
+            assert!(output.contains(" --> app.js:2:6\n"), "{}", output);
+            assert!(output.contains("\n2 | b = (;\n"), "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_render_misplaced_syntax_without_insert_help() {
+        let source = "class A { @d constructor() {} }";
+        let options = ParseOptions {
+            decorators: Some(DecoratorSyntax::Proposal2023),
+            ..ParseOptions::default()
+        };
+        let tokens = lexer::tokenize_with_options(source, &options);
+        let (_, errors) = Parser::with_options(tokens, options).parse_recovering();
+        assert_eq!(
+            DiagnosticRenderer::new("app.js", source).render_all(&errors),
+            "\
+error[E0001]: Decorators are not valid on constructors.
+ --> app.js:1:14
+  |
+1 | class A { @d constructor() {} }
+  |              ^^^^^^^^^^^ expected class member, found 'constructor'
+"
+        );
+    }
+
+    #[test]
+    fn test_render_with_color() {
+        let source = "a # b";
+        let (_, errors) = Parser::new(lexer::tokenize(source)).parse_recovering();
+        let output = DiagnosticRenderer::new("app.js", source)
+            .colored(true)
+            .render(&errors[0]);
+        assert_eq!(
+            output,
+            "\
+\x1b[1;31merror[E0005]\x1b[0m: \x1b[1mUnexpected character '#'.\x1b[0m
+ \x1b[1;34m-->\x1b[0m app.js:1:3
+  \x1b[1;34m|\x1b[0m
+\x1b[1;34m1\x1b[0m \x1b[1;34m|\x1b[0m a # b
+  \x1b[1;34m|\x1b[0m   \x1b[1;31m^\x1b[0m \x1b[1;31mexpected ';', found '#'\x1b[0m
+  \x1b[1;34m|\x1b[0m
+  \x1b[1;34m=\x1b[0m \x1b[1mhelp: remove this character\x1b[0m
+"
+        );
+    }
+}
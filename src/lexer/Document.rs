@@ -0,0 +1,592 @@
+use std::ops::Range;
+
+use crate::lexer::AstNode::AstNode;
+use crate::lexer::EarlyErrors;
+use crate::lexer::Lexer::{Lexer, LexerState};
+use crate::lexer::ParseError::ParseError;
+use crate::lexer::ParseOptions::ParseOptions;
+use crate::lexer::Parser::Parser;
+use crate::lexer::Token::{SpannedToken, Token};
+
+/// Replacement of the byte range `range` of the current source with `text`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// The part of a document that had to be redone after an edit, given as
+/// index ranges into the updated token and statement lists.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reparse {
+    pub tokens: Range<usize>,
+    pub statements: Range<usize>,
+}
+
+/// A parsed source text that can be updated in place after edits.
+///
+/// An edit re-lexes tokens from the one preceding the edit until the new
+/// token stream lines up with the old one again, then re-parses top-level
+/// statements from the one preceding the changed tokens until a statement
+/// boundary lines up again. Everything outside those windows, including the
+/// `AstNode`s of untouched statements and their syntax errors, is reused.
+/// Early errors need the whole program, so they are checked again after
+/// every edit that leaves no syntax errors.
+pub struct Document {
+    source: String,
+    options: ParseOptions,
+    tokens: Vec<SpannedToken>,
+    // The lexer state after each token, which re-lexing resumes in.
+    states: Vec<LexerState>,
+    // Errors reported while lexing, in source order.
+    lexer_errors: Vec<ParseError>,
+    // Whether a comment before the first token holds the `@flow` pragma.
+    flow_pragma: bool,
+    body: Vec<AstNode>,
+    // Token index at which each statement of `body` starts, and the errors
+    // reported while parsing it.
+    statement_starts: Vec<usize>,
+    statement_errors: Vec<Vec<ParseError>>,
+    early_errors: Vec<ParseError>,
+}
+
+impl Document {
+    pub fn new(source: &str) -> Self {
+        Self::with_options(source, &ParseOptions::default())
+    }
+
+    /// Creates a document that is lexed and parsed for the goal symbol and
+    /// syntax given by `options`, on every edit as well.
+    pub fn with_options(source: &str, options: &ParseOptions) -> Self {
+        let mut lexer = Lexer::with_options(source, options);
+        let (tokens, states) = lexer.tokenize_with_states();
+        let mut document = Document {
+            source: source.to_string(),
+            options: options.clone(),
+            tokens,
+            states,
+            lexer_errors: lexer.take_errors(),
+            flow_pragma: lexer.flow_pragma(),
+            body: Vec::new(),
+            statement_starts: Vec::new(),
+            statement_errors: Vec::new(),
+            early_errors: Vec::new(),
+        };
+        document.reparse(0, 0, 0);
+        document.validate();
+        document
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    pub fn tokens(&self) -> &[SpannedToken] {
+        &self.tokens
+    }
+
+    pub fn body(&self) -> &[AstNode] {
+        &self.body
+    }
+
+    /// The possibly partial tree, as returned by `Parser::parse_recovering`.
+    pub fn program(&self) -> AstNode {
+        AstNode::Program {
+            body: self.body.clone(),
+        }
+    }
+
+    /// The errors of the lexer, the parser and the early error checks, in
+    /// source order, as reported by `lexer::parse`.
+    pub fn errors(&self) -> Vec<ParseError> {
+        let mut errors = self.lexer_errors.clone();
+        errors.extend(self.statement_errors.iter().flatten().cloned());
+        errors.extend(self.early_errors.iter().cloned());
+        errors.sort_by_key(|error| error.span.start);
+        errors
+    }
+
+    /// Applies `edit` and updates tokens and statements incrementally.
+    pub fn edit(&mut self, edit: &TextEdit) -> Reparse {
+        let delta = edit.text.len() as isize - edit.range.len() as isize;
+        let flow_pragma = self.flow_pragma;
+        let (first, old_end, relexed) = self.relex(edit);
+        let statements = if self.flow_pragma == flow_pragma {
+            self.reparse(first, old_end, relexed)
+        } else {
+            // Adding or removing the pragma changes how every statement parses.
+            self.body.clear();
+            self.statement_starts.clear();
+            self.statement_errors.clear();
+            self.reparse(0, 0, 0)
+        };
+        for errors in &mut self.statement_errors[statements.end..] {
+            for error in errors {
+                error.span.start = (error.span.start as isize + delta) as usize;
+                error.span.end = (error.span.end as isize + delta) as usize;
+            }
+        }
+        self.validate();
+        Reparse {
+            tokens: first..first + relexed,
+            statements,
+        }
+    }
+
+    /// Replaces the tokens affected by `edit`. Returns the index of the first
+    /// replaced token, the end of the replaced range in the old token list and
+    /// the number of tokens that replaced it.
+    fn relex(&mut self, edit: &TextEdit) -> (usize, usize, usize) {
+        let Range { start, end } = edit.range;
+        let inserted_end = start + edit.text.len();
+        let delta = edit.text.len() as isize - (end - start) as isize;
+        self.source.replace_range(start..end, &edit.text);
+
+        // The token before the edit is re-lexed too, as the edit may extend it.
+        // Lexing resumes in the state after the token before that, so that
+        // templates and JSX are lexed as they were.
+        let first = self.tokens.partition_point(|token| token.span.end < start);
+        let (resume_at, state) = match first.checked_sub(1) {
+            Some(previous) => (self.tokens[previous].span.end, self.states[previous].clone()),
+            None => (0, LexerState::default()),
+        };
+        let mut lexer = Lexer::resuming(&self.source, resume_at, &self.options, state);
+
+        let mut relexed = Vec::new();
+        let mut relexed_states = Vec::new();
+        let mut lexer_errors = Vec::new();
+        let old_end = loop {
+            let token = lexer.next_spanned_token();
+            if token.span.start >= inserted_end {
+                // Past the edit the source is unchanged, so once a token lines
+                // up with an old one and leaves the lexer in the same state, the
+                // rest of the old stream is still valid.
+                let old_start = (token.span.start as isize - delta) as usize;
+                let index = self
+                    .tokens
+                    .partition_point(|old| old.span.start < old_start);
+                if let Some(old) = self.tokens.get(index) {
+                    if old.span.start == old_start
+                        && old.span.len() == token.span.len()
+                        && old.newline_before == token.newline_before
+                        && old.token == token.token
+                        && self.states[index] == lexer.state()
+                    {
+                        break index;
+                    }
+                }
+            }
+            // Errors of the token that lined up are already known.
+            lexer_errors.extend(lexer.take_errors());
+            let at_end = token.token == Token::EOF;
+            relexed.push(token);
+            relexed_states.push(lexer.state());
+            if at_end {
+                break self.tokens.len();
+            }
+        };
+
+        // Replace the lexer errors between the end of the token before the
+        // edit and the first reused token, and shift the ones after it.
+        let old_resume_end = self
+            .tokens
+            .get(old_end)
+            .map_or(usize::MAX, |token| token.span.start);
+        let replaced_from = self
+            .lexer_errors
+            .partition_point(|error| error.span.start < resume_at);
+        let replaced_to = self
+            .lexer_errors
+            .partition_point(|error| error.span.start < old_resume_end);
+        for error in &mut self.lexer_errors[replaced_to..] {
+            error.span.start = (error.span.start as isize + delta) as usize;
+            error.span.end = (error.span.end as isize + delta) as usize;
+        }
+        self.lexer_errors.splice(replaced_from..replaced_to, lexer_errors);
+
+        for token in &mut self.tokens[old_end..] {
+            token.span.start = (token.span.start as isize + delta) as usize;
+            token.span.end = (token.span.end as isize + delta) as usize;
+        }
+        // Only an edit before the first token can change the pragma.
+        if first == 0 {
+            self.flow_pragma = lexer.flow_pragma();
+        }
+        let count = relexed.len();
+        self.tokens.splice(first..old_end, relexed);
+        self.states.splice(first..old_end, relexed_states);
+        (first, old_end, count)
+    }
+
+    /// Re-parses the statements affected by replacing old tokens
+    /// `first..old_end` with `relexed` new ones, returning the range of
+    /// statements that were parsed again.
+    fn reparse(&mut self, first: usize, old_end: usize, relexed: usize) -> Range<usize> {
+        let token_delta = relexed as isize - (old_end - first) as isize;
+        let changed_end = first + relexed;
+
+        // A statement may end with the token before the change and still have
+        // looked at the changed token to decide where it ends.
+        let anchor = first.saturating_sub(1);
+        let first_statement = self
+            .statement_starts
+            .partition_point(|&start| start <= anchor)
+            .saturating_sub(1);
+        let start = self
+            .statement_starts
+            .get(first_statement)
+            .copied()
+            .unwrap_or(0);
+
+        let mut parser = Parser::with_source(
+            std::mem::take(&mut self.source),
+            std::mem::take(&mut self.tokens),
+            std::mem::take(&mut self.states),
+            std::mem::take(&mut self.lexer_errors),
+            self.parser_options(),
+        );
+        parser.seek(start);
+        parser.resume_after(&self.body[..first_statement]);
+        let mut body = Vec::new();
+        let mut starts = Vec::new();
+        let mut errors = Vec::new();
+        let reused_from = loop {
+            let position = parser.position();
+            // Tokens the parser lexed again as JSX count as changed too.
+            let changed_end = ((changed_end as isize + parser.inserted_tokens()) as usize)
+                .max(parser.edited_end());
+            if position >= changed_end {
+                let token_delta = token_delta + parser.inserted_tokens();
+                let old_position = (position as isize - token_delta) as usize;
+                if let Ok(index) = self.statement_starts.binary_search(&old_position) {
+                    // A changed directive prologue changes the strictness of
+                    // everything after it.
+                    if index >= first_statement && parser.continues_after(&self.body[..index]) {
+                        break index;
+                    }
+                }
+            }
+            match parser.parse_statement() {
+                Some(statement) => {
+                    body.push(statement);
+                    starts.push(position);
+                    errors.push(parser.take_errors());
+                }
+                None => break self.statement_starts.len(),
+            }
+        };
+        let token_delta = token_delta + parser.inserted_tokens();
+        (self.source, self.tokens, self.states, self.lexer_errors) = parser.into_parts();
+
+        let parsed = body.len();
+        self.body.splice(first_statement..reused_from, body);
+        self.statement_errors.splice(first_statement..reused_from, errors);
+        let reused: Vec<usize> = self.statement_starts[reused_from..]
+            .iter()
+            .map(|&start| (start as isize + token_delta) as usize)
+            .collect();
+        self.statement_starts.truncate(first_statement);
+        self.statement_starts.extend(starts);
+        self.statement_starts.extend(reused);
+        first_statement..first_statement + parsed
+    }
+
+    /// The options the parser runs with, which turn Flow on when the source
+    /// has the `@flow` pragma, as `lexer::parse` does.
+    fn parser_options(&self) -> ParseOptions {
+        let mut options = self.options.clone();
+        options.flow |= self.flow_pragma && !options.typescript;
+        options
+    }
+
+    fn validate(&mut self) {
+        self.early_errors.clear();
+        if self.statement_errors.iter().all(Vec::is_empty) {
+            self.early_errors = EarlyErrors::validate_spanned(
+                &self.program(),
+                &self.parser_options(),
+                &self.tokens,
+                &self.statement_starts,
+                self.tokens.len() - 1,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::ParseError::ErrorCode;
+    use crate::lexer::ParseOptions::SourceType;
+
+    fn replace(source: &str, pattern: &str, text: &str) -> TextEdit {
+        let start = source.find(pattern).unwrap();
+        TextEdit {
+            range: start..start + pattern.len(),
+            text: text.to_string(),
+        }
+    }
+
+    fn assert_full_reparse_equivalent(document: &Document) {
+        let mut options = document.options().clone();
+        let mut lexer = Lexer::with_options(document.source(), &options);
+        let (tokens, states) = lexer.tokenize_with_states();
+        let lexer_errors = lexer.take_errors();
+        options.flow |= lexer.flow_pragma() && !options.typescript;
+        let source = document.source().to_string();
+        let mut parser = Parser::with_source(source, tokens, states, lexer_errors, options);
+        let (program, _) = parser.parse_recovering();
+        let (_, tokens, _, _) = parser.into_parts();
+        assert_eq!(document.tokens(), tokens.as_slice());
+        let errors = crate::lexer::parse(document.source(), document.options())
+            .err()
+            .unwrap_or_default();
+        assert_eq!((document.program(), document.errors()), (program, errors));
+    }
+
+    #[test]
+    fn test_edit_inside_statement_reuses_neighbours() {
+        let source = "var a = 1;\nvar b = 2;\nvar c = 3;\nvar d = 4;\nvar e = 5;";
+        let mut document = Document::new(source);
+        let untouched = document.body()[4].clone();
+
+        let reparse = document.edit(&replace(source, "3", "x + 30"));
+
+        assert_eq!(document.source(), "var a = 1;\nvar b = 2;\nvar c = x + 30;\nvar d = 4;\nvar e = 5;");
+        assert_eq!(reparse.tokens, 13..16);
+        assert_eq!(reparse.statements, 2..3);
+        assert_eq!(document.body()[4], untouched);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_merging_tokens() {
+        let mut document = Document::new("foo = a + b;");
+        document.edit(&TextEdit {
+            range: 7..10,
+            text: String::new(),
+        });
+        assert_eq!(document.source(), "foo = ab;");
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "=", "=="));
+        assert_eq!(document.source(), "foo == ab;");
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_depending_on_semicolon_insertion() {
+        // Removing the newline joins the statements into a single call.
+        let source = "var a = b\n(c)\nd = 1";
+        let mut document = Document::new("var a = b;\n(c)\nd = 1");
+        assert_eq!(document.body().len(), 3);
+        document.edit(&replace("var a = b;\n(c)\nd = 1", ";", ""));
+        assert_eq!(document.source(), source);
+        assert_eq!(document.body().len(), 2);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_opening_comment() {
+        let source = "a = 1;\nb = 2;\nc = 3;\nd = 4;";
+        let mut document = Document::new(source);
+        document.edit(&replace(source, "b", "/* b"));
+        document.edit(&replace(document.source(), "d", "*/ d"));
+        assert_eq!(document.source(), "a = 1;\n/* b = 2;\nc = 3;\n*/ d = 4;");
+        assert_eq!(document.body().len(), 2);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_sequence_matches_full_reparse() {
+        let mut document = Document::new("function f(a) {\n  return a;\n}\nf(1);\n");
+        let edits = [
+            ("return a;", "if (a) return a * 2;\n  return 0;"),
+            ("f(1);", "var r = f(1) + f(2);"),
+            ("(a)", "(a, b)"),
+            ("\n}", "\n  b = 1;\n}"),
+            ("function", "var g = 1;\nfunction"),
+            ("var g = 1;\n", ""),
+            ("+ f(2)", ""),
+        ];
+        for (pattern, text) in edits.iter() {
+            let edit = replace(document.source(), pattern, text);
+            document.edit(&edit);
+            assert_full_reparse_equivalent(&document);
+        }
+        assert_eq!(
+            document.source(),
+            "function f(a, b) {\n  if (a) return a * 2;\n  return 0;\n  b = 1;\n}\nvar r = f(1) ;\n"
+        );
+    }
+
+    #[test]
+    fn test_edit_at_boundaries() {
+        let mut document = Document::new("a;\nb;");
+        document.edit(&TextEdit {
+            range: 0..0,
+            text: "z;\n".to_string(),
+        });
+        assert_full_reparse_equivalent(&document);
+        let end = document.source().len();
+        document.edit(&TextEdit {
+            range: end..end,
+            text: "\nc;".to_string(),
+        });
+        assert_full_reparse_equivalent(&document);
+        let end = document.source().len();
+        document.edit(&TextEdit {
+            range: 0..end,
+            text: String::new(),
+        });
+        assert!(document.body().is_empty());
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_inside_template_substitution() {
+        let source = "x = `a${b + c}d`; y;";
+        let mut document = Document::new(source);
+        document.edit(&replace(source, "c", "cc"));
+        assert_eq!(document.tokens()[6].token, Token::TemplateTail("d".to_string()));
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_inside_jsx() {
+        let options = ParseOptions {
+            jsx: true,
+            ..ParseOptions::default()
+        };
+        let source = "x = <a>hello world</a>; y;";
+        let mut document = Document::with_options(source, &options);
+        document.edit(&replace(source, "world", "there"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "<a>", "<a b={[c]}>"));
+        document.edit(&replace(document.source(), "c", "{ d: c }"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_around_jsx_after_parenthesis() {
+        let options = ParseOptions {
+            jsx: true,
+            ..ParseOptions::default()
+        };
+        let source = "a;\nif (x) <div>it's</div>;\ny = 'b';";
+        let mut document = Document::with_options(source, &options);
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(source, "a;", "aa;"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "it's", "that's"));
+        document.edit(&replace(document.source(), "(x)", "(x) y"));
+        assert_full_reparse_equivalent(&document);
+        document.edit(&replace(document.source(), "(x) y", "(x)"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_introducing_and_fixing_error() {
+        let source = "a = 1;\nb = 2;\nc = 3;";
+        let mut document = Document::new(source);
+        document.edit(&replace(source, "2", "("));
+        assert_eq!(document.body()[1], AstNode::Error);
+        assert_eq!(document.body().len(), 3);
+        assert_eq!(document.errors().len(), 1);
+        assert_full_reparse_equivalent(&document);
+
+        // Errors after the edit are kept, shifted to their new position.
+        let reparse = document.edit(&replace(document.source(), "1", "100"));
+        assert_eq!(reparse.statements, 0..1);
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "(", "(2)"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_after_directive_prologue() {
+        let source = "'use strict';\n'b';\nc;\n'd';";
+        let mut document = Document::new(source);
+        document.edit(&replace(source, "'b'", "'e'"));
+        assert_full_reparse_equivalent(&document);
+        document.edit(&replace(document.source(), "'d'", "with (x) {}"));
+        assert_eq!(document.errors().len(), 1);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_changing_strictness() {
+        let source = "'use strict';\na;\nwith (x) {}";
+        let mut document = Document::new(source);
+        assert_eq!(document.errors()[0].code, ErrorCode::StrictWith);
+
+        document.edit(&replace(source, "strict", "sloppy"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "sloppy", "strict"));
+        assert_eq!(document.errors()[0].code, ErrorCode::StrictWith);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_reports_early_errors_and_flow_pragma() {
+        let source = "let a;\nlet b;\nx = 1;";
+        let mut document = Document::new(source);
+        document.edit(&replace(source, "b", "a"));
+        assert_eq!(document.errors().len(), 1);
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "x = 1", "type T = number"));
+        assert!(!document.errors().is_empty());
+        document.edit(&TextEdit {
+            range: 0..0,
+            text: "// @flow\n".to_string(),
+        });
+        assert_eq!(document.errors().len(), 1);
+        assert_full_reparse_equivalent(&document);
+    }
+
+    #[test]
+    fn test_edit_with_options() {
+        let options = ParseOptions {
+            source_type: SourceType::Module,
+            ecma_version: 2020,
+            ..ParseOptions::default()
+        };
+        let source = "import a from 'm';\nawait a;\nlet n = 1_000;\nb = 2;";
+        let mut document = Document::with_options(source, &options);
+        // Only the numeric separator is an error in an ES2020 module.
+        assert_eq!(document.errors().len(), 1);
+        assert_eq!(document.errors()[0].code, ErrorCode::UnsupportedSyntax);
+        assert_full_reparse_equivalent(&document);
+
+        // Errors after the edit keep up with the text.
+        document.edit(&replace(source, "'m'", "'mod'"));
+        assert_eq!(document.errors()[0].span.start, source.find("1_000").unwrap() + 2);
+        assert_full_reparse_equivalent(&document);
+
+        document.edit(&replace(document.source(), "1_000", "1000"));
+        assert!(document.errors().is_empty());
+        assert_full_reparse_equivalent(&document);
+
+        // Module code is strict.
+        document.edit(&replace(document.source(), "b = 2;", "with (b) {}"));
+        assert_eq!(document.errors()[0].code, ErrorCode::StrictWith);
+        assert_full_reparse_equivalent(&document);
+    }
+}
@@ -0,0 +1,1434 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::lexer::AstNode::AstNode;
+use crate::lexer::ParseError::{ErrorCode, ParseError};
+use crate::lexer::ParseOptions::{ParseOptions, SourceType};
+use crate::lexer::Token::{Span, SpannedToken};
+
+/// A violation of the static semantics found by `validate`. The tree keeps
+/// no source positions, so messages name the binding or label involved and
+/// `statement` is the index in the program body of the top-level statement
+/// the error was found in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EarlyError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub statement: usize,
+}
+
+impl fmt::Display for EarlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// Checks a parsed program for the early errors that need more context than
+/// the parser keeps: redeclarations, duplicate parameters, misplaced
+/// `return`, `break`, `continue` and `new.target`, misused `yield` and
+/// `await`, `eval` or `arguments` bound or assigned in strict mode code, and
+/// in modules, duplicate exports and exports of undeclared names.
+pub fn validate(program: &AstNode, options: &ParseOptions) -> Vec<EarlyError> {
+    let module = options.source_type == SourceType::Module;
+    let mut validator = Validator {
+        errors: Vec::new(),
+        top_level_index: 0,
+        exported: HashSet::new(),
+        local_exports: Vec::new(),
+        scopes: Vec::new(),
+        strict: module,
+        module,
+        in_function: false,
+        in_async: false,
+        in_generator: false,
+        in_params: false,
+        in_class_element: false,
+        new_target: false,
+        labels: Vec::new(),
+        loop_depth: 0,
+        breakable_depth: 0,
+    };
+    if let AstNode::Program { body } = program {
+        validator.strict |= has_use_strict(body);
+        validator.scopes.push(Scope::function());
+        for (index, statement) in body.iter().enumerate() {
+            validator.top_level_index = index;
+            validator.statement(statement);
+        }
+        validator.check_local_exports();
+    }
+    validator.errors
+}
+
+/// Runs `validate` and reports each error with the span of the top-level
+/// statement it was found in, where `starts` holds the index in `tokens` at
+/// which each statement starts and `end` the index after the last one.
+pub(crate) fn validate_spanned(
+    program: &AstNode,
+    options: &ParseOptions,
+    tokens: &[SpannedToken],
+    starts: &[usize],
+    end: usize,
+) -> Vec<ParseError> {
+    validate(program, options)
+        .into_iter()
+        .map(|error| {
+            let first = &tokens[starts[error.statement]];
+            let last_index = starts.get(error.statement + 1).copied().unwrap_or(end);
+            let last = &tokens[last_index - 1];
+            ParseError {
+                code: error.code,
+                span: Span::new(first.span.start, last.span.end),
+                expected: "valid statement".to_string(),
+                found: first.token.clone(),
+                message: error.message,
+            }
+        })
+        .collect()
+}
+
+/// Names declared directly in a block, function body or program.
+#[derive(Default)]
+struct Scope {
+    lexical: HashSet<String>,
+    // Block-level function declarations, which sloppy mode code may repeat.
+    functions: HashSet<String>,
+    // `var` names declared here or in a nested block.
+    var: HashSet<String>,
+    // Parameters or the catch binding, which `let` may not redeclare.
+    params: HashSet<String>,
+    // TypeScript and Flow declarations such as interfaces, which only
+    // `export` looks up.
+    types: HashSet<String>,
+    // Whether `var` declarations stop here.
+    function: bool,
+}
+
+impl Scope {
+    fn function() -> Scope {
+        Scope {
+            function: true,
+            ..Scope::default()
+        }
+    }
+}
+
+struct Label {
+    name: String,
+    is_loop: bool,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum FunctionKind {
+    Normal,
+    Arrow,
+    Method,
+}
+
+struct Validator {
+    errors: Vec<EarlyError>,
+    // The top-level statement being visited.
+    top_level_index: usize,
+    // Names exported by the module so far, and the local names that
+    // `export { a }` refers to with the statement that exports them.
+    exported: HashSet<String>,
+    local_exports: Vec<(String, usize)>,
+    scopes: Vec<Scope>,
+    strict: bool,
+    module: bool,
+    in_function: bool,
+    in_async: bool,
+    in_generator: bool,
+    // Set while visiting parameter defaults, where `yield` and `await`
+    // expressions are not allowed.
+    in_params: bool,
+    // Set in field initialisers and static blocks, outside any function
+    // other than arrow functions, where `arguments` may not be used.
+    in_class_element: bool,
+    // Whether `new.target` may be used: in functions other than arrow
+    // functions, in class elements and in arrow functions inside them.
+    new_target: bool,
+    // Labels of the statements enclosing the current one, and the number of
+    // loops and switches around it, within the current function.
+    labels: Vec<Label>,
+    loop_depth: usize,
+    breakable_depth: usize,
+}
+
+impl Validator {
+    fn report(&mut self, code: ErrorCode, message: String) {
+        self.errors.push(EarlyError {
+            code,
+            message,
+            statement: self.top_level_index,
+        });
+    }
+
+    fn statements(&mut self, statements: &[AstNode]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &AstNode) {
+        match statement {
+            AstNode::ExpressionStatement { expression, .. } => self.expression(expression),
+            AstNode::VariableDeclaration {
+                kind, declarations, ..
+            } => {
+                for declarator in declarations {
+                    if let AstNode::VariableDeclarator { id, init, .. } = declarator {
+                        for name in self.binding(id) {
+                            self.declare(&name, kind);
+                        }
+                        if let Some(init) = init {
+                            self.expression(init);
+                        }
+                    }
+                }
+            }
+            AstNode::FunctionDeclaration {
+                id,
+                params,
+                body,
+                generator,
+                r#async,
+                ..
+            } => {
+                if let AstNode::Identifier { name, .. } = &**id {
+                    // Functions are var-scoped at the top of a function body.
+                    let kind = match self.scopes.last() {
+                        Some(scope) if scope.function => "var",
+                        _ => "function",
+                    };
+                    self.declare(name, kind);
+                }
+                self.function(
+                    Some(id),
+                    params,
+                    body,
+                    *r#async,
+                    *generator,
+                    FunctionKind::Normal,
+                );
+            }
+            AstNode::ClassDeclaration {
+                id,
+                super_class,
+                body,
+                decorators,
+                ..
+            } => {
+                if let AstNode::Identifier { name, .. } = &**id {
+                    self.declare(name, "class");
+                }
+                self.class(Some(id), super_class.as_deref(), body, decorators);
+            }
+            AstNode::BlockStatement { body } => {
+                self.scopes.push(Scope::default());
+                self.statements(body);
+                self.scopes.pop();
+            }
+            AstNode::IfStatement {
+                test,
+                consequent,
+                alternate,
+            } => {
+                self.expression(test);
+                self.statement(consequent);
+                if let Some(alternate) = alternate {
+                    self.statement(alternate);
+                }
+            }
+            AstNode::ReturnStatement { argument } => {
+                if !self.in_function {
+                    self.report(
+                        ErrorCode::IllegalReturn,
+                        "'return' outside of function.".to_string(),
+                    );
+                }
+                if let Some(argument) = argument {
+                    self.expression(argument);
+                }
+            }
+            AstNode::WhileStatement { test, body } | AstNode::DoWhileStatement { body, test } => {
+                self.expression(test);
+                self.loop_body(body);
+            }
+            AstNode::ForStatement {
+                init,
+                test,
+                update,
+                body,
+            } => {
+                self.scopes.push(Scope::default());
+                if let Some(init) = init {
+                    match &**init {
+                        AstNode::VariableDeclaration { .. } => self.statement(init),
+                        init => self.expression(init),
+                    }
+                }
+                for expression in [test, update].into_iter().flatten() {
+                    self.expression(expression);
+                }
+                self.loop_body(body);
+                self.scopes.pop();
+            }
+            AstNode::ForInStatement { left, right, body }
+            | AstNode::ForOfStatement {
+                left, right, body, ..
+            } => {
+                self.scopes.push(Scope::default());
+                match &**left {
+                    AstNode::VariableDeclaration { .. } => self.statement(left),
+                    left => self.assignment_target(left),
+                }
+                self.expression(right);
+                self.loop_body(body);
+                self.scopes.pop();
+            }
+            AstNode::SwitchStatement {
+                discriminant,
+                cases,
+            } => {
+                self.expression(discriminant);
+                self.breakable_depth += 1;
+                self.scopes.push(Scope::default());
+                for case in cases {
+                    if let AstNode::SwitchCase { test, consequent } = case {
+                        if let Some(test) = test {
+                            self.expression(test);
+                        }
+                        self.statements(consequent);
+                    }
+                }
+                self.scopes.pop();
+                self.breakable_depth -= 1;
+            }
+            AstNode::TryStatement {
+                block,
+                handler,
+                finalizer,
+            } => {
+                self.statement(block);
+                if let Some(handler) = handler {
+                    if let AstNode::CatchClause { param, body } = &**handler {
+                        let mut scope = Scope::default();
+                        if let Some(param) = param {
+                            scope.params = self.binding(param).into_iter().collect();
+                        }
+                        // The catch body shares the scope of its binding.
+                        self.scopes.push(scope);
+                        if let AstNode::BlockStatement { body } = &**body {
+                            self.statements(body);
+                        }
+                        self.scopes.pop();
+                    }
+                }
+                if let Some(finalizer) = finalizer {
+                    self.statement(finalizer);
+                }
+            }
+            AstNode::ThrowStatement { argument } => self.expression(argument),
+            AstNode::LabeledStatement { label, body } => {
+                let AstNode::Identifier { name, .. } = &**label else {
+                    return;
+                };
+                self.reference(name);
+                if self.labels.iter().any(|label| &label.name == name) {
+                    self.report(
+                        ErrorCode::Redeclaration,
+                        format!("Label '{}' has already been declared.", name),
+                    );
+                }
+                let mut labeled = &**body;
+                while let AstNode::LabeledStatement { body, .. } = labeled {
+                    labeled = body;
+                }
+                self.labels.push(Label {
+                    name: name.clone(),
+                    is_loop: is_loop(labeled),
+                });
+                self.statement(body);
+                self.labels.pop();
+            }
+            AstNode::BreakStatement { label } => match label.as_deref() {
+                Some(AstNode::Identifier { name, .. })
+                    if !self.labels.iter().any(|label| &label.name == name) =>
+                {
+                    self.report(
+                        ErrorCode::InvalidJump,
+                        format!("Undefined label '{}'.", name),
+                    );
+                }
+                Some(_) => {}
+                None if self.breakable_depth == 0 => self.report(
+                    ErrorCode::InvalidJump,
+                    "'break' outside of a loop or switch.".to_string(),
+                ),
+                _ => {}
+            },
+            AstNode::ContinueStatement { label } => match label.as_deref() {
+                Some(AstNode::Identifier { name, .. }) => {
+                    match self.labels.iter().find(|label| &label.name == name) {
+                        Some(label) if label.is_loop => {}
+                        Some(_) => self.report(
+                            ErrorCode::InvalidJump,
+                            format!("Label '{}' does not denote a loop.", name),
+                        ),
+                        None => self.report(
+                            ErrorCode::InvalidJump,
+                            format!("Undefined label '{}'.", name),
+                        ),
+                    }
+                }
+                _ if self.loop_depth == 0 => self.report(
+                    ErrorCode::InvalidJump,
+                    "'continue' outside of a loop.".to_string(),
+                ),
+                _ => {}
+            },
+            AstNode::WithStatement { object, body } => {
+                self.expression(object);
+                self.statement(body);
+            }
+            AstNode::ImportDeclaration { specifiers, .. } => {
+                for specifier in specifiers {
+                    if let AstNode::ImportSpecifier { local, .. }
+                    | AstNode::ImportDefaultSpecifier { local }
+                    | AstNode::ImportNamespaceSpecifier { local } = specifier
+                    {
+                        for name in self.binding(local) {
+                            self.declare(&name, "import");
+                        }
+                    }
+                }
+            }
+            AstNode::ExportNamedDeclaration {
+                declaration,
+                specifiers,
+                source,
+                export_kind,
+                ..
+            } => {
+                // Type exports may merge with other declarations of the name.
+                let values = export_kind != "type";
+                if let Some(declaration) = declaration {
+                    self.statement(declaration);
+                    if values {
+                        for name in declared_names(declaration) {
+                            self.export(name);
+                        }
+                    }
+                }
+                for specifier in specifiers {
+                    if let AstNode::ExportSpecifier {
+                        local,
+                        exported,
+                        export_kind,
+                    } = specifier
+                    {
+                        if let (None, AstNode::Identifier { name: local, .. }) = (source, &**local)
+                        {
+                            self.local_exports
+                                .push((local.clone(), self.top_level_index));
+                        }
+                        if values && export_kind != "type" {
+                            self.export(module_export_name(exported));
+                        }
+                    }
+                }
+            }
+            AstNode::ExportDefaultDeclaration { declaration } => {
+                match &**declaration {
+                    AstNode::FunctionDeclaration { .. } | AstNode::ClassDeclaration { .. } => {
+                        self.statement(declaration)
+                    }
+                    expression => self.expression(expression),
+                }
+                self.export("default".to_string());
+            }
+            AstNode::ExportAllDeclaration {
+                exported: Some(exported),
+                ..
+            } => self.export(module_export_name(exported)),
+            AstNode::TSInterfaceDeclaration { id, .. }
+            | AstNode::TSTypeAliasDeclaration { id, .. }
+            | AstNode::TSEnumDeclaration { id, .. }
+            | AstNode::TSModuleDeclaration { id, .. }
+            | AstNode::TSDeclareFunction { id, .. }
+            | AstNode::TSImportEqualsDeclaration { id, .. }
+            | AstNode::TypeAlias { id, .. }
+            | AstNode::OpaqueType { id, .. }
+            | AstNode::DeclareVariable { id, .. }
+            | AstNode::DeclareFunction { id }
+            | AstNode::DeclareClass { id, .. }
+            | AstNode::DeclareTypeAlias { id, .. }
+            | AstNode::DeclareOpaqueType { id, .. } => {
+                if let (AstNode::Identifier { name, .. }, Some(scope)) =
+                    (&**id, self.scopes.last_mut())
+                {
+                    scope.types.insert(name.clone());
+                }
+            }
+            AstNode::DeclareExportDeclaration {
+                declaration: Some(declaration),
+                ..
+            } => self.statement(declaration),
+            _ => {}
+        }
+    }
+
+    /// Records a name the module exports, which must be unique.
+    fn export(&mut self, name: String) {
+        if !self.exported.insert(name.clone()) {
+            self.report(
+                ErrorCode::DuplicateExport,
+                format!("Duplicate export of '{}'.", name),
+            );
+        }
+    }
+
+    /// Checks that every name exported with `export { a }` is declared at
+    /// the top level of the module.
+    fn check_local_exports(&mut self) {
+        let local_exports = std::mem::take(&mut self.local_exports);
+        for (name, statement) in local_exports {
+            let declared = self.scopes.first().is_some_and(|scope| {
+                scope.lexical.contains(&name)
+                    || scope.var.contains(&name)
+                    || scope.types.contains(&name)
+            });
+            if !declared {
+                self.top_level_index = statement;
+                self.report(
+                    ErrorCode::UndeclaredExport,
+                    format!("Export '{}' is not defined.", name),
+                );
+            }
+        }
+    }
+
+    fn loop_body(&mut self, body: &AstNode) {
+        self.loop_depth += 1;
+        self.breakable_depth += 1;
+        self.statement(body);
+        self.loop_depth -= 1;
+        self.breakable_depth -= 1;
+    }
+
+    /// Declares `name` in the current scope. `kind` is "var" for names that
+    /// are hoisted to the enclosing function, and the declaring keyword for
+    /// block-scoped ones.
+    fn declare(&mut self, name: &str, kind: &str) {
+        let strict = self.strict;
+        let redeclared = if kind == "var" {
+            let mut redeclared = false;
+            for scope in self.scopes.iter_mut().rev() {
+                redeclared |= scope.lexical.contains(name);
+                scope.var.insert(name.to_string());
+                if scope.function {
+                    break;
+                }
+            }
+            redeclared
+        } else {
+            let Some(scope) = self.scopes.last_mut() else {
+                return;
+            };
+            // Sloppy mode code may declare a block-level function twice.
+            let repeated_function = !strict && kind == "function" && scope.functions.contains(name);
+            let redeclared = (scope.lexical.contains(name) && !repeated_function)
+                || scope.var.contains(name)
+                || scope.params.contains(name);
+            scope.lexical.insert(name.to_string());
+            if kind == "function" {
+                scope.functions.insert(name.to_string());
+            }
+            redeclared
+        };
+        if redeclared {
+            self.report(
+                ErrorCode::Redeclaration,
+                format!("Identifier '{}' has already been declared.", name),
+            );
+        }
+    }
+
+    /// Visits a binding pattern, checking and returning the names it binds.
+    fn binding(&mut self, pattern: &AstNode) -> Vec<String> {
+        let mut names = Vec::new();
+        self.collect_binding(pattern, &mut names);
+        for name in &names {
+            self.check_binding_name(name);
+        }
+        names
+    }
+
+    fn collect_binding(&mut self, pattern: &AstNode, names: &mut Vec<String>) {
+        match pattern {
+            // The `this` parameter of TypeScript and Flow only types the
+            // receiver.
+            AstNode::Identifier { name, .. } if name == "this" => {}
+            AstNode::Identifier { name, .. } => names.push(name.clone()),
+            AstNode::ArrayPattern { elements, .. } => {
+                for element in elements.iter().flatten() {
+                    self.collect_binding(element, names);
+                }
+            }
+            AstNode::ObjectPattern { properties, .. } => {
+                for property in properties {
+                    match property {
+                        AstNode::Property {
+                            key,
+                            value,
+                            computed,
+                            ..
+                        } => {
+                            if *computed {
+                                self.expression(key);
+                            }
+                            self.collect_binding(value, names);
+                        }
+                        property => self.collect_binding(property, names),
+                    }
+                }
+            }
+            AstNode::AssignmentPattern { left, right } => {
+                self.collect_binding(left, names);
+                self.expression(right);
+            }
+            AstNode::RestElement { argument, .. } => self.collect_binding(argument, names),
+            AstNode::TSParameterProperty { parameter, .. } => {
+                self.collect_binding(parameter, names)
+            }
+            _ => {}
+        }
+    }
+
+    fn check_binding_name(&mut self, name: &str) {
+        if self.strict && (name == "eval" || name == "arguments") {
+            self.report(
+                ErrorCode::StrictEvalArguments,
+                format!("Binding '{}' in strict mode.", name),
+            );
+        }
+        self.reference(name);
+    }
+
+    /// Checks a name used as an identifier, which `yield` and `await` may
+    /// not be where they are reserved.
+    fn reference(&mut self, name: &str) {
+        let reserved = match name {
+            "yield" => self.strict || self.in_generator,
+            "await" => self.module || self.in_async,
+            _ => false,
+        };
+        if reserved {
+            self.report(
+                ErrorCode::InvalidYieldOrAwait,
+                format!("'{}' cannot be used as an identifier here.", name),
+            );
+        }
+        if self.in_class_element && name == "arguments" {
+            self.report(
+                ErrorCode::StrictEvalArguments,
+                "'arguments' is not allowed in class field initialisers or static blocks."
+                    .to_string(),
+            );
+        }
+    }
+
+    fn assignment_target(&mut self, target: &AstNode) {
+        match target {
+            AstNode::Identifier { name, .. } => {
+                if self.strict && (name == "eval" || name == "arguments") {
+                    self.report(
+                        ErrorCode::StrictEvalArguments,
+                        format!("Assignment to '{}' in strict mode.", name),
+                    );
+                }
+                self.reference(name);
+            }
+            AstNode::ArrayPattern { elements, .. } => {
+                for element in elements.iter().flatten() {
+                    self.assignment_target(element);
+                }
+            }
+            AstNode::ObjectPattern { properties, .. } => {
+                for property in properties {
+                    match property {
+                        AstNode::Property {
+                            key,
+                            value,
+                            computed,
+                            ..
+                        } => {
+                            if *computed {
+                                self.expression(key);
+                            }
+                            self.assignment_target(value);
+                        }
+                        property => self.assignment_target(property),
+                    }
+                }
+            }
+            AstNode::AssignmentPattern { left, right } => {
+                self.assignment_target(left);
+                self.expression(right);
+            }
+            AstNode::RestElement { argument, .. } => self.assignment_target(argument),
+            target => self.expression(target),
+        }
+    }
+
+    fn expression(&mut self, expression: &AstNode) {
+        match expression {
+            AstNode::Identifier { name, .. } => self.reference(name),
+            AstNode::ArrayExpression { elements } => {
+                for element in elements.iter().flatten() {
+                    self.expression(element);
+                }
+            }
+            AstNode::ObjectExpression { properties } => {
+                for property in properties {
+                    self.property(property);
+                }
+            }
+            AstNode::SpreadElement { argument } => self.expression(argument),
+            AstNode::TSAsExpression { expression, .. }
+            | AstNode::TSSatisfiesExpression { expression, .. }
+            | AstNode::TSNonNullExpression { expression }
+            | AstNode::TSInstantiationExpression { expression, .. }
+            | AstNode::TSTypeAssertion { expression, .. }
+            | AstNode::TypeCastExpression { expression, .. } => self.expression(expression),
+            AstNode::FunctionExpression {
+                id,
+                params,
+                body,
+                generator,
+                r#async,
+                ..
+            } => self.function(
+                id.as_deref(),
+                params,
+                body,
+                *r#async,
+                *generator,
+                FunctionKind::Normal,
+            ),
+            AstNode::ArrowFunctionExpression {
+                params,
+                body,
+                r#async,
+                ..
+            } => self.function(None, params, body, *r#async, false, FunctionKind::Arrow),
+            AstNode::ClassExpression {
+                id,
+                super_class,
+                body,
+                decorators,
+                ..
+            } => self.class(id.as_deref(), super_class.as_deref(), body, decorators),
+            AstNode::CallExpression {
+                callee, arguments, ..
+            }
+            | AstNode::NewExpression {
+                callee, arguments, ..
+            } => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            AstNode::MemberExpression {
+                object,
+                property,
+                computed,
+                ..
+            } => {
+                self.expression(object);
+                if *computed {
+                    self.expression(property);
+                }
+            }
+            AstNode::ChainExpression { expression } => self.expression(expression),
+            AstNode::ImportExpression {
+                source, options, ..
+            } => {
+                self.expression(source);
+                if let Some(options) = options {
+                    self.expression(options);
+                }
+            }
+            AstNode::UnaryExpression { argument, .. } | AstNode::ThrowExpression { argument } => {
+                self.expression(argument)
+            }
+            AstNode::DoExpression { body } => self.statement(body),
+            AstNode::UpdateExpression { argument, .. } => self.assignment_target(argument),
+            AstNode::AwaitExpression { argument } => {
+                if self.in_params {
+                    self.report(
+                        ErrorCode::InvalidYieldOrAwait,
+                        "'await' expressions are not allowed in parameters.".to_string(),
+                    );
+                }
+                self.expression(argument);
+            }
+            AstNode::YieldExpression { argument, .. } => {
+                if self.in_params {
+                    self.report(
+                        ErrorCode::InvalidYieldOrAwait,
+                        "'yield' expressions are not allowed in parameters.".to_string(),
+                    );
+                }
+                if let Some(argument) = argument {
+                    self.expression(argument);
+                }
+            }
+            AstNode::BinaryExpression { left, right, .. }
+            | AstNode::LogicalExpression { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            AstNode::TernaryExpression {
+                left,
+                middle,
+                right,
+            } => {
+                self.expression(left);
+                self.expression(middle);
+                self.expression(right);
+            }
+            AstNode::SequenceExpression { expressions } => {
+                for expression in expressions {
+                    self.expression(expression);
+                }
+            }
+            AstNode::AssignmentExpression { left, right, .. } => {
+                self.assignment_target(left);
+                self.expression(right);
+            }
+            AstNode::JSXElement {
+                opening_element,
+                children,
+                ..
+            } => {
+                if let AstNode::JSXOpeningElement { attributes, .. } = &**opening_element {
+                    for attribute in attributes {
+                        match attribute {
+                            AstNode::JSXAttribute {
+                                value: Some(value), ..
+                            } => self.expression(value),
+                            AstNode::JSXSpreadAttribute { argument } => self.expression(argument),
+                            _ => {}
+                        }
+                    }
+                }
+                for child in children {
+                    self.expression(child);
+                }
+            }
+            AstNode::JSXFragment { children } => {
+                for child in children {
+                    self.expression(child);
+                }
+            }
+            AstNode::JSXExpressionContainer { expression }
+            | AstNode::JSXSpreadChild { expression } => self.expression(expression),
+            AstNode::MetaProperty { meta, .. } if !self.new_target => {
+                if matches!(&**meta, AstNode::Identifier { name, .. } if name == "new") {
+                    self.report(
+                        ErrorCode::IllegalNewTarget,
+                        "'new.target' is only allowed in functions and class bodies.".to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn property(&mut self, property: &AstNode) {
+        match property {
+            AstNode::Property {
+                key,
+                value,
+                kind,
+                computed,
+                method,
+                ..
+            } => {
+                if *computed {
+                    self.expression(key);
+                }
+                match &**value {
+                    AstNode::FunctionExpression {
+                        params,
+                        body,
+                        generator,
+                        r#async,
+                        ..
+                    } if *method || kind != "init" => self.function(
+                        None,
+                        params,
+                        body,
+                        *r#async,
+                        *generator,
+                        FunctionKind::Method,
+                    ),
+                    value => self.expression(value),
+                }
+            }
+            property => self.expression(property),
+        }
+    }
+
+    /// Visits a class. Every part of a class, including its decorators, is
+    /// strict mode code.
+    fn class(
+        &mut self,
+        id: Option<&AstNode>,
+        super_class: Option<&AstNode>,
+        body: &AstNode,
+        decorators: &[AstNode],
+    ) {
+        let strict = std::mem::replace(&mut self.strict, true);
+        self.decorators(decorators);
+        if let Some(AstNode::Identifier { name, .. }) = id {
+            self.check_binding_name(name);
+        }
+        if let Some(super_class) = super_class {
+            self.expression(super_class);
+        }
+        if let AstNode::ClassBody { body } = body {
+            for member in body {
+                self.class_member(member);
+            }
+        }
+        self.strict = strict;
+    }
+
+    fn class_member(&mut self, member: &AstNode) {
+        match member {
+            AstNode::MethodDefinition {
+                key,
+                value,
+                computed,
+                decorators,
+                ..
+            } => {
+                self.decorators(decorators);
+                if *computed {
+                    self.expression(key);
+                }
+                if let AstNode::FunctionExpression {
+                    params,
+                    body,
+                    generator,
+                    r#async,
+                    ..
+                } = &**value
+                {
+                    self.function(
+                        None,
+                        params,
+                        body,
+                        *r#async,
+                        *generator,
+                        FunctionKind::Method,
+                    );
+                }
+            }
+            AstNode::PropertyDefinition {
+                key,
+                value,
+                computed,
+                decorators,
+                ..
+            }
+            | AstNode::AccessorProperty {
+                key,
+                value,
+                computed,
+                decorators,
+                ..
+            } => {
+                self.decorators(decorators);
+                if *computed {
+                    self.expression(key);
+                }
+                if let Some(value) = value {
+                    self.in_class_element(|validator| validator.expression(value));
+                }
+            }
+            AstNode::StaticBlock { body } => self.in_class_element(|validator| {
+                validator.scopes.push(Scope::function());
+                validator.statements(body);
+                validator.scopes.pop();
+            }),
+            _ => {}
+        }
+    }
+
+    fn decorators(&mut self, decorators: &[AstNode]) {
+        for decorator in decorators {
+            if let AstNode::Decorator { expression } = decorator {
+                self.expression(expression);
+            }
+        }
+    }
+
+    /// Runs `visit` for a field initialiser or static block, which are
+    /// neither inside the surrounding function nor functions themselves.
+    fn in_class_element(&mut self, visit: impl FnOnce(&mut Self)) {
+        let in_function = std::mem::replace(&mut self.in_function, false);
+        let in_async = std::mem::replace(&mut self.in_async, false);
+        let in_generator = std::mem::replace(&mut self.in_generator, false);
+        let labels = std::mem::take(&mut self.labels);
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let breakable_depth = std::mem::replace(&mut self.breakable_depth, 0);
+        let in_class_element = std::mem::replace(&mut self.in_class_element, true);
+        let new_target = std::mem::replace(&mut self.new_target, true);
+        visit(self);
+        self.in_class_element = in_class_element;
+        self.new_target = new_target;
+        self.in_function = in_function;
+        self.in_async = in_async;
+        self.in_generator = in_generator;
+        self.labels = labels;
+        self.loop_depth = loop_depth;
+        self.breakable_depth = breakable_depth;
+    }
+
+    fn function(
+        &mut self,
+        id: Option<&AstNode>,
+        params: &[AstNode],
+        body: &AstNode,
+        is_async: bool,
+        generator: bool,
+        kind: FunctionKind,
+    ) {
+        let strict = self.strict;
+        if let AstNode::BlockStatement { body } = body {
+            self.strict |= has_use_strict(body);
+        }
+        // The name of a function expression is bound outside its body.
+        if let Some(AstNode::Identifier { name, .. }) = id {
+            self.check_binding_name(name);
+        }
+
+        let in_function = std::mem::replace(&mut self.in_function, true);
+        // The parameters of an arrow function are in the context around it,
+        // where `yield` and `await` may be reserved.
+        let (in_async, in_generator) = if kind == FunctionKind::Arrow {
+            let in_async = self.in_async;
+            self.in_async |= is_async;
+            (in_async, self.in_generator)
+        } else {
+            (
+                std::mem::replace(&mut self.in_async, is_async),
+                std::mem::replace(&mut self.in_generator, generator),
+            )
+        };
+        // Arrow functions see the `arguments` of the code around them.
+        let in_class_element = kind == FunctionKind::Arrow && self.in_class_element;
+        let in_class_element = std::mem::replace(&mut self.in_class_element, in_class_element);
+        let new_target = kind != FunctionKind::Arrow || self.new_target;
+        let new_target = std::mem::replace(&mut self.new_target, new_target);
+        let labels = std::mem::take(&mut self.labels);
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let breakable_depth = std::mem::replace(&mut self.breakable_depth, 0);
+
+        self.in_params = true;
+        let mut names = Vec::new();
+        for param in params {
+            names.extend(self.binding(param));
+        }
+        self.in_params = false;
+        let simple = params
+            .iter()
+            .all(|param| matches!(param, AstNode::Identifier { .. }));
+        if self.strict || !simple || kind != FunctionKind::Normal {
+            let mut seen = HashSet::new();
+            for name in &names {
+                if !seen.insert(name) {
+                    self.report(
+                        ErrorCode::DuplicateParameter,
+                        format!("Duplicate parameter name '{}'.", name),
+                    );
+                }
+            }
+        }
+
+        // Arrow function bodies are only async code in async arrow functions,
+        // and never generator code.
+        if kind == FunctionKind::Arrow {
+            self.in_async = is_async;
+            self.in_generator = false;
+        }
+        let mut scope = Scope::function();
+        scope.params = names.into_iter().collect();
+        self.scopes.push(scope);
+        match body {
+            AstNode::BlockStatement { body } => self.statements(body),
+            expression => self.expression(expression),
+        }
+        self.scopes.pop();
+
+        self.strict = strict;
+        self.in_function = in_function;
+        self.in_async = in_async;
+        self.in_generator = in_generator;
+        self.in_class_element = in_class_element;
+        self.new_target = new_target;
+        self.labels = labels;
+        self.loop_depth = loop_depth;
+        self.breakable_depth = breakable_depth;
+    }
+}
+
+/// The names that an exported declaration binds.
+fn declared_names(declaration: &AstNode) -> Vec<String> {
+    let mut names = Vec::new();
+    match declaration {
+        AstNode::VariableDeclaration { declarations, .. } => {
+            for declarator in declarations {
+                if let AstNode::VariableDeclarator { id, .. } = declarator {
+                    pattern_names(id, &mut names);
+                }
+            }
+        }
+        AstNode::FunctionDeclaration { id, .. } | AstNode::ClassDeclaration { id, .. } => {
+            pattern_names(id, &mut names)
+        }
+        _ => {}
+    }
+    names
+}
+
+fn pattern_names(pattern: &AstNode, names: &mut Vec<String>) {
+    match pattern {
+        AstNode::Identifier { name, .. } => names.push(name.clone()),
+        AstNode::ArrayPattern { elements, .. } => {
+            for element in elements.iter().flatten() {
+                pattern_names(element, names);
+            }
+        }
+        AstNode::ObjectPattern { properties, .. } => {
+            for property in properties {
+                match property {
+                    AstNode::Property { value, .. } => pattern_names(value, names),
+                    property => pattern_names(property, names),
+                }
+            }
+        }
+        AstNode::AssignmentPattern { left: pattern, .. }
+        | AstNode::RestElement {
+            argument: pattern, ..
+        } => pattern_names(pattern, names),
+        _ => {}
+    }
+}
+
+/// The name of an `export` or `export * as` specifier, which may be a
+/// string as in `export { a as "b c" }`.
+fn module_export_name(name: &AstNode) -> String {
+    match name {
+        AstNode::Identifier { name, .. } | AstNode::StringLiteral(name) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Whether a directive prologue makes the code that follows strict.
+fn has_use_strict(body: &[AstNode]) -> bool {
+    body.iter()
+        .map_while(|statement| match statement {
+            AstNode::ExpressionStatement {
+                directive: Some(directive),
+                ..
+            } => Some(directive),
+            _ => None,
+        })
+        .any(|directive| directive == "use strict")
+}
+
+fn is_loop(statement: &AstNode) -> bool {
+    matches!(
+        statement,
+        AstNode::WhileStatement { .. }
+            | AstNode::DoWhileStatement { .. }
+            | AstNode::ForStatement { .. }
+            | AstNode::ForInStatement { .. }
+            | AstNode::ForOfStatement { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer;
+    use crate::lexer::Parser::Parser;
+    use crate::lexer::Token::Span;
+
+    fn early_errors(source: &str, source_type: SourceType) -> Vec<ErrorCode> {
+        let options = ParseOptions {
+            source_type,
+            ..ParseOptions::default()
+        };
+        let tokens = lexer::tokenize_with_options(source, &options);
+        let program = Parser::with_options(tokens, options.clone())
+            .parse()
+            .unwrap();
+        validate(&program, &options)
+            .into_iter()
+            .map(|error| error.code)
+            .collect()
+    }
+
+    fn script_errors(source: &str) -> Vec<ErrorCode> {
+        early_errors(source, SourceType::Script)
+    }
+
+    #[test]
+    fn test_redeclarations() {
+        use ErrorCode::Redeclaration;
+        assert_eq!(script_errors("let a; let a;"), vec![Redeclaration]);
+        assert_eq!(script_errors("var a; let a;"), vec![Redeclaration]);
+        assert_eq!(script_errors("let a; { var a; }"), vec![Redeclaration]);
+        assert_eq!(
+            script_errors("const a = 1; function a() {}"),
+            vec![Redeclaration]
+        );
+        assert_eq!(
+            script_errors("function f(a) { let a; }"),
+            vec![Redeclaration]
+        );
+        assert_eq!(
+            script_errors("try {} catch (e) { let e; }"),
+            vec![Redeclaration]
+        );
+        assert_eq!(
+            script_errors("switch (x) { case 1: let a; case 2: let a; }"),
+            vec![Redeclaration]
+        );
+        assert_eq!(script_errors("a: a: ;"), vec![Redeclaration]);
+        assert_eq!(
+            early_errors("import a from 'm'; let a;", SourceType::Module),
+            vec![Redeclaration]
+        );
+
+        assert!(script_errors("var a; var a; function a() {}").is_empty());
+        assert!(script_errors("let a; { let a; } function f(a) { var a; }").is_empty());
+        assert!(script_errors("for (let i;;) {} for (let i of x) {} let i;").is_empty());
+        assert!(script_errors("{ function f() {} function f() {} }").is_empty());
+        assert_eq!(
+            script_errors("'use strict'; { function f() {} function f() {} }"),
+            vec![Redeclaration]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_parameters() {
+        use ErrorCode::DuplicateParameter;
+        assert!(script_errors("function f(a, a) {}").is_empty());
+        assert_eq!(
+            script_errors("function f(a, a) { 'use strict'; }"),
+            vec![DuplicateParameter]
+        );
+        assert_eq!(
+            script_errors("function f(a, [a]) {}"),
+            vec![DuplicateParameter]
+        );
+        assert_eq!(script_errors("(a, a) => 1;"), vec![DuplicateParameter]);
+        assert_eq!(script_errors("({ m(a, a) {} });"), vec![DuplicateParameter]);
+        assert_eq!(
+            early_errors("function f(a, a) {}", SourceType::Module),
+            vec![DuplicateParameter]
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        use ErrorCode::{IllegalReturn, InvalidJump};
+        assert_eq!(script_errors("return;"), vec![IllegalReturn]);
+        assert_eq!(
+            script_errors("class A { static { return; } }"),
+            vec![IllegalReturn]
+        );
+        assert!(script_errors("function f() { return; } () => { return; };").is_empty());
+
+        assert_eq!(script_errors("a: { break b; }"), vec![InvalidJump]);
+        assert_eq!(script_errors("a: { continue a; }"), vec![InvalidJump]);
+        assert_eq!(script_errors("break;"), vec![InvalidJump]);
+        assert_eq!(
+            script_errors("while (x) { function f() { continue; } }"),
+            vec![InvalidJump]
+        );
+        assert_eq!(
+            script_errors("a: while (x) { (() => { break a; }); }"),
+            vec![InvalidJump]
+        );
+        assert!(script_errors(
+            "a: b: for (;;) { switch (x) { case 1: break; default: continue a; } break b; }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_new_target() {
+        use ErrorCode::IllegalNewTarget;
+        assert_eq!(script_errors("new.target;"), vec![IllegalNewTarget]);
+        assert_eq!(script_errors("() => new.target;"), vec![IllegalNewTarget]);
+        assert_eq!(
+            early_errors("if (x) { f(new.target); }", SourceType::Module),
+            vec![IllegalNewTarget]
+        );
+        assert!(script_errors(
+            "function f() { return () => new.target; } \
+             class A { x = new.target; static { new.target; } m() { new.target; } }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_yield_and_await() {
+        use ErrorCode::InvalidYieldOrAwait;
+        assert!(script_errors("var yield = 1; await = yield;").is_empty());
+        assert_eq!(
+            script_errors("'use strict'; var yield;"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert_eq!(
+            script_errors("function* g(a = yield) {}"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert_eq!(
+            script_errors("async (a = await b) => a;"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert_eq!(
+            script_errors("function* g() { function yield() {} }"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert!(script_errors("function* g() { function f() { var yield; } }").is_empty());
+        assert_eq!(
+            script_errors("async function f() { (await) => 1; }"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert_eq!(
+            script_errors("function* g() { (yield) => 1; }"),
+            vec![InvalidYieldOrAwait]
+        );
+        assert!(script_errors("async function f() { () => { var await; }; }").is_empty());
+    }
+
+    #[test]
+    fn test_strict_eval_and_arguments() {
+        use ErrorCode::StrictEvalArguments;
+        assert!(script_errors("eval = 1; var arguments; function eval() {}").is_empty());
+        assert_eq!(
+            script_errors("'use strict'; eval = 1;"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("'use strict'; arguments++;"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("'use strict'; [eval] = a;"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("function f(eval) { 'use strict'; }"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("class A { m(arguments) {} }"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            early_errors("let eval;", SourceType::Module),
+            vec![StrictEvalArguments]
+        );
+    }
+
+    #[test]
+    fn test_arguments_in_class_elements() {
+        use ErrorCode::StrictEvalArguments;
+        assert_eq!(
+            script_errors("class A { x = arguments; }"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("class A { static x = () => arguments[0]; }"),
+            vec![StrictEvalArguments]
+        );
+        assert_eq!(
+            script_errors("class A { static { f(arguments); } }"),
+            vec![StrictEvalArguments]
+        );
+        assert!(script_errors(
+            "function f() { class A { [arguments[0]] = function () { return arguments; }; } }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_module_exports() {
+        use ErrorCode::{DuplicateExport, UndeclaredExport};
+        let module_errors = |source| early_errors(source, SourceType::Module);
+        assert_eq!(
+            module_errors("export let a; export function a() {}"),
+            vec![ErrorCode::Redeclaration, DuplicateExport]
+        );
+        assert_eq!(
+            module_errors("let a, b; export { a, b as a };"),
+            vec![DuplicateExport]
+        );
+        assert_eq!(
+            module_errors("export default 1; export default function () {}"),
+            vec![DuplicateExport]
+        );
+        assert_eq!(
+            module_errors("let a; export { a as default }; export default a;"),
+            vec![DuplicateExport]
+        );
+        assert_eq!(
+            module_errors("export * as ns from 'm'; export const [ns] = x;"),
+            vec![DuplicateExport]
+        );
+        assert_eq!(module_errors("export { a };"), vec![UndeclaredExport]);
+        assert_eq!(
+            module_errors("function f() { var a; } export { a as b };"),
+            vec![UndeclaredExport]
+        );
+
+        assert!(
+            module_errors("export { a, b, c as 'd e' }; import a from 'm'; var b; { var c; }")
+                .is_empty()
+        );
+        assert!(module_errors("export { a, b as a2 } from 'm'; export * from 'n';").is_empty());
+    }
+
+    #[test]
+    fn test_parse_reports_early_errors() {
+        let options = ParseOptions {
+            source_type: SourceType::Module,
+            ..ParseOptions::default()
+        };
+        let errors = lexer::parse("let a = 1;\nexport { b };\nlet a;", &options).unwrap_err();
+        let errors: Vec<(ErrorCode, Span)> = errors
+            .into_iter()
+            .map(|error| (error.code, error.span))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (ErrorCode::UndeclaredExport, Span::new(11, 24)),
+                (ErrorCode::Redeclaration, Span::new(25, 31)),
+            ]
+        );
+        assert!(lexer::parse("let a = 1;\nexport { a };", &options).is_ok());
+    }
+}
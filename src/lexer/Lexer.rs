@@ -1,51 +1,296 @@
-use std::iter::Peekable;
-use std::str::Chars;
+use crate::lexer::ParseError::{ErrorCode, ParseError};
+use crate::lexer::ParseOptions::{ParseOptions, SourceType};
+use crate::lexer::Token::{Span, SpannedToken, Token};
 
-use crate::lexer::Token::Token;
-
-struct Lexer<'a> {
-    source: Peekable<Chars<'a>>,
+pub struct Lexer<'a> {
+    source: &'a str,
     pos: usize,
+    // End of the previously returned spanned token, used to detect line
+    // terminators between tokens.
+    last_end: usize,
+    options: ParseOptions,
+    // Malformed literals, and syntax newer than `options.ecma_version` that
+    // was lexed anyway.
+    errors: Vec<ParseError>,
+    // The kind of the previously returned token, which tells whether a `<`
+    // starts a JSX tag or is an operator.
+    previous: Previous,
+    // The JSX constructs enclosing the current position, innermost last.
+    jsx: Vec<JsxContext>,
+    // Whether a comment before the first token holds the `@flow` pragma.
+    flow_pragma: bool,
+    // The number of `{` left open in each template substitution being
+    // lexed, innermost last. The `}` that closes a substitution continues
+    // its template.
+    templates: Vec<usize>,
+}
+
+/// What a lexer needs to resume after a token: the kind of the token, and
+/// the JSX constructs and template substitutions still open after it.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub(crate) struct LexerState {
+    previous: Previous,
+    jsx: Vec<JsxContext>,
+    templates: Vec<usize>,
+}
+
+impl LexerState {
+    /// Whether the token this state follows can end an expression, so that a
+    /// `<` after it was taken for an operator.
+    pub(crate) fn after_expression(&self) -> bool {
+        self.previous == Previous::ExpressionEnd
+    }
+
+    /// The same state as if the token before could not end an expression,
+    /// for resuming where the parser knows that an expression starts.
+    pub(crate) fn expecting_expression(mut self) -> LexerState {
+        self.previous = Previous::Other;
+        self
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+enum Previous {
+    // No token has been returned yet, so only comments precede the position
+    #[default]
+    Nothing,
+    Less,
+    Slash,
+    // A token that can end an expression, after which `<` is an operator
+    ExpressionEnd,
+    Other,
+}
+
+impl Previous {
+    fn of(token: &Token) -> Previous {
+        match token {
+            Token::Less => Previous::Less,
+            Token::Slash => Previous::Slash,
+            Token::Identifier(_)
+            | Token::PrivateIdentifier(_)
+            | Token::String(_)
+            | Token::Number(_)
+            | Token::LegacyOctal(_)
+            | Token::Template(_)
+            | Token::TemplateTail(_)
+            | Token::RightParen
+            | Token::RightBracket
+            | Token::RightBrace
+            | Token::This
+            | Token::Super
+            | Token::Null
+            | Token::True
+            | Token::False
+            | Token::PlusPlus
+            | Token::MinusMinus => Previous::ExpressionEnd,
+            _ => Previous::Other,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum JsxContext {
+    // Between `<` and `>`; `closing` once the `/` of `</` is seen
+    Tag { closing: bool },
+    // Between an opening tag and its closing tag
+    Children,
+    // A `{}` in a tag or children, lexed as JavaScript
+    Expression,
+    // A JavaScript `{` nested in an expression container
+    Brace,
 }
 
 impl<'a> Lexer<'a> {
-    fn new(source: &'a str) -> Self {
+    pub fn new(source: &'a str) -> Self {
+        Self::with_options(source, &ParseOptions::default())
+    }
+
+    pub fn with_options(source: &'a str, options: &ParseOptions) -> Self {
+        Self::resuming(source, 0, options, LexerState::default())
+    }
+
+    /// Returns the errors reported since the last call.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Whether a comment before the first token holds the `@flow` pragma,
+    /// as in `// @flow` or `/* @flow strict */`.
+    pub fn flow_pragma(&self) -> bool {
+        self.flow_pragma
+    }
+
+    /// Creates a lexer that resumes at byte offset `pos`, which must be the
+    /// end of a token after which `state` was the lexer's state, or 0 with
+    /// the default state.
+    pub(crate) fn resuming(
+        source: &'a str,
+        pos: usize,
+        options: &ParseOptions,
+        state: LexerState,
+    ) -> Self {
         Self {
-            source: source.chars().peekable(),
-            pos: 0,
+            source,
+            pos,
+            last_end: pos,
+            options: options.clone(),
+            errors: Vec::new(),
+            previous: state.previous,
+            jsx: state.jsx,
+            flow_pragma: false,
+            templates: state.templates,
         }
     }
 
-    fn next_token(&mut self) -> Option<Token> {
-        self.skip_whitespace();
+    /// The state after the last returned token, for `resuming` after it.
+    pub(crate) fn state(&self) -> LexerState {
+        LexerState {
+            previous: self.previous,
+            jsx: self.jsx.clone(),
+            templates: self.templates.clone(),
+        }
+    }
 
-        let ch = self.peek();
-        if ch.is_none() {
-            return None;
+    /// Scans the whole source, skipping comments. The returned vector always
+    /// ends with a `Token::EOF` positioned at the end of the source.
+    pub fn tokenize(&mut self) -> Vec<SpannedToken> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next_spanned_token();
+            let done = token.token == Token::EOF;
+            tokens.push(token);
+            if done {
+                return tokens;
+            }
         }
+    }
 
-        let ch = ch.unwrap();
+    /// Like `tokenize`, but also returns the state after each token, from
+    /// which a lexer `resuming` at the end of that token continues.
+    pub(crate) fn tokenize_with_states(&mut self) -> (Vec<SpannedToken>, Vec<LexerState>) {
+        let mut tokens = Vec::new();
+        let mut states = Vec::new();
+        loop {
+            let token = self.next_spanned_token();
+            let done = token.token == Token::EOF;
+            tokens.push(token);
+            states.push(self.state());
+            if done {
+                return (tokens, states);
+            }
+        }
+    }
+
+    /// Returns the next real token with its span, or `Token::EOF` once the
+    /// source is exhausted.
+    pub fn next_spanned_token(&mut self) -> SpannedToken {
+        loop {
+            let context = self.jsx.last().copied();
+            // Whitespace between JSX children is part of their text.
+            if context != Some(JsxContext::Children) {
+                self.skip_whitespace();
+            }
+            let start = self.pos;
+            let token = match self.peek() {
+                None => Token::EOF,
+                Some(_) if context == Some(JsxContext::Children) => self.scan_jsx_child(),
+                Some(_) if matches!(context, Some(JsxContext::Tag { .. })) => self.scan_jsx_tag(),
+                Some('<') if self.starts_jsx_tag() => {
+                    self.pos += 1;
+                    self.jsx.push(JsxContext::Tag { closing: false });
+                    Token::Less
+                }
+                Some(ch) => match self.next_token() {
+                    Some(token) => token,
+                    // Comments produce no token.
+                    None if self.pos > start => continue,
+                    None => {
+                        self.pos += ch.len_utf8();
+                        Token::Illegal(ch)
+                    }
+                },
+            };
+            if matches!(context, Some(JsxContext::Expression | JsxContext::Brace)) {
+                self.track_jsx_braces(&token);
+            }
+            let newline_before = self.source[self.last_end..start]
+                .chars()
+                .any(is_line_terminator);
+            self.last_end = self.pos;
+            self.previous = Previous::of(&token);
+            return SpannedToken {
+                token,
+                span: Span::new(start, self.pos),
+                newline_before,
+            };
+        }
+    }
 
-        if ch.is_ascii_digit() {
+    pub fn next_token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+
+        let ch = self.peek()?;
+
+        let is_fraction = ch == '.' && self.peek_n(1).is_some_and(|next| next.is_ascii_digit());
+        if ch.is_ascii_digit() || is_fraction {
             return Some(self.scan_number());
         } else if ch == '"' || ch == '\'' {
             return Some(self.scan_string());
-        } else if ch.is_ascii_alphabetic() {
+        } else if is_identifier_start(ch) {
             return Some(self.scan_identifier());
         }
 
         match ch {
             '+' => {
-                self.pos += 1;
-                Some(Token::Plus)
+                if self.peek_n(1) == Some('+') {
+                    self.pos += 2;
+                    Some(Token::PlusPlus)
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::PlusEqual)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Plus)
+                }
             }
             '-' => {
-                self.pos += 1;
-                Some(Token::Minus)
+                if self.html_comments() && self.peek_n(1) == Some('-') && self.peek_n(2) == Some('>') && self.at_line_start() {
+                    self.skip_comment();
+                    None
+                } else if self.peek_n(1) == Some('-') {
+                    self.pos += 2;
+                    Some(Token::MinusMinus)
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::MinusEqual)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Minus)
+                }
             }
             '*' => {
-                self.pos += 1;
-                Some(Token::Star)
+                if self.peek_n(1) == Some('*') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::StarStarEqual)
+                } else if self.peek_n(1) == Some('*') {
+                    self.pos += 2;
+                    Some(Token::StarStar)
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::StarEqual)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Star)
+                }
+            }
+            '%' => {
+                if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::PercentEqual)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Percent)
+                }
             }
             '/' => {
                 // manually added
@@ -58,11 +303,20 @@ impl<'a> Lexer<'a> {
                     self.pos += 2;
                     self.skip_comment_block();
                     None
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::SlashEqual)
                 } else {
                     self.pos += 1;
                     Some(Token::Slash)
                 }
             }
+            '#' if self.peek_n(1).is_some_and(is_identifier_start) => {
+                self.pos += 1;
+                // Reserved words are valid private names, as in `#class`.
+                let name = self.scan_identifier().to_string();
+                Some(Token::PrivateIdentifier(name))
+            }
             '(' => {
                 self.pos += 1;
                 Some(Token::LeftParen)
@@ -73,11 +327,28 @@ impl<'a> Lexer<'a> {
             }
             '{' => {
                 self.pos += 1;
+                if let Some(open) = self.templates.last_mut() {
+                    *open += 1;
+                }
                 Some(Token::LeftBrace)
             }
             '}' => {
                 self.pos += 1;
-                Some(Token::RightBrace)
+                match self.templates.last_mut() {
+                    Some(0) => {
+                        self.templates.pop();
+                        Some(self.scan_template(false))
+                    }
+                    Some(open) => {
+                        *open -= 1;
+                        Some(Token::RightBrace)
+                    }
+                    None => Some(Token::RightBrace),
+                }
+            }
+            '`' => {
+                self.pos += 1;
+                Some(self.scan_template(true))
             }
             '[' => {
                 self.pos += 1;
@@ -96,20 +367,34 @@ impl<'a> Lexer<'a> {
                 Some(Token::Comma)
             }
             '.' => {
-                self.pos += 1;
-                Some(Token::Dot)
+                if self.peek_n(1) == Some('.') && self.peek_n(2) == Some('.') {
+                    self.pos += 3;
+                    Some(Token::Ellipsis)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Dot)
+                }
             }
             '=' => {
-                if self.peek_n(1) == Some('=') {
+                if self.peek_n(1) == Some('=') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::EqualEqualEqual)
+                } else if self.peek_n(1) == Some('=') {
                     self.pos += 2;
                     Some(Token::EqualEqual)
+                } else if self.peek_n(1) == Some('>') {
+                    self.pos += 2;
+                    Some(Token::Arrow)
                 } else {
                     self.pos += 1;
                     Some(Token::Equal)
                 }
             }
             '!' => {
-                if self.peek_n(1) == Some('=') {
+                if self.peek_n(1) == Some('=') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::BangEqualEqual)
+                } else if self.peek_n(1) == Some('=') {
                     self.pos += 2;
                     Some(Token::BangEqual)
                 } else {
@@ -118,7 +403,16 @@ impl<'a> Lexer<'a> {
                 }
             }
             '<' => {
-                if self.peek_n(1) == Some('=') {
+                if self.html_comments() && self.source[self.pos..].starts_with("<!--") {
+                    self.skip_comment();
+                    None
+                } else if self.peek_n(1) == Some('<') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::LessLessEqual)
+                } else if self.peek_n(1) == Some('<') {
+                    self.pos += 2;
+                    Some(Token::LessLess)
+                } else if self.peek_n(1) == Some('=') {
                     self.pos += 2;
                     Some(Token::LessEqual)
                 } else {
@@ -127,7 +421,19 @@ impl<'a> Lexer<'a> {
                 }
             }
             '>' => {
-                if self.peek_n(1) == Some('=') {
+                if self.peek_n(1) == Some('>') && self.peek_n(2) == Some('>') && self.peek_n(3) == Some('=') {
+                    self.pos += 4;
+                    Some(Token::GreaterGreaterGreaterEqual)
+                } else if self.peek_n(1) == Some('>') && self.peek_n(2) == Some('>') {
+                    self.pos += 3;
+                    Some(Token::GreaterGreaterGreater)
+                } else if self.peek_n(1) == Some('>') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::GreaterGreaterEqual)
+                } else if self.peek_n(1) == Some('>') {
+                    self.pos += 2;
+                    Some(Token::GreaterGreater)
+                } else if self.peek_n(1) == Some('=') {
                     self.pos += 2;
                     Some(Token::GreaterEqual)
                 } else {
@@ -136,57 +442,195 @@ impl<'a> Lexer<'a> {
                 }
             }
             '&' => {
-                if self.peek_n(1) == Some('&') {
+                if self.peek_n(1) == Some('&') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::AmpersandAmpersandEqual)
+                } else if self.peek_n(1) == Some('&') {
                     self.pos += 2;
                     Some(Token::AmpersandAmpersand)
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::AmpersandEqual)
                 } else {
                     self.pos += 1;
                     Some(Token::Ampersand)
                 }
             }
             '|' => {
-                if self.peek_n(1) == Some('|') {
+                if self.peek_n(1) == Some('|') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::PipePipeEqual)
+                } else if self.peek_n(1) == Some('|') {
                     self.pos += 2;
                     Some(Token::PipePipe)
+                } else if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::PipeEqual)
+                } else if self.peek_n(1) == Some('>') {
+                    self.pos += 2;
+                    Some(Token::PipeGreater)
                 } else {
                     self.pos += 1;
                     Some(Token::Pipe)
                 }
             }
+            '^' => {
+                if self.peek_n(1) == Some('=') {
+                    self.pos += 2;
+                    Some(Token::CaretEqual)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Caret)
+                }
+            }
+            '~' => {
+                self.pos += 1;
+                Some(Token::Tilde)
+            }
+            '@' => {
+                self.pos += 1;
+                Some(Token::At)
+            }
+            '?' => {
+                if self.peek_n(1) == Some('?') && self.peek_n(2) == Some('=') {
+                    self.pos += 3;
+                    Some(Token::QuestionQuestionEqual)
+                } else if self.peek_n(1) == Some('?') {
+                    self.pos += 2;
+                    Some(Token::QuestionQuestion)
+                } else if self.peek_n(1) == Some('.')
+                    && !self.peek_n(2).is_some_and(|ch| ch.is_ascii_digit())
+                {
+                    // `a?.5:b` is a conditional, not an optional chain.
+                    self.pos += 2;
+                    Some(Token::QuestionDot)
+                } else {
+                    self.pos += 1;
+                    Some(Token::Question)
+                }
+            }
+            ':' => {
+                self.pos += 1;
+                Some(Token::Colon)
+            }
             _ => None,
         }
     }
 
     fn peek(&mut self) -> Option<char> {
-        let mut chars = self.source.clone();
-
-        for _ in 0..self.pos {
-            chars.next();
-        }
-        chars.peek().cloned()
+        self.source[self.pos..].chars().next()
     }
 
     fn peek_n(&self, n: usize) -> Option<char> {
-        let mut chars = self.source.clone();
-        for _ in 0..(self.pos + n) {
-            chars.next();
-        }
-        chars.peek().cloned()
+        self.source[self.pos..].chars().nth(n)
     }
 
     fn scan_number(&mut self) -> Token {
+        let start = self.pos;
         let mut number = String::new();
+        let mut invalid = Vec::new();
+
+        let mut separated = self.scan_digits(&mut number, &mut invalid);
+        if self.peek() == Some('.') {
+            number.push('.');
+            self.pos += 1;
+            separated |= self.scan_digits(&mut number, &mut invalid);
+        }
+        // An exponent only follows when digits do, so `1e` is `1` and `e`.
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            let sign = self.peek_n(1).filter(|ch| *ch == '+' || *ch == '-');
+            let digits_at = 1 + sign.is_some() as usize;
+            if self.peek_n(digits_at).is_some_and(|ch| ch.is_ascii_digit()) {
+                number.push(e);
+                number.extend(sign);
+                self.pos += digits_at;
+                separated |= self.scan_digits(&mut number, &mut invalid);
+            }
+        }
+        // The number ends at its first `.` and after its exponent, so
+        // `1..toString()` is a member access on `1.`, but the digits of
+        // `1.2.3` cannot start another number.
+        if self.peek() == Some('.') && self.peek_n(1).is_some_and(|ch| ch.is_ascii_digit()) {
+            let extra_start = self.pos;
+            while self.peek().is_some_and(|ch| ch == '.' || ch == '_' || ch.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            invalid.push((
+                Span::new(extra_start, self.pos),
+                "A number literal can only contain one '.'.",
+            ));
+        }
+
+        let legacy = number.len() > 1
+            && number.starts_with('0')
+            && number.chars().all(|ch| ch.is_ascii_digit());
+        let decimal = number.parse::<f64>();
+        let token = if legacy && !separated {
+            // `010` is octal, but `08` and `09` are decimal.
+            let value = u64::from_str_radix(&number, 8)
+                .map_or_else(|_| decimal.clone().unwrap_or(f64::NAN), |value| value as f64);
+            Token::LegacyOctal(value)
+        } else {
+            if decimal.is_err() {
+                invalid.push((Span::new(start, self.pos), "Invalid number literal."));
+            }
+            Token::Number(decimal.unwrap_or(f64::NAN))
+        };
+        for (span, message) in invalid {
+            self.errors.push(ParseError {
+                code: ErrorCode::InvalidNumber,
+                span,
+                expected: "digit".to_string(),
+                found: token.clone(),
+                message: message.to_string(),
+            });
+        }
+        if separated {
+            if let Some(message) = self.options.unsupported("numeric separators", 2021) {
+                self.errors.push(ParseError {
+                    code: ErrorCode::UnsupportedSyntax,
+                    span: Span::new(start, self.pos),
+                    expected: format!("ES{} syntax", self.options.ecma_version),
+                    found: token.clone(),
+                    message,
+                });
+            }
+        }
+        token
+    }
 
+    /// Appends a run of decimal digits to `number`, skipping numeric
+    /// separators, and returns whether there were any. A separator must be a
+    /// single `_` between two digits, so `1__0` and `1_` are errors.
+    fn scan_digits(
+        &mut self,
+        number: &mut String,
+        invalid: &mut Vec<(Span, &'static str)>,
+    ) -> bool {
+        let mut separated = false;
         while let Some(ch) = self.peek() {
-            if ch.is_ascii_digit() || ch == '.' {
+            if ch.is_ascii_digit() {
                 number.push(ch);
                 self.pos += 1;
+            } else if ch == '_' && number.ends_with(|digit: char| digit.is_ascii_digit()) {
+                let separator_start = self.pos;
+                while self.peek() == Some('_') {
+                    self.pos += 1;
+                }
+                separated = true;
+                if self.pos - separator_start > 1
+                    || !self.peek().is_some_and(|next| next.is_ascii_digit())
+                {
+                    invalid.push((
+                        Span::new(separator_start, self.pos),
+                        "Numeric separators must be a single '_' between digits.",
+                    ));
+                }
             } else {
                 break;
             }
         }
-
-        Token::Number(number.parse().unwrap())
+        separated
     }
 
     fn scan_string(&mut self) -> Token {
@@ -204,11 +648,49 @@ impl<'a> Lexer<'a> {
         Token::String(string)
     }
 
+    /// Scans the text of a template literal after its opening `` ` ``, or
+    /// after the `}` ending a substitution when `head` is false, up to and
+    /// including the closing `` ` `` or the `${` of the next substitution.
+    fn scan_template(&mut self, head: bool) -> Token {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some('$') if self.peek_n(1) == Some('{') => {
+                    let text = self.source[start..self.pos].to_string();
+                    self.pos += 2;
+                    self.templates.push(0);
+                    return if head {
+                        Token::TemplateHead(text)
+                    } else {
+                        Token::TemplateMiddle(text)
+                    };
+                }
+                Some('`') | None => {
+                    let text = self.source[start..self.pos].to_string();
+                    self.next();
+                    return if head {
+                        Token::Template(text)
+                    } else {
+                        Token::TemplateTail(text)
+                    };
+                }
+                // An escaped `` ` `` or `$` does not end the text.
+                Some('\\') => {
+                    self.pos += 1;
+                    self.next();
+                }
+                Some(_) => {
+                    self.next();
+                }
+            }
+        }
+    }
+
     fn scan_identifier(&mut self) -> Token {
         let mut identifier = String::new();
 
         while let Some(ch) = self.peek() {
-            if ch.is_ascii_alphabetic() || ch.is_ascii_digit() || ch == '_' {
+            if is_identifier_start(ch) || ch.is_ascii_digit() {
                 identifier.push(ch);
                 self.pos += 1;
             } else {
@@ -218,23 +700,49 @@ impl<'a> Lexer<'a> {
 
         match identifier.as_str() {
             "var" => Token::Var,
+            "class" => Token::Class,
+            "import" => Token::Import,
+            "export" => Token::Export,
+            "extends" => Token::Extends,
+            "super" => Token::Super,
+            "const" => Token::Const,
+            "break" => Token::Break,
+            "case" => Token::Case,
+            "catch" => Token::Catch,
+            "continue" => Token::Continue,
+            "debugger" => Token::Debugger,
+            "default" => Token::Default,
+            "do" => Token::Do,
+            "finally" => Token::Finally,
+            "for" => Token::For,
+            "switch" => Token::Switch,
+            "throw" => Token::Throw,
+            "try" => Token::Try,
+            "with" => Token::With,
             "if" => Token::If,
+            "in" => Token::In,
+            "instanceof" => Token::Instanceof,
+            "typeof" => Token::Typeof,
+            "void" => Token::Void,
+            "delete" => Token::Delete,
+            "new" => Token::New,
+            "await" => Token::Await,
             "else" => Token::Else,
             "while" => Token::While,
             "function" => Token::Function,
             "return" => Token::Return,
             "this" => Token::This,
             "null" => Token::Null,
-            "True" => Token::True,
-            "False" => Token::False,
+            "true" => Token::True,
+            "false" => Token::False,
             _ => Token::Identifier(identifier),
         }
     }
 
     fn next(&mut self) -> Option<char> {
         let ch = self.peek();
-        if ch.is_some() {
-            self.pos += 1;
+        if let Some(ch) = ch {
+            self.pos += ch.len_utf8();
         }
         ch
     }
@@ -242,36 +750,255 @@ impl<'a> Lexer<'a> {
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.peek() {
             if ch.is_whitespace() {
-                self.pos += 1;
+                self.pos += ch.len_utf8();
             } else {
                 break;
             }
         }
     }
     // manually added
+    // Whether `<!--` and `-->` start single-line comments, which is only the
+    // case for scripts.
+    fn html_comments(&self) -> bool {
+        self.options.source_type == SourceType::Script
+    }
+
+    // Whether only whitespace and comments precede the current position on
+    // its line, as required before an HTML-like `-->` comment.
+    fn at_line_start(&self) -> bool {
+        self.previous == Previous::Nothing
+            || self.source[self.last_end..self.pos].chars().any(is_line_terminator)
+    }
+
+    // Whether the `<` at the current position starts a JSX element: JSX is
+    // enabled, an expression may start here, and a tag name or the `>` of a
+    // fragment follows.
+    fn starts_jsx_tag(&self) -> bool {
+        self.options.jsx
+            && self.previous != Previous::ExpressionEnd
+            && self.peek_n(1).is_some_and(|next| is_identifier_start(next) || next == '>')
+            && !(self.options.typescript && self.starts_type_parameters())
+    }
+
+    // Whether the `<` at the current position opens the type parameters of a
+    // generic arrow function, as in `<T,>(x: T) => x` or `<T extends U>`,
+    // which TSX tells apart from a tag this way.
+    fn starts_type_parameters(&self) -> bool {
+        let rest = &self.source[self.pos + 1..];
+        let name_end = rest
+            .find(|ch: char| !(is_identifier_start(ch) || ch.is_ascii_digit()))
+            .unwrap_or(rest.len());
+        let after = rest[name_end..].trim_start();
+        name_end > 0 && (after.starts_with(',')
+            || (after.starts_with("extends")
+                && !after["extends".len()..].starts_with(|ch: char| {
+                    is_identifier_start(ch) || ch.is_ascii_digit()
+                })))
+    }
+
+    // Scans a token between `<` and `>` of a JSX tag.
+    fn scan_jsx_tag(&mut self) -> Token {
+        let ch = self.peek().unwrap();
+        if is_identifier_start(ch) {
+            let start = self.pos;
+            while self
+                .peek()
+                .is_some_and(|ch| is_identifier_start(ch) || ch.is_ascii_digit() || ch == '-')
+            {
+                self.pos += 1;
+            }
+            return Token::JSXIdentifier(self.source[start..self.pos].to_string());
+        }
+        match ch {
+            '"' | '\'' => {
+                // Attribute strings have no escapes, but may hold entities.
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|next| next != ch) {
+                    self.next();
+                }
+                let value = decode_entities(&self.source[start..self.pos]);
+                self.next();
+                Token::String(value)
+            }
+            '<' => {
+                self.pos += 1;
+                self.jsx.push(JsxContext::Tag { closing: false });
+                Token::Less
+            }
+            '/' => {
+                self.pos += 1;
+                if self.previous == Previous::Less {
+                    self.jsx.pop();
+                    self.jsx.push(JsxContext::Tag { closing: true });
+                }
+                Token::Slash
+            }
+            '>' => {
+                self.pos += 1;
+                let self_closing = self.previous == Previous::Slash;
+                match self.jsx.pop() {
+                    Some(JsxContext::Tag { closing: true }) => {
+                        if self.jsx.last() == Some(&JsxContext::Children) {
+                            self.jsx.pop();
+                        }
+                    }
+                    _ if self_closing => {}
+                    _ => self.jsx.push(JsxContext::Children),
+                }
+                Token::Greater
+            }
+            '{' => {
+                self.pos += 1;
+                self.jsx.push(JsxContext::Expression);
+                Token::LeftBrace
+            }
+            _ => self.next_token().unwrap_or_else(|| {
+                self.pos += ch.len_utf8();
+                Token::Illegal(ch)
+            }),
+        }
+    }
+
+    // Scans text, a `{` or a `<` between JSX tags.
+    fn scan_jsx_child(&mut self) -> Token {
+        match self.peek().unwrap() {
+            '<' => {
+                self.pos += 1;
+                self.jsx.push(JsxContext::Tag { closing: false });
+                Token::Less
+            }
+            '{' => {
+                self.pos += 1;
+                self.jsx.push(JsxContext::Expression);
+                Token::LeftBrace
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(ch) = self.peek().filter(|&ch| ch != '<' && ch != '{') {
+                    // `}` and `>` have to be written as `{'}'}` or `&gt;`.
+                    if ch == '}' || ch == '>' {
+                        let found = if ch == '}' { Token::RightBrace } else { Token::Greater };
+                        self.errors.push(ParseError {
+                            code: ErrorCode::InvalidCharacter,
+                            span: Span::new(self.pos, self.pos + 1),
+                            expected: "JSX text".to_string(),
+                            found,
+                            message: format!("Unexpected '{}' in JSX text.", ch),
+                        });
+                    }
+                    self.next();
+                }
+                Token::JSXText(decode_entities(&self.source[start..self.pos]))
+            }
+        }
+    }
+
+    // Pairs up braces of JavaScript code inside JSX, so that the `}` that
+    // closes an expression container returns to the tag or children.
+    fn track_jsx_braces(&mut self, token: &Token) {
+        match token {
+            Token::LeftBrace => self.jsx.push(JsxContext::Brace),
+            Token::RightBrace => {
+                self.jsx.pop();
+            }
+            _ => {}
+        }
+    }
+
     fn skip_comment(&mut self) {
+        let start = self.pos;
         while let Some(ch) = self.peek() {
             if ch != '\n' {
-                self.pos += 1;
+                self.pos += ch.len_utf8();
             } else {
                 self.pos += 1;
                 break;
             }
         }
+        self.check_flow_pragma(start);
     }
     // manually added
     fn skip_comment_block(&mut self) {
+        let start = self.pos;
         while let Some(ch) = self.peek() {
             if ch == '*' && self.peek_n(1) == Some('/') {
                 self.pos += 2;
                 break;
             } else {
-                self.pos += 1;
+                self.pos += ch.len_utf8();
+            }
+        }
+        self.check_flow_pragma(start);
+    }
+
+    // Records a `@flow` pragma in the comment that started at `start`, which
+    // only counts before the first token.
+    fn check_flow_pragma(&mut self, start: usize) {
+        let mut words = self.source[start..self.pos].split(|ch: char| ch.is_whitespace() || ch == '*' || ch == '/');
+        if self.previous == Previous::Nothing && words.any(|word| word == "@flow") {
+            self.flow_pragma = true;
+        }
+    }
+}
+
+fn is_identifier_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || ch == '$'
+}
+
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+// Named character references recognised in JSX text and attributes.
+const HTML_ENTITIES: &[(&str, char)] = &[
+    ("quot", '"'), ("amp", '&'), ("apos", '\''), ("lt", '<'), ("gt", '>'),
+    ("nbsp", '\u{a0}'), ("iexcl", '¡'), ("cent", '¢'), ("pound", '£'), ("yen", '¥'),
+    ("sect", '§'), ("copy", '©'), ("laquo", '«'), ("not", '¬'), ("shy", '\u{ad}'),
+    ("reg", '®'), ("deg", '°'), ("plusmn", '±'), ("micro", 'µ'), ("para", '¶'),
+    ("middot", '·'), ("raquo", '»'), ("iquest", '¿'), ("times", '×'), ("divide", '÷'),
+    ("ndash", '–'), ("mdash", '—'), ("lsquo", '‘'), ("rsquo", '’'), ("sbquo", '‚'),
+    ("ldquo", '“'), ("rdquo", '”'), ("bdquo", '„'), ("dagger", '†'), ("bull", '•'),
+    ("hellip", '…'), ("prime", '′'), ("lsaquo", '‹'), ("rsaquo", '›'), ("euro", '€'),
+    ("trade", '™'), ("larr", '←'), ("uarr", '↑'), ("rarr", '→'), ("darr", '↓'),
+    ("harr", '↔'), ("minus", '−'), ("infin", '∞'), ("ne", '≠'), ("le", '≤'),
+    ("ge", '≥'), ("hearts", '♥'), ("zwj", '\u{200d}'), ("zwnj", '\u{200c}'),
+];
+
+// Replaces `&name;`, `&#123;` and `&#x7B;` references in JSX text. Anything
+// that is not a known reference is kept as written.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let ch = reference.and_then(|name| {
+            if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(decimal) = name.strip_prefix('#') {
+                decimal.parse().ok().and_then(char::from_u32)
+            } else {
+                HTML_ENTITIES.iter().find(|(entity, _)| *entity == name).map(|(_, ch)| *ch)
+            }
+        });
+        match (ch, reference) {
+            (Some(ch), Some(name)) => {
+                decoded.push(ch);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
             }
         }
     }
+    decoded.push_str(rest);
+    decoded
 }
 
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -440,4 +1167,333 @@ mod tests {
         assert_eq!(token14, Some(Token::Identifier("z".to_string())));
         assert_eq!(token15, Some(Token::Semicolon));
     }
+
+    #[test]
+    fn test_illegal_character() {
+        let tokens: Vec<Token> = Lexer::new("a # b")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Illegal('#'),
+                Token::Identifier("b".to_string()),
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compound_operators() {
+        let tokens: Vec<Token> = Lexer::new("a **= b ** c % d >>>= e >>> f ?? g ??= h &&= i ||= j &= k |= l ^= m %= n in o instanceof p => q")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .filter(|token| !matches!(token, Token::Identifier(_)))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::StarStarEqual,
+                Token::StarStar,
+                Token::Percent,
+                Token::GreaterGreaterGreaterEqual,
+                Token::GreaterGreaterGreater,
+                Token::QuestionQuestion,
+                Token::QuestionQuestionEqual,
+                Token::AmpersandAmpersandEqual,
+                Token::PipePipeEqual,
+                Token::AmpersandEqual,
+                Token::PipeEqual,
+                Token::CaretEqual,
+                Token::PercentEqual,
+                Token::In,
+                Token::Instanceof,
+                Token::Arrow,
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_chaining_punctuator() {
+        let tokens: Vec<Token> = Lexer::new("a?.b ?.5:c")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::QuestionDot,
+                Token::Identifier("b".to_string()),
+                Token::Question,
+                Token::Number(0.5),
+                Token::Colon,
+                Token::Identifier("c".to_string()),
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_punctuator() {
+        let tokens: Vec<Token> = Lexer::new("a |> f(%)||b|=c")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::PipeGreater,
+                Token::Identifier("f".to_string()),
+                Token::LeftParen,
+                Token::Percent,
+                Token::RightParen,
+                Token::PipePipe,
+                Token::Identifier("b".to_string()),
+                Token::PipeEqual,
+                Token::Identifier("c".to_string()),
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let tokens: Vec<Token> = Lexer::new("[...xs, a..5]")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::LeftBracket,
+                Token::Ellipsis,
+                Token::Identifier("xs".to_string()),
+                Token::Comma,
+                Token::Identifier("a".to_string()),
+                Token::Dot,
+                Token::Number(0.5),
+                Token::RightBracket,
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_private_identifier() {
+        let tokens: Vec<Token> = Lexer::new("this.#x; #class in o; a # b")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::This,
+                Token::Dot,
+                Token::PrivateIdentifier("x".to_string()),
+                Token::Semicolon,
+                Token::PrivateIdentifier("class".to_string()),
+                Token::In,
+                Token::Identifier("o".to_string()),
+                Token::Semicolon,
+                Token::Identifier("a".to_string()),
+                Token::Illegal('#'),
+                Token::Identifier("b".to_string()),
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_at_sign() {
+        let tokens: Vec<Token> = Lexer::new("@dec class")
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::At,
+                Token::Identifier("dec".to_string()),
+                Token::Class,
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jsx_tokens() {
+        let options = ParseOptions {
+            jsx: true,
+            ..ParseOptions::default()
+        };
+        let source = "<a b-c='&amp;'>x &lt; y {z}</a> < 1";
+        let tokens: Vec<Token> = Lexer::with_options(source, &options)
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Less,
+                Token::JSXIdentifier("a".to_string()),
+                Token::JSXIdentifier("b-c".to_string()),
+                Token::Equal,
+                Token::String("&".to_string()),
+                Token::Greater,
+                Token::JSXText("x < y ".to_string()),
+                Token::LeftBrace,
+                Token::Identifier("z".to_string()),
+                Token::RightBrace,
+                Token::Less,
+                Token::Slash,
+                Token::JSXIdentifier("a".to_string()),
+                Token::Greater,
+                Token::Less,
+                Token::Number(1.0),
+                Token::EOF,
+            ]
+        );
+        assert_eq!(decode_entities("&#65;&#x42;&copy;&nope; & b"), "AB©&nope; & b");
+    }
+
+    #[test]
+    fn test_tsx_type_parameters() {
+        let options = ParseOptions {
+            jsx: true,
+            typescript: true,
+            ..ParseOptions::default()
+        };
+        let tokens: Vec<Token> = Lexer::with_options("<T,>(x) <T extends U> <T>", &options)
+            .tokenize()
+            .into_iter()
+            .map(|token| token.token)
+            .collect();
+        assert_eq!(tokens[..3], [Token::Less, Token::Identifier("T".to_string()), Token::Comma]);
+        assert_eq!(tokens[7..10], [Token::Less, Token::Identifier("T".to_string()), Token::Extends]);
+        // Anything else is still a tag.
+        assert_eq!(tokens[12..14], [Token::Less, Token::JSXIdentifier("T".to_string())]);
+    }
+
+    #[test]
+    fn test_flow_pragma() {
+        let mut lexer = Lexer::new("/**\n * @flow strict\n */\nlet x;");
+        lexer.tokenize();
+        assert!(lexer.flow_pragma());
+        let mut lexer = Lexer::new("// @noflow\nlet x; // @flow");
+        lexer.tokenize();
+        assert!(!lexer.flow_pragma());
+    }
+
+    #[test]
+    fn test_template_literals() {
+        let mut lexer = Lexer::new("`a` `b${ {c} }d${e}\\`f`");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|token| token.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Template("a".to_string()),
+                Token::TemplateHead("b".to_string()),
+                Token::LeftBrace,
+                Token::Identifier("c".to_string()),
+                Token::RightBrace,
+                Token::TemplateMiddle("d".to_string()),
+                Token::Identifier("e".to_string()),
+                Token::TemplateTail("\\`f".to_string()),
+                Token::EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decimal_numbers() {
+        let mut lexer = Lexer::new("1.5 .5 1. 2e3 1.5E-2 1e+1_0 1e x");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|token| token.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number(1.5),
+                Token::Number(0.5),
+                Token::Number(1.0),
+                Token::Number(2000.0),
+                Token::Number(0.015),
+                Token::Number(1e10),
+                Token::Number(1.0),
+                Token::Identifier("e".to_string()),
+                Token::Identifier("x".to_string()),
+                Token::EOF,
+            ]
+        );
+        assert!(lexer.take_errors().is_empty());
+
+        // The second `.` of `1..toString()` is a member access.
+        let mut lexer = Lexer::new("1..toString()");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|token| token.token).collect();
+        assert_eq!(
+            &tokens[..3],
+            &[Token::Number(1.0), Token::Dot, Token::Identifier("toString".to_string())]
+        );
+        assert!(lexer.take_errors().is_empty());
+
+        let mut lexer = Lexer::new("1.2.3;");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|token| token.token).collect();
+        assert_eq!(tokens, vec![Token::Number(1.2), Token::Semicolon, Token::EOF]);
+        let errors = lexer.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::InvalidNumber);
+        assert_eq!(errors[0].span, Span::new(3, 5));
+        assert_eq!(errors[0].message, "A number literal can only contain one '.'.");
+    }
+
+    #[test]
+    fn test_numeric_separators() {
+        let mut lexer = Lexer::new("1_000_000 1__0 2_ x");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|token| token.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number(1000000.0),
+                Token::Number(10.0),
+                Token::Number(2.0),
+                Token::Identifier("x".to_string()),
+                Token::EOF,
+            ]
+        );
+        let errors = lexer.take_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| error.code == ErrorCode::InvalidNumber));
+        assert_eq!(errors[0].span, Span::new(11, 13));
+        assert_eq!(errors[1].span, Span::new(16, 17));
+        assert_eq!(
+            errors[0].message,
+            "Numeric separators must be a single '_' between digits."
+        );
+
+        let options = ParseOptions {
+            ecma_version: 2020,
+            ..ParseOptions::default()
+        };
+        let mut lexer = Lexer::with_options("x = 1_000;", &options);
+        lexer.tokenize();
+        let errors = lexer.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::UnsupportedSyntax);
+        assert_eq!(errors[0].span, Span::new(4, 9));
+        assert_eq!(
+            errors[0].message,
+            "ES2021 is required for numeric separators, but ecma_version is 2020."
+        );
+    }
 }
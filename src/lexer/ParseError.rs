@@ -0,0 +1,182 @@
+use std::fmt;
+
+use crate::lexer::Token::{Span, Token};
+
+/// Stable identifiers for the kinds of errors the parser reports.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ErrorCode {
+    /// A specific punctuator or keyword was required.
+    UnexpectedToken,
+    ExpectedIdentifier,
+    ExpectedExpression,
+    /// A statement was not terminated by `;` and no semicolon can be inserted.
+    MissingSemicolon,
+    /// The source contains a character that does not start any token.
+    InvalidCharacter,
+    /// `??` combined with `||` or `&&` without parentheses.
+    MixedCoalesce,
+    /// `delete` applied to a plain identifier in strict mode code, or to a
+    /// private member.
+    StrictDelete,
+    /// `++` or `--` applied to something that cannot be assigned to.
+    InvalidUpdateTarget,
+    /// A unary expression used as the left operand of `**`.
+    UnaryBeforeExponent,
+    /// `?.` in the callee of a `new` expression.
+    InvalidOptionalChain,
+    /// A getter with parameters, or a setter without exactly one.
+    InvalidAccessorParameters,
+    /// A `with` statement in strict mode code.
+    StrictWith,
+    /// A line break between `throw` and its expression.
+    NewlineAfterThrow,
+    /// More than one `default` clause in a `switch`.
+    DuplicateDefault,
+    /// An expression that cannot be assigned to on the left of `=` or in a
+    /// `for`…`in`/`of` head.
+    InvalidAssignmentTarget,
+    /// A `const` declaration without an initialiser.
+    MissingInitializer,
+    /// `let` used as a name in a `let` or `const` declaration.
+    LetBinding,
+    /// A declaration in a `for`...`in`/`of` head with several bindings or an
+    /// initialiser.
+    InvalidForDeclaration,
+    /// `{ a = 1 }` in an object literal that is not destructured.
+    ShorthandInitializer,
+    /// A line break between an arrow function's parameters and `=>`.
+    NewlineBeforeArrow,
+    /// A class body with more than one constructor.
+    DuplicateConstructor,
+    /// `super()` outside a derived class constructor, or `super.x` outside
+    /// a method.
+    InvalidSuper,
+    /// A `#name` that no enclosing class declares.
+    UndeclaredPrivateName,
+    /// An `import` or `export` declaration that is not at the top level of
+    /// a module.
+    MisplacedImportExport,
+    /// Syntax introduced after the targeted `ecma_version`.
+    UnsupportedSyntax,
+    /// A name declared twice in a scope where that is not allowed, such as
+    /// two `let` declarations or a `let` redeclaring a `var`.
+    Redeclaration,
+    /// The same parameter name twice in a strict, arrow, method or
+    /// non-simple parameter list.
+    DuplicateParameter,
+    /// A `return` outside any function.
+    IllegalReturn,
+    /// A `break` or `continue` to an undefined label, or outside any loop or
+    /// switch it could leave.
+    InvalidJump,
+    /// `yield` or `await` used as a name where it is reserved, or as an
+    /// operator in a parameter list.
+    InvalidYieldOrAwait,
+    /// A number literal with a leading zero in strict mode code.
+    LegacyOctal,
+    /// `eval` or `arguments` bound or assigned in strict mode code, or
+    /// `arguments` used in a class field initialiser or static block.
+    StrictEvalArguments,
+    /// Syntax that `ParseOptions` has not enabled, such as decorators.
+    FeatureNotEnabled,
+    /// A JSX closing tag whose name differs from its opening tag.
+    MismatchedClosingTag,
+    /// A malformed number literal, such as `1__0`, `1_` or `1.2.3`.
+    InvalidNumber,
+    /// A module exporting the same name twice, including two `export
+    /// default` declarations.
+    DuplicateExport,
+    /// `export { a }` naming a binding the module does not declare.
+    UndeclaredExport,
+    /// A class member named `constructor` that is not a plain method, or a
+    /// static member named `prototype`.
+    ReservedMemberName,
+    /// `new.target` outside any function or class body.
+    IllegalNewTarget,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedToken => "E0001",
+            ErrorCode::ExpectedIdentifier => "E0002",
+            ErrorCode::ExpectedExpression => "E0003",
+            ErrorCode::MissingSemicolon => "E0004",
+            ErrorCode::InvalidCharacter => "E0005",
+            ErrorCode::MixedCoalesce => "E0006",
+            ErrorCode::StrictDelete => "E0007",
+            ErrorCode::InvalidUpdateTarget => "E0008",
+            ErrorCode::UnaryBeforeExponent => "E0009",
+            ErrorCode::InvalidOptionalChain => "E0010",
+            ErrorCode::InvalidAccessorParameters => "E0011",
+            ErrorCode::StrictWith => "E0012",
+            ErrorCode::NewlineAfterThrow => "E0013",
+            ErrorCode::DuplicateDefault => "E0014",
+            ErrorCode::InvalidAssignmentTarget => "E0015",
+            ErrorCode::MissingInitializer => "E0016",
+            ErrorCode::LetBinding => "E0017",
+            ErrorCode::InvalidForDeclaration => "E0018",
+            ErrorCode::ShorthandInitializer => "E0019",
+            ErrorCode::NewlineBeforeArrow => "E0020",
+            ErrorCode::DuplicateConstructor => "E0021",
+            ErrorCode::InvalidSuper => "E0022",
+            ErrorCode::UndeclaredPrivateName => "E0023",
+            ErrorCode::MisplacedImportExport => "E0024",
+            ErrorCode::UnsupportedSyntax => "E0025",
+            ErrorCode::Redeclaration => "E0026",
+            ErrorCode::DuplicateParameter => "E0027",
+            ErrorCode::IllegalReturn => "E0028",
+            ErrorCode::InvalidJump => "E0029",
+            ErrorCode::InvalidYieldOrAwait => "E0030",
+            ErrorCode::LegacyOctal => "E0031",
+            ErrorCode::StrictEvalArguments => "E0032",
+            ErrorCode::FeatureNotEnabled => "E0033",
+            ErrorCode::MismatchedClosingTag => "E0034",
+            ErrorCode::InvalidNumber => "E0035",
+            ErrorCode::DuplicateExport => "E0036",
+            ErrorCode::UndeclaredExport => "E0037",
+            ErrorCode::ReservedMemberName => "E0038",
+            ErrorCode::IllegalNewTarget => "E0039",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub code: ErrorCode,
+    pub span: Span,
+    /// What the parser was looking for, e.g. `';'` or `expression`.
+    pub expected: String,
+    pub found: Token,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Describes the offending token the way it appears in messages.
+    pub fn found_description(&self) -> String {
+        match &self.found {
+            Token::EOF => self.found.to_string(),
+            token => format!("'{}'", token),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} Expected {}, found {}.",
+            self.message,
+            self.expected,
+            self.found_description()
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
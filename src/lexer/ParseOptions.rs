@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+/// The goal symbol source text is parsed with.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SourceType {
+    #[default]
+    Script,
+    /// Module code is always strict, may use `import`, `export` and top-level
+    /// `await`, and has no HTML-like comments.
+    Module,
+}
+
+/// Which proposal `@decorator` syntax follows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DecoratorSyntax {
+    /// TypeScript's `experimentalDecorators` and Babel's legacy mode: `@` may
+    /// be followed by any chain of member accesses and calls, and
+    /// decorators go before `export`.
+    Legacy,
+    /// The 2023 proposal: `@` takes a dotted name with an optional final
+    /// call, or a parenthesised expression. Decorators may also follow
+    /// `export`, and `accessor` declares auto-accessor fields.
+    Proposal2023,
+}
+
+/// A proposal that is not part of any ECMAScript edition yet. Each one is
+/// enabled on its own by adding it to `ParseOptions::features`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Feature {
+    /// `using` and `await using` declarations.
+    ExplicitResourceManagement,
+    /// `import defer * as ns from "m"` and `import.defer("m")`.
+    ImportDefer,
+    /// `import source x from "m"` and `import.source("m")`.
+    SourcePhaseImports,
+    /// The Hack-style pipeline `x |> f(%)`, where `%` refers to the value on
+    /// the left.
+    PipelineOperator,
+    /// `do { ... }` blocks used as expressions.
+    DoExpressions,
+    /// `throw` used as an expression, as in `x ?? throw new Error()`.
+    ThrowExpressions,
+}
+
+impl Feature {
+    fn description(&self) -> &'static str {
+        match self {
+            Feature::ExplicitResourceManagement => "'using' declarations",
+            Feature::ImportDefer => "deferred imports",
+            Feature::SourcePhaseImports => "source phase imports",
+            Feature::PipelineOperator => "the pipeline operator",
+            Feature::DoExpressions => "'do' expressions",
+            Feature::ThrowExpressions => "'throw' expressions",
+        }
+    }
+}
+
+/// The newest ECMAScript edition the parser knows, and the default target.
+pub const LATEST_ECMA_VERSION: u32 = 2025;
+
+/// Options shared by the lexer and the parser.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseOptions {
+    pub source_type: SourceType,
+    /// The year of the newest ECMAScript edition whose syntax is accepted,
+    /// e.g. `2017`. Use `5` for ES5.
+    pub ecma_version: u32,
+    /// Enables decorators with the given syntax.
+    pub decorators: Option<DecoratorSyntax>,
+    /// Lexes `<` at the start of an expression as a JSX tag.
+    pub jsx: bool,
+    /// Accepts TypeScript syntax: type annotations, interfaces, enums,
+    /// namespaces and the other TypeScript-only declarations. With `jsx`
+    /// this parses TSX, where `<T>x` type assertions are not available.
+    pub typescript: bool,
+    /// Parses a `.d.ts` declaration file, where every statement is an
+    /// ambient declaration as if written with `declare`. Only takes effect
+    /// together with `typescript`.
+    pub declaration_file: bool,
+    /// Accepts Flow syntax: type annotations, type aliases, opaque types and
+    /// type imports. `lexer::parse` also turns this on for sources with a
+    /// `// @flow` pragma. Not meant to be combined with `typescript`.
+    pub flow: bool,
+    /// Proposals whose syntax is accepted. Without them, the syntax is still
+    /// parsed but reported as not enabled.
+    pub features: HashSet<Feature>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            source_type: SourceType::Script,
+            ecma_version: LATEST_ECMA_VERSION,
+            decorators: None,
+            jsx: false,
+            typescript: false,
+            declaration_file: false,
+            flow: false,
+            features: HashSet::new(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The error message for using `feature`, introduced in ES`version`, when
+    /// the targeted edition is older.
+    pub(crate) fn unsupported(&self, feature: &str, version: u32) -> Option<String> {
+        (self.ecma_version < version).then(|| {
+            format!(
+                "ES{} is required for {}, but ecma_version is {}.",
+                version, feature, self.ecma_version
+            )
+        })
+    }
+
+    /// The error message for using `feature` when it is not enabled.
+    pub(crate) fn disabled(&self, feature: Feature) -> Option<String> {
+        (!self.features.contains(&feature)).then(|| {
+            format!(
+                "Support for {} is not enabled; add Feature::{:?} to ParseOptions.features.",
+                feature.description(),
+                feature
+            )
+        })
+    }
+}
@@ -1,305 +1,10339 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
 use crate::lexer::AstNode::AstNode;
-use crate::lexer::Token::Token;
+use crate::lexer::Lexer::{Lexer, LexerState};
+use crate::lexer::ParseError::{ErrorCode, ParseError};
+use crate::lexer::ParseOptions::{DecoratorSyntax, Feature, ParseOptions, SourceType};
+use crate::lexer::Token::{Span, SpannedToken, Token};
+
+type ParseResult<T> = Result<T, ParseError>;
+// The type parameters, parameters and return type of a TypeScript signature.
+type Signature = (Option<Box<AstNode>>, Vec<AstNode>, Option<Box<AstNode>>);
+// The parameters, return type and body of a function, which TypeScript
+// signatures leave out.
+type FunctionParts = (Vec<AstNode>, Option<Box<AstNode>>, Option<AstNode>);
+
+pub struct Parser {
+    tokens: Vec<SpannedToken>,
+    current: usize,
+    errors: Vec<ParseError>,
+    options: ParseOptions,
+    // Whether the top-level statements parsed so far were all directives.
+    prologue: bool,
+    // Whether the code being parsed is strict mode code, and whether `await`
+    // is an operator rather than an identifier.
+    strict: bool,
+    in_async: bool,
+    // Whether `yield` is an operator rather than an identifier.
+    in_generator: bool,
+    // Cleared while parsing a `for` head initialiser, where `in` starts a
+    // `for`...`in` loop instead of being a binary operator.
+    allow_in: bool,
+    // Token indices of `=` in shorthand properties such as `{ a = 1 }`,
+    // which are only valid once the object literal becomes a pattern.
+    cover_initializers: Vec<usize>,
+    // Whether `super.x` and `super()` may appear in the current function.
+    super_property: bool,
+    super_call: bool,
+    // One entry per class body being parsed, innermost last.
+    class_scopes: Vec<ClassScope>,
+    // Whether the code is in a TypeScript ambient context, inside `declare`
+    // or a declaration file, where functions have no bodies.
+    ambient: bool,
+    // Set while parsing the parameters of a constructor, which may declare
+    // TypeScript parameter properties.
+    parameter_properties: bool,
+    // Cleared while parsing the `extends` type of a conditional type, where
+    // another conditional type needs parentheses.
+    conditional_types: bool,
+    // Changes made to the tokens, such as splitting `>>` to close type
+    // arguments, so that rewinding can undo them.
+    token_edits: Vec<TokenEdit>,
+    // The lexer state after each token, if the parser was given them, kept
+    // in line with the tokens when they change.
+    states: Vec<LexerState>,
+    // The source text and the errors reported while lexing it, if the parser
+    // was given them, for lexing tokens again where the lexer guessed wrong.
+    source: Option<String>,
+    lexer_errors: Vec<ParseError>,
+    // The number of `%` topic references in the pipeline body being parsed,
+    // or `None` outside of one.
+    topic_references: Option<usize>,
+    // The index of the token that starts the consequent of the innermost
+    // conditional expression being parsed, where `(b): c => d` is only an
+    // arrow function if a `:` for the conditional follows it.
+    consequent_start: Option<usize>,
+}
 
-struct Parser {
-    tokens: Vec<Token>,
+/// Parser state saved before speculatively parsing TypeScript syntax that
+/// may turn out to be something else, such as the type arguments in
+/// `f<T>(x)`, which could also be comparisons.
+struct Checkpoint {
     current: usize,
+    errors: usize,
+    cover_initializers: usize,
+    token_edits: usize,
+    class_scopes: usize,
+    private_references: usize,
+}
+
+/// Tokens from `index` that the parser replaced with `inserted` others,
+/// together with their lexer states, and likewise the lexer errors from
+/// `error_index` that were replaced with `inserted_errors` others.
+struct TokenEdit {
+    index: usize,
+    tokens: Vec<SpannedToken>,
+    states: Vec<LexerState>,
+    inserted: usize,
+    error_index: usize,
+    errors: Vec<ParseError>,
+    inserted_errors: usize,
+}
+
+/// The modifiers before a class member. JavaScript only has `static`; the
+/// others are TypeScript's, except `accessor`, which also comes with the
+/// 2023 decorators proposal.
+#[derive(Default)]
+struct ClassModifiers {
+    accessibility: Option<String>,
+    r#static: bool,
+    r#abstract: bool,
+    r#override: bool,
+    readonly: bool,
+    declare: bool,
+    accessor: bool,
+}
+
+/// Private names declared in a class body and the `#name` references made
+/// inside it, which are checked once the whole body has been parsed.
+#[derive(Default)]
+struct ClassScope {
+    declared: HashSet<String>,
+    referenced: Vec<(String, usize)>,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Parser {
-        Parser { tokens, current: 0 }
+    pub fn new(mut tokens: Vec<SpannedToken>) -> Parser {
+        if tokens.last().map(|last| &last.token) != Some(&Token::EOF) {
+            let end = tokens.last().map_or(0, |last| last.span.end);
+            tokens.push(SpannedToken {
+                token: Token::EOF,
+                span: Span::new(end, end),
+                newline_before: false,
+            });
+        }
+        Parser {
+            tokens,
+            current: 0,
+            errors: Vec::new(),
+            options: ParseOptions::default(),
+            prologue: true,
+            strict: false,
+            in_async: false,
+            in_generator: false,
+            allow_in: true,
+            cover_initializers: Vec::new(),
+            super_property: false,
+            super_call: false,
+            class_scopes: Vec::new(),
+            ambient: false,
+            parameter_properties: false,
+            conditional_types: true,
+            token_edits: Vec::new(),
+            states: Vec::new(),
+            source: None,
+            lexer_errors: Vec::new(),
+            topic_references: None,
+            consequent_start: None,
+        }
     }
 
-    fn parse(&mut self) -> AstNode {
-        let mut nodes = Vec::new();
-        while !self.is_at_end() {
-            nodes.push(self.declaration());
+    /// Creates a parser for the goal symbol given by `options`. Module code
+    /// is strict and may use `await` at the top level.
+    pub fn with_options(tokens: Vec<SpannedToken>, options: ParseOptions) -> Parser {
+        let module = options.source_type == SourceType::Module;
+        let ambient = options.typescript && options.declaration_file;
+        Parser {
+            options,
+            strict: module,
+            in_async: module,
+            ambient,
+            ..Parser::new(tokens)
         }
-        AstNode::Program { body: nodes }
     }
 
-    fn declaration(&mut self) -> AstNode {
-        if self.match_token(Token::Var) {
-            self.var_declaration()
-        } else if self.match_token(Token::Function) {
-            self.function("function")
-        } else {
-            self.statement()
+    /// Like `with_options`, for the tokens of `source` together with the
+    /// lexer state after each of them and the lexer's errors, as
+    /// `Lexer::tokenize_with_states` and `Lexer::take_errors` return them.
+    /// Where the lexer took a `<` after `)`, `]` or `}` for an operator but
+    /// an expression starts, as in `if (x) <div />`, the parser lexes the
+    /// tokens from there again as JSX.
+    pub(crate) fn with_source(
+        source: String,
+        tokens: Vec<SpannedToken>,
+        states: Vec<LexerState>,
+        lexer_errors: Vec<ParseError>,
+        options: ParseOptions,
+    ) -> Parser {
+        Parser {
+            states,
+            source: Some(source),
+            lexer_errors,
+            ..Parser::with_options(tokens, options)
         }
     }
 
-    fn var_declaration(&mut self) -> AstNode {
-        let id = self.consume(Token::Identifier, "Expect variable name.");
-        let init = if self.match_token(Token::Equal) {
-            Some(self.expression())
+    pub fn parse(&mut self) -> Result<AstNode, Vec<ParseError>> {
+        let (program, errors) = self.parse_recovering();
+        if errors.is_empty() {
+            Ok(program)
         } else {
-            None
-        };
-        self.consume(Token::Semicolon, "Expect ';' after variable declaration.");
-        AstNode::VariableDeclaration {
-            id: Box::new(AstNode::Identifier(id.lexeme)),
-            init: Box::new(init.unwrap_or(AstNode::NumberLiteral(0.0))),
+            Err(errors)
         }
     }
 
-    fn function(&mut self, kind: &str) -> AstNode {
-        let name = self.consume(Token::Identifier, &format!("Expect {} name.", kind));
-        self.consume(
-            Token::LeftParen,
-            &format!("Expect '(' after {} name.", kind),
-        );
-        let mut parameters = Vec::new();
-        if !self.check(Token::RightParen) {
-            loop {
-                parameters.push(self.consume(Token::Identifier, "Expect parameter name."));
-                if !self.match_token(Token::Comma) {
-                    break;
-                }
-            }
+    /// Parses the whole input even if it contains syntax errors. Statements
+    /// that fail to parse are replaced by `AstNode::Error` and parsing resumes
+    /// at the next statement boundary.
+    pub fn parse_recovering(&mut self) -> (AstNode, Vec<ParseError>) {
+        let mut nodes = Vec::new();
+        while let Some(node) = self.parse_statement() {
+            nodes.push(node);
         }
-        self.consume(Token::RightParen, "Expect ')' after parameters.");
-        self.consume(
-            Token::LeftBrace,
-            &format!("Expect '{{' before {} body.", kind),
-        );
-        let body = self.block();
-        AstNode::FunctionDeclaration {
-            id: Box::new(AstNode::Identifier(name.lexeme)),
-            params: parameters
-                .iter()
-                .map(|param| AstNode::Identifier(param.lexeme))
-                .collect(),
-            body: Box::new(body),
+        (AstNode::Program { body: nodes }, self.take_errors())
+    }
+
+    /// Parses a single top-level statement starting at the current token, or
+    /// returns `None` at the end of input.
+    pub(crate) fn parse_statement(&mut self) -> Option<AstNode> {
+        if self.is_at_end() {
+            return None;
         }
+        let start = self.current;
+        let statement = self.recovering(Self::try_module_item);
+        let mut prologue = self.prologue;
+        let statement = self.directive(statement, start, &mut prologue);
+        self.prologue = prologue;
+        Some(statement)
     }
 
-    fn statement(&mut self) -> AstNode {
-        if self.match_token(Token::LeftBrace) {
-            self.block()
-        } else if self.match_token(Token::If) {
-            self.if_statement()
-        } else if self.match_token(Token::Return) {
-            self.return_statement()
-        } else {
-            self.expression_statement()
+    /// Restores the state that parsing `previous`, the top-level statements
+    /// before the current position, leaves behind: whether the directive
+    /// prologue is still open and whether it made the program strict.
+    pub(crate) fn resume_after(&mut self, previous: &[AstNode]) {
+        let (prologue, strict) = prologue_after(previous);
+        self.prologue = prologue;
+        self.strict |= strict;
+    }
+
+    /// Whether the parser is in the state that parsing `previous` left
+    /// behind, so that the statements that followed them parse as before.
+    pub(crate) fn continues_after(&self, previous: &[AstNode]) -> bool {
+        let (prologue, strict) = prologue_after(previous);
+        let module = self.options.source_type == SourceType::Module;
+        self.prologue == prologue && self.strict == (strict || module)
+    }
+
+    /// Returns the errors reported since the last call.
+    pub(crate) fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Index of the next token to be consumed.
+    pub(crate) fn position(&self) -> usize {
+        self.current
+    }
+
+    /// Moves the cursor to the token at `index`.
+    pub(crate) fn seek(&mut self, index: usize) {
+        self.current = index;
+    }
+
+    /// The number of tokens the parser has added, by splitting tokens or
+    /// lexing them again, less the number it has removed.
+    pub(crate) fn inserted_tokens(&self) -> isize {
+        self.token_edits
+            .iter()
+            .map(|edit| edit.inserted as isize - edit.tokens.len() as isize)
+            .sum()
+    }
+
+    /// The index after the last token the parser has added by splitting
+    /// tokens or lexing them again, or 0 if it has changed none.
+    pub(crate) fn edited_end(&self) -> usize {
+        let mut end = 0;
+        for edit in &self.token_edits {
+            let edit_end = edit.index + edit.inserted;
+            end = if edit.index < end {
+                // The edit moved the end of the ones before it.
+                let moved = end as isize + edit.inserted as isize - edit.tokens.len() as isize;
+                edit_end.max(moved as usize)
+            } else {
+                edit_end
+            };
         }
+        end
+    }
+
+    /// What was given to `with_source`, with the changes the parser made to
+    /// the tokens, their states and the lexer errors.
+    pub(crate) fn into_parts(
+        self,
+    ) -> (String, Vec<SpannedToken>, Vec<LexerState>, Vec<ParseError>) {
+        (
+            self.source.unwrap_or_default(),
+            self.tokens,
+            self.states,
+            self.lexer_errors,
+        )
+    }
+
+    fn declaration(&mut self) -> AstNode {
+        self.recovering(Self::try_declaration)
     }
 
-    fn parse_number(&mut self) -> AstNode {
+    /// Runs `parse` for one statement, turning a failure into
+    /// `AstNode::Error` and skipping to the next statement boundary.
+    fn recovering(&mut self, parse: fn(&mut Self) -> ParseResult<AstNode>) -> AstNode {
         let start = self.current;
-        while is_digit(self.peek()) {
-            self.advance();
+        let cover_start = self.cover_initializers.len();
+        let result = parse(self);
+        // Shorthand initialisers left over were never destructured.
+        let cover_initializers = self.cover_initializers.split_off(cover_start);
+        match result {
+            Ok(node) => {
+                for index in cover_initializers {
+                    let error = self.error_at(
+                        index,
+                        ErrorCode::ShorthandInitializer,
+                        "':'",
+                        "Invalid shorthand property initializer.",
+                    );
+                    self.errors.push(error);
+                }
+                node
+            }
+            Err(error) => {
+                self.errors.push(error);
+                self.synchronize(start);
+                AstNode::Error
+            }
         }
-        if self.peek() == '.' && is_digit(self.peek_next()) {
+    }
+
+    /// Skips to the next likely statement boundary: past a `;`, or before a
+    /// `}` or a keyword that starts a statement. At least one token is
+    /// skipped when the statement failed on its first token.
+    fn synchronize(&mut self, start: usize) {
+        if self.current == start {
             self.advance();
-            while is_digit(self.peek()) {
-                self.advance();
+        }
+        while !self.is_at_end() {
+            if self.previous() == &Token::Semicolon {
+                return;
+            }
+            match self.peek() {
+                Token::RightBrace
+                | Token::Var
+                | Token::Const
+                | Token::Function
+                | Token::Class
+                | Token::If
+                | Token::For
+                | Token::While
+                | Token::Do
+                | Token::Switch
+                | Token::Try
+                | Token::Throw
+                | Token::Return
+                | Token::Break
+                | Token::Continue => return,
+                _ => {
+                    self.advance();
+                }
             }
         }
-        let value = self.source[start..self.current].parse().unwrap();
-        AstNode::NumberLiteral(value)
     }
 
-    fn parse_string(&mut self) -> AstNode {
-        let start = self.current + 1;
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\\' {
-                self.advance();
+    /// Marks `statement`, which starts at token `start`, as a directive if it
+    /// continues the directive prologue of a program or function body. A
+    /// `"use strict"` directive makes the rest of the body strict.
+    fn directive(&mut self, statement: AstNode, start: usize, in_prologue: &mut bool) -> AstNode {
+        if !*in_prologue {
+            return statement;
+        }
+        // A parenthesised string is an ordinary expression statement.
+        let directive = match (&statement, &self.tokens[start].token) {
+            (AstNode::ExpressionStatement { expression, .. }, Token::String(_)) => {
+                match &**expression {
+                    AstNode::StringLiteral(value) => Some(value.clone()),
+                    _ => None,
+                }
             }
-            self.advance();
+            _ => None,
+        };
+        let Some(directive) = directive else {
+            *in_prologue = false;
+            return statement;
+        };
+        if directive == "use strict" {
+            self.strict = true;
         }
-        if self.is_at_end() {
-            // Handle unterminated string error
+        AstNode::ExpressionStatement {
+            expression: Box::new(AstNode::StringLiteral(directive.clone())),
+            directive: Some(directive),
         }
-        let value = self.source[start..self.current].to_string();
-        self.advance();
-        AstNode::StringLiteral(value)
     }
 
-    fn parse_identifier(&mut self) -> AstNode {
-        let start = self.current;
-        while is_alphanumeric(self.peek()) {
+    /// Parses a statement at the top level, where `import` and `export`
+    /// declarations may also appear.
+    fn try_module_item(&mut self) -> ParseResult<AstNode> {
+        if self.options.source_type == SourceType::Script {
+            self.reject_using("'using' declarations are not allowed at the top level of a script.");
+            self.try_declaration()
+        } else if self.is_import_declaration() {
             self.advance();
+            self.import_declaration()
+        } else if self.match_token(Token::Export) {
+            self.export_declaration(Vec::new())
+        } else if self.check(&Token::At) {
+            self.decorated_declaration(true)
+        } else {
+            self.try_declaration()
         }
-        let value = self.source[start..self.current].to_string();
-        AstNode::Identifier(value)
     }
 
-    fn parse_call_expression(&mut self) -> AstNode {
-        let callee = self.parse_primary();
-        let mut arguments = vec![];
-        while self.peek() == '(' {
+    fn try_declaration(&mut self) -> ParseResult<AstNode> {
+        if let Some(declaration) = self
+            .ts_declaration()
+            .or_else(|| self.flow_declaration())
+            .or_else(|| self.flow_declare())
+        {
+            declaration
+        } else if let Some(kind) = self.match_variable_kind() {
+            self.var_declaration(kind)
+        } else if self.match_token(Token::Function) {
+            self.function(false, true)
+        } else if self.match_token(Token::Class) {
+            self.class(true, Vec::new())
+        } else if self.check(&Token::At) {
+            self.decorated_declaration(false)
+        } else if self.is_async_function() {
             self.advance();
-            arguments.push(self.parse_expression());
-            if self.peek() != ')' {
-                // Handle missing ')' error
-            }
             self.advance();
+            self.function(true, true)
+        } else {
+            // `import` and `export` are allowed in declaration files.
+            let is_module_item = self.is_import_declaration() || self.check(&Token::Export);
+            if self.ambient && !self.check(&Token::Semicolon) && !is_module_item {
+                let error = self.error(
+                    ErrorCode::UnexpectedToken,
+                    "declaration",
+                    "Statements are not allowed in ambient contexts.",
+                );
+                self.errors.push(error);
+            }
+            self.statement()
         }
-        AstNode::CallExpression {
-            callee: Box::new(callee),
-            arguments,
+    }
+
+    /// Parses decorators and the class declaration they apply to, which at
+    /// the top level of a module may be exported.
+    fn decorated_declaration(&mut self, allow_export: bool) -> ParseResult<AstNode> {
+        let decorators = self.decorators()?;
+        if allow_export && self.match_token(Token::Export) {
+            return self.export_declaration(decorators);
         }
+        self.consume(Token::Class, "Expect 'class' after decorators.")?;
+        self.class(true, decorators)
     }
 
-    fn parse_expression(&mut self) -> AstNode {
-        self.parse_assignment()
+    /// Parses any `@decorator`s before a class or class member.
+    fn decorators(&mut self) -> ParseResult<Vec<AstNode>> {
+        let mut decorators = Vec::new();
+        while self.check(&Token::At) {
+            if self.options.decorators.is_none() {
+                let error = self.error(
+                    ErrorCode::FeatureNotEnabled,
+                    "class or class member",
+                    "Decorators are not enabled.",
+                );
+                self.errors.push(error);
+            }
+            self.advance();
+            let expression = self.decorator_expression()?;
+            decorators.push(AstNode::Decorator {
+                expression: Box::new(expression),
+            });
+        }
+        Ok(decorators)
     }
 
-    fn parse_assignment(&mut self) -> AstNode {
-        let left = self.parse_ternary();
+    /// Parses what follows `@`: a parenthesised expression, or a name with
+    /// member accesses and calls. The 2023 syntax only allows one call, at
+    /// the end, and no computed member accesses, so a `[` starts the
+    /// decorated member's computed key.
+    fn decorator_expression(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::LeftParen) {
+            let expression = self.allowing_in(Self::parse_expression)?;
+            self.consume(Token::RightParen, "Expect ')' after decorator expression.")?;
+            return Ok(expression);
+        }
+        let legacy = self.options.decorators == Some(DecoratorSyntax::Legacy);
+        let mut expression =
+            AstNode::identifier(self.consume_identifier("Expect decorator name.")?);
+        loop {
+            if self.match_token(Token::Dot) {
+                expression = self.parse_member_property(expression, false)?;
+            } else if legacy && self.match_token(Token::LeftBracket) {
+                let property = self.allowing_in(Self::parse_expression)?;
+                self.consume(Token::RightBracket, "Expect ']' after computed property.")?;
+                expression = AstNode::MemberExpression {
+                    object: Box::new(expression),
+                    property: Box::new(property),
+                    computed: true,
+                    optional: false,
+                };
+            } else if self.match_token(Token::LeftParen) {
+                expression = AstNode::CallExpression {
+                    callee: Box::new(expression),
+                    arguments: self.allowing_in(Self::parse_arguments)?,
+                    optional: false,
+                    type_arguments: None,
+                };
+                if !legacy {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(expression)
+    }
 
-        if self.match_token(Token::Equal)
-            || self.match_token(Token::PlusEqual)
-            || self.match_token(Token::MinusEqual)
-            || self.match_token(Token::StarEqual)
-            || self.match_token(Token::SlashEqual)
-        {
-            let operator = self.previous().to_string();
-            let right = self.parse_assignment();
-            return AstNode::AssignmentExpression {
-                operator,
-                left: Box::new(left),
-                right: Box::new(right),
+    /// Parses the decorators of `export @decorator class`, which may be
+    /// written on only one side of `export`, and the `class` keyword.
+    fn exported_class_decorators(&mut self, before: Vec<AstNode>) -> ParseResult<Vec<AstNode>> {
+        let mut decorators = before;
+        if self.check(&Token::At) {
+            let index = self.current;
+            let after = self.decorators()?;
+            let message = if !decorators.is_empty() {
+                Some("Decorators may not appear both before and after 'export'.")
+            } else if self.options.decorators == Some(DecoratorSyntax::Legacy) {
+                Some("Legacy decorators must come before 'export'.")
+            } else {
+                None
             };
+            if let Some(message) = message {
+                let error = self.error_at(index, ErrorCode::UnexpectedToken, "'class'", message);
+                self.errors.push(error);
+            }
+            decorators.extend(after);
         }
+        self.consume(Token::Class, "Expect 'class' after decorators.")?;
+        Ok(decorators)
+    }
+
+    /// The kind of variable declaration starting at the current token, if
+    /// any. `let` is only a keyword when a binding follows it, so `let = 1`
+    /// is an assignment.
+    fn variable_kind(&self) -> Option<&'static str> {
+        match self.peek() {
+            Token::Var => Some("var"),
+            Token::Const => Some("const"),
+            Token::Identifier(name)
+                if name == "let"
+                    && matches!(
+                        self.peek_next(),
+                        Token::Identifier(_) | Token::Await | Token::LeftBracket | Token::LeftBrace
+                    ) =>
+            {
+                Some("let")
+            }
+            _ => None,
+        }
+    }
 
-        left
+    /// The kind of an explicit resource management declaration at the
+    /// current token, `using x` or `await using x`, with no line break
+    /// between the words. In a `for` head, `using of` starts a
+    /// `for`...`of` loop instead.
+    fn using_kind(&self) -> Option<&'static str> {
+        let (kind, start) = if self.in_async && self.check(&Token::Await) {
+            ("await using", self.current + 1)
+        } else {
+            ("using", self.current)
+        };
+        let using = &self.tokens[start];
+        if !matches!(&using.token, Token::Identifier(word) if word == "using")
+            || (start > self.current && using.newline_before)
+        {
+            return None;
+        }
+        let name = &self.tokens[start + 1];
+        match &name.token {
+            Token::Identifier(word)
+                if word == "of"
+                    && !self.allow_in
+                    && !matches!(&self.tokens[start + 2].token, Token::Identifier(next) if next == "of") =>
+            {
+                None
+            }
+            Token::Identifier(_) if !name.newline_before => Some(kind),
+            _ => None,
+        }
     }
 
-    fn parse_ternary(&mut self) -> AstNode {
-        let left = self.parse_or();
+    /// Reports a `using` or `await using` declaration at the current token,
+    /// for the statement positions where one may not appear.
+    fn reject_using(&mut self, message: &str) {
+        if self.using_kind().is_some() {
+            let error = self.error(ErrorCode::UnexpectedToken, "statement", message);
+            self.errors.push(error);
+        }
+    }
 
-        if self.match_token(Token::Question) {
+    /// Consumes the keywords that start a variable declaration, including
+    /// `using` and `await using`, and returns the declaration's kind.
+    fn match_variable_kind(&mut self) -> Option<&'static str> {
+        if let Some(kind) = self.variable_kind() {
             self.advance();
-            let middle = self.parse_expression();
-            self.consume(Token::Colon, "Expect ':' after '?' in ternary operator.");
-            let right = self.parse_expression();
-            return AstNode::TernaryExpression {
-                left: Box::new(left),
-                middle: Box::new(middle),
-                right: Box::new(right),
+            return Some(kind);
+        }
+        let kind = self.using_kind()?;
+        self.require_feature(self.current, Feature::ExplicitResourceManagement);
+        if kind == "await using" {
+            self.advance();
+        }
+        self.advance();
+        Some(kind)
+    }
+
+    fn var_declaration(&mut self, kind: &str) -> ParseResult<AstNode> {
+        let declaration = self.variable_declarators(kind)?;
+        self.consume_semicolon("Expect ';' after variable declaration.")?;
+        Ok(declaration)
+    }
+
+    /// Parses the comma-separated declarators after `var`, `let`, `const` or
+    /// `using`.
+    fn variable_declarators(&mut self, kind: &str) -> ParseResult<AstNode> {
+        if kind != "var" {
+            self.require_version(self.current - 1, 2015, "lexical declarations");
+        }
+        let mut declarations = Vec::new();
+        loop {
+            let id_index = self.current;
+            let id = self.parse_binding_target("Expect variable name.")?;
+            let definite = self.options.typescript && self.match_token(Token::Bang);
+            if kind != "var" && id == AstNode::identifier("let".to_string()) {
+                let error = self.error_at(
+                    id_index,
+                    ErrorCode::LetBinding,
+                    "identifier",
+                    "'let' cannot be declared with 'let' or 'const'.",
+                );
+                self.errors.push(error);
+            }
+            let is_pattern = !matches!(id, AstNode::Identifier { .. });
+            let is_using = kind.ends_with("using");
+            let id = self.annotate(id, false)?;
+            let init = if self.match_token(Token::Equal) {
+                Some(Box::new(self.parse_assignment()?))
+            } else {
+                // The head of a `for`...`in`/`of` loop provides the value,
+                // and ambient declarations have none.
+                let in_loop_head = self.check(&Token::In) || self.is_word("of");
+                let needs_init = kind == "const" || is_using || is_pattern;
+                if needs_init && !in_loop_head && !self.ambient && !definite {
+                    let message = if is_using {
+                        "Missing initializer in using declaration."
+                    } else if is_pattern {
+                        "Missing initializer in destructuring declaration."
+                    } else {
+                        "Missing initializer in const declaration."
+                    };
+                    let error = self.error(ErrorCode::MissingInitializer, "'='", message);
+                    self.errors.push(error);
+                }
+                None
             };
+            declarations.push(AstNode::VariableDeclarator {
+                id: Box::new(id),
+                init,
+                definite,
+            });
+            if !self.match_token(Token::Comma) {
+                break;
+            }
         }
+        Ok(AstNode::VariableDeclaration {
+            kind: kind.to_string(),
+            declarations,
+            declare: false,
+        })
+    }
 
-        left
+    /// Whether `async function` starts at the current token. No line break
+    /// may separate the two words.
+    fn is_async_function(&self) -> bool {
+        self.is_word("async")
+            && self.peek_next() == &Token::Function
+            && !self.tokens[self.current + 1].newline_before
     }
 
-    fn parse_or(&mut self) -> AstNode {
-        let mut left = self.parse_and();
+    /// Parses a function after the `function` keyword, including an optional
+    /// `*` for generators. Only declarations require a name.
+    fn function(&mut self, is_async: bool, is_declaration: bool) -> ParseResult<AstNode> {
+        if is_async {
+            self.require_version(self.current - 2, 2017, "async functions");
+        }
+        let generator = self.match_generator_star(is_async);
+        let id = if is_declaration || !matches!(self.peek(), Token::LeftParen | Token::Less) {
+            Some(self.consume_identifier("Expect function name.")?)
+        } else {
+            None
+        };
+        let type_parameters = self.parse_type_parameters()?;
+        self.consume(Token::LeftParen, "Expect '(' after function name.")?;
+        // TypeScript declarations may leave out the body, for overloads and
+        // in ambient contexts.
+        let allow_signature = is_declaration && self.options.typescript;
+        let (params, return_type, body) = self.with_super(false, false, |parser| {
+            parser.function_rest("function", is_async, generator, allow_signature)
+        })?;
+        let Some(body) = body else {
+            return Ok(AstNode::TSDeclareFunction {
+                id: Box::new(AstNode::identifier(id.unwrap_or_default())),
+                params,
+                generator,
+                r#async: is_async,
+                type_parameters,
+                return_type,
+                declare: false,
+            });
+        };
+        Ok(match id {
+            Some(name) if is_declaration => AstNode::FunctionDeclaration {
+                id: Box::new(AstNode::identifier(name)),
+                params,
+                body: Box::new(body),
+                generator,
+                r#async: is_async,
+                type_parameters,
+                return_type,
+            },
+            id => AstNode::FunctionExpression {
+                id: id.map(|name| Box::new(AstNode::identifier(name))),
+                params,
+                body: Box::new(body),
+                generator,
+                r#async: is_async,
+                type_parameters,
+                return_type,
+            },
+        })
+    }
 
-        while self.match_token(Token::PipePipe) {
-            let operator = self.previous().to_string();
-            let right = self.parse_and();
-            left = AstNode::LogicalExpression {
-                operator,
-                left: Box::new(left),
-                right: Box::new(right),
-            };
+    /// Matches the `*` that makes a function or method a generator.
+    fn match_generator_star(&mut self, is_async: bool) -> bool {
+        if !self.match_token(Token::Star) {
+            return false;
         }
+        let (version, feature) = if is_async {
+            (2018, "async generators")
+        } else {
+            (2015, "generators")
+        };
+        self.require_version(self.current - 1, version, feature);
+        true
+    }
 
-        left
+    /// Parses the parameter list after its `(`, the TypeScript return type
+    /// and the function body, with `await` and `yield` treated as operators
+    /// for async functions and generators respectively. With
+    /// `allow_signature` the body may be left out, leaving `None`.
+    fn function_rest(
+        &mut self,
+        kind: &str,
+        is_async: bool,
+        generator: bool,
+        allow_signature: bool,
+    ) -> ParseResult<FunctionParts> {
+        let in_async = std::mem::replace(&mut self.in_async, is_async);
+        let in_generator = std::mem::replace(&mut self.in_generator, generator);
+        let result = self.parse_params().and_then(|params| {
+            let return_type = self.parse_return_type()?;
+            if allow_signature && !self.check(&Token::LeftBrace) {
+                self.consume_semicolon(&format!("Expect '{{' or ';' after {} signature.", kind))?;
+                return Ok((params, return_type, None));
+            }
+            if self.ambient && self.check(&Token::LeftBrace) {
+                let error = self.error(
+                    ErrorCode::UnexpectedToken,
+                    "';'",
+                    "An implementation cannot be declared in ambient contexts.",
+                );
+                self.errors.push(error);
+            }
+            self.consume(
+                Token::LeftBrace,
+                &format!("Expect '{{' before {} body.", kind),
+            )?;
+            let body = self.allowing_in(Self::function_body)?;
+            Ok((params, return_type, Some(body)))
+        });
+        self.in_async = in_async;
+        self.in_generator = in_generator;
+        result
     }
 
-    fn parse_and(&mut self) -> AstNode {
-        let mut left = self.parse_equality();
+    /// Parses a method after the `(` that follows its name and any type
+    /// parameters. Methods may use `super.x`; derived class constructors may
+    /// also call `super()`. Class methods in TypeScript may leave out the
+    /// body, as overloads and abstract methods do.
+    fn method(
+        &mut self,
+        type_parameters: Option<Box<AstNode>>,
+        is_async: bool,
+        generator: bool,
+        super_call: bool,
+        allow_signature: bool,
+    ) -> ParseResult<AstNode> {
+        let (params, return_type, body) = self.with_super(true, super_call, |parser| {
+            parser.function_rest("method", is_async, generator, allow_signature)
+        })?;
+        Ok(match body {
+            Some(body) => AstNode::FunctionExpression {
+                id: None,
+                params,
+                body: Box::new(body),
+                generator,
+                r#async: is_async,
+                type_parameters,
+                return_type,
+            },
+            None => AstNode::TSEmptyBodyFunctionExpression {
+                params,
+                generator,
+                r#async: is_async,
+                type_parameters,
+                return_type,
+            },
+        })
+    }
 
-        while self.match_token(Token::AmpersandAmpersand) {
-            let operator = self.previous().to_string();
-            let right = self.parse_equality();
-            left = AstNode::LogicalExpression {
-                operator,
-                left: Box::new(left),
-                right: Box::new(right),
-            };
+    /// Runs `parse` with the given uses of `super` allowed. Arrow functions
+    /// keep the setting of the code around them.
+    fn with_super<T>(
+        &mut self,
+        property: bool,
+        call: bool,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let super_property = std::mem::replace(&mut self.super_property, property);
+        let super_call = std::mem::replace(&mut self.super_call, call);
+        let result = parse(self);
+        self.super_property = super_property;
+        self.super_call = super_call;
+        result
+    }
+
+    /// Reports an accessor whose parameter count does not match its kind.
+    fn check_accessor_params(&mut self, kind: &str, function: &AstNode, key_start: usize) {
+        let (count, description) = match kind {
+            "get" => (0, "no parameters"),
+            "set" => (1, "exactly one parameter"),
+            _ => return,
+        };
+        if let AstNode::FunctionExpression { params, .. } = function {
+            if params.len() != count {
+                let error = self.error_at(
+                    key_start,
+                    ErrorCode::InvalidAccessorParameters,
+                    description,
+                    &format!("A '{}' accessor must have {}.", kind, description),
+                );
+                self.errors.push(error);
+            }
         }
+    }
 
-        left
+    /// Parses a class after the `class` keyword. Only declarations require a
+    /// name. All parts of a class are strict mode code.
+    fn class(&mut self, is_declaration: bool, decorators: Vec<AstNode>) -> ParseResult<AstNode> {
+        self.require_version(self.current - 1, 2015, "classes");
+        let strict = std::mem::replace(&mut self.strict, true);
+        let result = self.class_rest(is_declaration, decorators);
+        self.strict = strict;
+        result
     }
 
-    fn parse_equality(&mut self) -> AstNode {
-        let mut left = self.parse_comparison();
+    fn class_rest(
+        &mut self,
+        is_declaration: bool,
+        decorators: Vec<AstNode>,
+    ) -> ParseResult<AstNode> {
+        let names_class =
+            self.is_identifier() && !(self.options.typescript && self.is_word("implements"));
+        let id = if is_declaration || names_class {
+            Some(self.consume_identifier("Expect class name.")?)
+        } else {
+            None
+        };
+        let type_parameters = self.parse_type_parameters()?;
+        let mut super_type_arguments = None;
+        let super_class = if self.match_token(Token::Extends) {
+            let super_class = self.parse_call_expression()?;
+            if self.type_syntax() && self.check(&Token::Less) {
+                super_type_arguments = Some(Box::new(self.type_arguments()?));
+            }
+            Some(Box::new(super_class))
+        } else {
+            None
+        };
+        let mut implements = Vec::new();
+        if self.options.typescript && self.match_word("implements") {
+            loop {
+                let (expression, type_arguments) = self.ts_heritage()?;
+                implements.push(AstNode::TSClassImplements {
+                    expression: Box::new(expression),
+                    type_arguments,
+                });
+                if !self.match_token(Token::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(Token::LeftBrace, "Expect '{' before class body.")?;
+        self.class_scopes.push(ClassScope::default());
+        let body = self.class_body(super_class.is_some());
+        let scope = self.class_scopes.pop().unwrap_or_default();
+        self.resolve_private_names(scope);
+        let body = Box::new(body?);
+        Ok(match id {
+            Some(name) if is_declaration => AstNode::ClassDeclaration {
+                id: Box::new(AstNode::identifier(name)),
+                super_class,
+                body,
+                decorators,
+                type_parameters,
+                super_type_arguments,
+                implements,
+                r#abstract: false,
+                declare: false,
+            },
+            id => AstNode::ClassExpression {
+                id: id.map(|name| Box::new(AstNode::identifier(name))),
+                super_class,
+                body,
+                decorators,
+                type_parameters,
+                super_type_arguments,
+                implements,
+            },
+        })
+    }
 
-        while self.match_token(Token::EqualEqual) || self.match_token(Token::BangEqual) {
-            let operator = self.previous().to_string();
-            let right = self.parse_comparison();
-            left = AstNode::BinaryExpression {
-                operator,
-                left: Box::new(left),
-                right: Box::new(right),
+    fn class_body(&mut self, derived: bool) -> ParseResult<AstNode> {
+        let mut body = Vec::new();
+        let mut has_constructor = false;
+        // Private names declared so far, with whether a static member
+        // declared them and, for a getter or setter still missing its
+        // other half, its kind.
+        let mut private_names: HashMap<String, (bool, Option<String>)> = HashMap::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            if self.match_token(Token::Semicolon) {
+                continue;
+            }
+            let member_start = self.current;
+            let member = self.class_member(derived)?;
+            // TypeScript constructor overloads have no body and may repeat.
+            let is_constructor = matches!(
+                &member,
+                AstNode::MethodDefinition { kind, value, .. }
+                    if kind == "constructor" && matches!(**value, AstNode::FunctionExpression { .. })
+            );
+            if is_constructor {
+                if has_constructor {
+                    let error = self.error_at(
+                        member_start,
+                        ErrorCode::DuplicateConstructor,
+                        "one constructor",
+                        "A class may only have one constructor.",
+                    );
+                    self.errors.push(error);
+                }
+                has_constructor = true;
+            }
+            if let Some((name, is_static, accessor)) = private_member(&member) {
+                // Only a getter and a setter of the same placement may share
+                // a name.
+                let duplicate = match private_names.get(name) {
+                    None => false,
+                    Some((was_static, Some(kind))) => {
+                        *was_static != is_static || accessor.is_none_or(|accessor| accessor == kind)
+                    }
+                    Some((_, None)) => true,
+                };
+                if duplicate {
+                    let error = self.error_at(
+                        member_start,
+                        ErrorCode::Redeclaration,
+                        "unique private name",
+                        &format!("Private name '#{}' has already been declared.", name),
+                    );
+                    self.errors.push(error);
+                } else {
+                    let pending = if private_names.contains_key(name) {
+                        None
+                    } else {
+                        accessor.map(str::to_string)
+                    };
+                    private_names.insert(name.to_string(), (is_static, pending));
+                }
+            }
+            body.push(member);
+        }
+        self.consume(Token::RightBrace, "Expect '}' after class body.")?;
+        Ok(AstNode::ClassBody { body })
+    }
+
+    /// Parses a method, accessor, field or static block, or in TypeScript
+    /// an index signature. `static`, `async`, `get`, `set` and `accessor`
+    /// are modifiers only when a member name follows them.
+    fn class_member(&mut self, derived: bool) -> ParseResult<AstNode> {
+        let decorators = self.decorators()?;
+        let modifiers = self.class_modifiers();
+        let is_static = modifiers.r#static;
+        if is_static && self.match_token(Token::LeftBrace) {
+            self.require_version(self.current - 2, 2022, "class static blocks");
+            if !decorators.is_empty() {
+                let error = self.error_at(
+                    self.current - 2,
+                    ErrorCode::UnexpectedToken,
+                    "class member",
+                    "Decorators are not valid on static blocks.",
+                );
+                self.errors.push(error);
+            }
+            return self.static_block();
+        }
+        if self.options.typescript && self.ts_starts_index_signature() {
+            let signature = self.ts_index_signature(modifiers.readonly, is_static)?;
+            self.consume_semicolon("Expect ';' after index signature.")?;
+            return Ok(signature);
+        }
+        // Auto-accessors come with the 2023 decorators proposal.
+        let is_accessor = modifiers.accessor
+            || (self.options.decorators == Some(DecoratorSyntax::Proposal2023)
+                && self.is_word("accessor")
+                && !self.tokens[self.current + 1].newline_before
+                && is_class_key_start(self.peek_next()));
+        if is_accessor {
+            if !modifiers.accessor {
+                self.advance();
+            }
+            let (key, computed) = self.parse_class_key()?;
+            let type_annotation = self.parse_type_annotation()?;
+            let value = if self.match_token(Token::Equal) {
+                Some(Box::new(self.field_initializer()?))
+            } else {
+                None
+            };
+            self.consume_semicolon("Expect ';' after class field.")?;
+            return Ok(AstNode::AccessorProperty {
+                key: Box::new(key),
+                value,
+                computed,
+                r#static: is_static,
+                decorators,
+                type_annotation,
+                accessibility: modifiers.accessibility,
+                r#override: modifiers.r#override,
+            });
+        }
+        let is_async = self.is_word("async")
+            && !self.tokens[self.current + 1].newline_before
+            && (is_class_key_start(self.peek_next()) || self.peek_next() == &Token::Star);
+        if is_async {
+            self.require_version(self.current, 2017, "async methods");
+            self.advance();
+        }
+        let generator = self.match_generator_star(is_async);
+        let accessor = if !is_async
+            && !generator
+            && (self.is_word("get") || self.is_word("set"))
+            && is_class_key_start(self.peek_next())
+        {
+            Some(self.advance().to_string())
+        } else {
+            None
+        };
+
+        let variance = self.flow_variance();
+        let key_start = self.current;
+        let (key, computed) = self.parse_class_key()?;
+        let optional = self.options.typescript && self.match_token(Token::Question);
+        let definite = self.options.typescript && !optional && self.match_token(Token::Bang);
+        let is_method = accessor.is_some()
+            || is_async
+            || generator
+            || self.check(&Token::LeftParen)
+            || (self.type_syntax() && self.check(&Token::Less));
+        if is_method && variance.is_some() {
+            let error = self.error_at(
+                key_start - 1,
+                ErrorCode::UnexpectedToken,
+                "class member",
+                "Variance is only allowed on fields.",
+            );
+            self.errors.push(error);
+        }
+        let key_name = match &key {
+            AstNode::Identifier { name, .. } | AstNode::StringLiteral(name) if !computed => {
+                Some(name.as_str())
+            }
+            _ => None,
+        };
+        let reserved = match key_name {
+            Some("prototype") if is_static => {
+                Some("Classes may not have a static member named 'prototype'.")
+            }
+            Some("constructor") if !is_method => {
+                Some("Classes may not have a field named 'constructor'.")
+            }
+            Some("constructor") if !is_static && (accessor.is_some() || is_async || generator) => {
+                Some("Class constructor may not be an accessor, generator or async method.")
+            }
+            _ => None,
+        };
+        if let Some(message) = reserved {
+            let error = self.error_at(
+                key_start,
+                ErrorCode::ReservedMemberName,
+                "member name",
+                message,
+            );
+            self.errors.push(error);
+        }
+        if !is_method {
+            self.require_version(key_start, 2022, "class fields");
+            let type_annotation = self.parse_type_annotation()?;
+            let value = if self.match_token(Token::Equal) {
+                Some(Box::new(self.field_initializer()?))
+            } else {
+                None
             };
+            self.consume_semicolon("Expect ';' after class field.")?;
+            if modifiers.r#abstract {
+                return Ok(AstNode::TSAbstractPropertyDefinition {
+                    key: Box::new(key),
+                    computed,
+                    r#static: is_static,
+                    type_annotation,
+                    accessibility: modifiers.accessibility,
+                    optional,
+                    readonly: modifiers.readonly,
+                    r#override: modifiers.r#override,
+                });
+            }
+            return Ok(AstNode::PropertyDefinition {
+                key: Box::new(key),
+                value,
+                computed,
+                r#static: is_static,
+                decorators,
+                type_annotation,
+                accessibility: modifiers.accessibility,
+                optional,
+                definite,
+                readonly: modifiers.readonly,
+                declare: modifiers.declare,
+                r#override: modifiers.r#override,
+                variance,
+            });
         }
 
-        left
+        let type_parameters = self.parse_type_parameters()?;
+        self.consume(Token::LeftParen, "Expect '(' after method name.")?;
+        let is_constructor = !is_static
+            && !computed
+            && matches!(&key, AstNode::Identifier { name, .. } | AstNode::StringLiteral(name) if name == "constructor");
+        let kind = match accessor {
+            Some(kind) => kind,
+            None if is_constructor => "constructor".to_string(),
+            None => "method".to_string(),
+        };
+        if kind == "constructor" && !decorators.is_empty() {
+            let error = self.error_at(
+                key_start,
+                ErrorCode::UnexpectedToken,
+                "class member",
+                "Decorators are not valid on constructors.",
+            );
+            self.errors.push(error);
+        }
+        self.parameter_properties = is_constructor;
+        let value = self.method(
+            type_parameters,
+            is_async,
+            generator,
+            is_constructor && derived,
+            self.options.typescript,
+        );
+        self.parameter_properties = false;
+        let value = value?;
+        self.check_accessor_params(&kind, &value, key_start);
+        if modifiers.r#abstract {
+            return Ok(AstNode::TSAbstractMethodDefinition {
+                key: Box::new(key),
+                value: Box::new(value),
+                kind,
+                computed,
+                r#static: is_static,
+                accessibility: modifiers.accessibility,
+                optional,
+                r#override: modifiers.r#override,
+            });
+        }
+        Ok(AstNode::MethodDefinition {
+            key: Box::new(key),
+            value: Box::new(value),
+            kind,
+            computed,
+            r#static: is_static,
+            decorators,
+            accessibility: modifiers.accessibility,
+            optional,
+            r#override: modifiers.r#override,
+        })
     }
 
-    fn parse_primary(&mut self) -> AstNode {
-        if self.match_token(Token::False) {
-            AstNode::NumberLiteral(0.0)
-        } else if self.match_token(Token::True) {
-            AstNode::NumberLiteral(1.0)
-        } else if self.match_token(Token::Nil) {
-            AstNode::NumberLiteral(0.0)
-        } else if self.match_token(Token::Number) {
-            self.previous().literal
-        } else if self.match_token(Token::String) {
-            self.previous().literal
-        } else if self.match_token(Token::Identifier) {
-            AstNode::Identifier(self.previous().lexeme)
-        } else if self.match_token(Token::LeftParen) {
-            let expression = self.parse_expression();
-            if self.peek() != Token::RightParen {
-                // Handle missing ')' error
+    /// Parses the modifiers before a class member. Each is only a modifier
+    /// when a member name, or for `static` a static block, follows it; in
+    /// Flow the name may have a variance sigil. TypeScript's modifiers must
+    /// also be on the same line as that name.
+    fn class_modifiers(&mut self) -> ClassModifiers {
+        let mut modifiers = ClassModifiers::default();
+        while let Token::Identifier(word) = self.peek().clone() {
+            let is_modifier = match word.as_str() {
+                "static" => !modifiers.r#static,
+                "public" | "private" | "protected" | "abstract" | "override" | "readonly"
+                | "declare" | "accessor" => {
+                    self.options.typescript && !self.tokens[self.current + 1].newline_before
+                }
+                _ => false,
+            };
+            let next = self.peek_next();
+            let starts_member = is_class_key_start(next)
+                || next == &Token::Star
+                || (word == "static" && next == &Token::LeftBrace)
+                || (self.options.flow && matches!(next, Token::Plus | Token::Minus));
+            if !is_modifier || !starts_member {
+                break;
             }
             self.advance();
-            expression
-        } else {
-            // Handle unexpected token
+            match word.as_str() {
+                "static" => modifiers.r#static = true,
+                "abstract" => modifiers.r#abstract = true,
+                "override" => modifiers.r#override = true,
+                "readonly" => modifiers.readonly = true,
+                "declare" => modifiers.declare = true,
+                "accessor" => modifiers.accessor = true,
+                _ => modifiers.accessibility = Some(word),
+            }
         }
+        modifiers
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::lexer;
+    /// Parses a class member name, declaring it if it is private.
+    fn parse_class_key(&mut self) -> ParseResult<(AstNode, bool)> {
+        if let Token::PrivateIdentifier(name) = self.peek().clone() {
+            self.require_version(self.current, 2022, "private class members");
+            self.advance();
+            if let Some(scope) = self.class_scopes.last_mut() {
+                scope.declared.insert(name.clone());
+            }
+            return Ok((AstNode::PrivateIdentifier(name), false));
+        }
+        self.parse_property_key()
+    }
 
-    use super::*;
+    /// Field initialisers and static blocks run like methods: `super.x` is
+    /// allowed while `await` and `yield` are not operators.
+    fn class_element_code<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let in_async = std::mem::replace(&mut self.in_async, false);
+        let in_generator = std::mem::replace(&mut self.in_generator, false);
+        let result = self.with_super(true, false, parse);
+        self.in_async = in_async;
+        self.in_generator = in_generator;
+        result
+    }
 
-    #[test]
-    fn test_parsing_simple_math_expression() {
-        let input = "2 + 2";
-        let expected_output = AstNode::BinaryExpression {
-            operator: "+".to_string(),
-            left: Box::new(AstNode::NumberLiteral(2.0)),
-            right: Box::new(AstNode::NumberLiteral(2.0)),
-        };
-        let tokens = lexer::tokenize(input);
-        let ast = Parser::parse(tokens);
-        assert_eq!(ast, expected_output);
+    fn field_initializer(&mut self) -> ParseResult<AstNode> {
+        self.class_element_code(|parser| parser.allowing_in(Self::parse_assignment))
     }
 
-    #[test]
-    fn test_parsing_function_call() {
-        let input = "add(2, 3)";
-        let expected_output = AstNode::CallExpression {
-            callee: Box::new(AstNode::Identifier("add".to_string())),
-            arguments: vec![AstNode::NumberLiteral(2.0), AstNode::NumberLiteral(3.0)],
-        };
-        let tokens = lexer::tokenize(input);
-        let ast = Parser::parse(tokens);
-        assert_eq!(ast, expected_output);
+    fn static_block(&mut self) -> ParseResult<AstNode> {
+        self.class_element_code(|parser| {
+            let mut body = Vec::new();
+            while !parser.check(&Token::RightBrace) && !parser.is_at_end() {
+                body.push(parser.declaration());
+            }
+            parser.consume(Token::RightBrace, "Expect '}' after static block.")?;
+            Ok(AstNode::StaticBlock { body })
+        })
     }
 
-    #[test]
+    /// Records a `#name` reference, to be checked when its class body ends.
+    fn reference_private_name(&mut self, name: String, index: usize) {
+        match self.class_scopes.last_mut() {
+            Some(scope) => scope.referenced.push((name, index)),
+            None => self.report_undeclared_private_name(index),
+        }
+    }
+
+    /// Checks the references made in a finished class body. Names the class
+    /// does not declare may still belong to an enclosing class.
+    fn resolve_private_names(&mut self, scope: ClassScope) {
+        for (name, index) in scope.referenced {
+            if scope.declared.contains(&name) {
+                continue;
+            }
+            match self.class_scopes.last_mut() {
+                Some(outer) => outer.referenced.push((name, index)),
+                None => self.report_undeclared_private_name(index),
+            }
+        }
+    }
+
+    fn report_undeclared_private_name(&mut self, index: usize) {
+        let error = self.error_at(
+            index,
+            ErrorCode::UndeclaredPrivateName,
+            "declared private name",
+            "Private name is not declared in an enclosing class.",
+        );
+        self.errors.push(error);
+    }
+
+    /// Parses formal parameters after the `(`, up to and including the `)`.
+    fn parse_params(&mut self) -> ParseResult<Vec<AstNode>> {
+        let parameter_properties = std::mem::take(&mut self.parameter_properties);
+        let mut params = Vec::new();
+        while !self.check(&Token::RightParen) {
+            if self.match_token(Token::Ellipsis) {
+                self.require_version(self.current - 1, 2015, "rest parameters");
+                let argument = self.parse_binding_target("Expect parameter name.")?;
+                let rest = AstNode::RestElement {
+                    argument: Box::new(argument),
+                    type_annotation: None,
+                };
+                params.push(self.annotate(rest, false)?);
+                break;
+            }
+            let start = self.current;
+            if self.type_syntax() {
+                params.push(self.typed_param(parameter_properties)?);
+            } else {
+                params.push(self.parse_binding_element("Expect parameter name.")?);
+            }
+            if matches!(params.last(), Some(AstNode::AssignmentPattern { .. })) {
+                self.require_version(start, 2015, "default parameters");
+            }
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+            if self.check(&Token::RightParen) {
+                self.require_version(self.current - 1, 2017, "trailing commas in parameter lists");
+            }
+        }
+        self.consume(Token::RightParen, "Expect ')' after parameters.")?;
+        Ok(params)
+    }
+
+    /// Parses a binding identifier or an object or array destructuring
+    /// pattern, as used by declarations, parameters and `catch`.
+    fn parse_binding_target(&mut self, message: &str) -> ParseResult<AstNode> {
+        if matches!(self.peek(), Token::LeftBracket | Token::LeftBrace) {
+            self.require_version(self.current, 2015, "destructuring patterns");
+        }
+        if self.match_token(Token::LeftBracket) {
+            self.parse_array_binding()
+        } else if self.match_token(Token::LeftBrace) {
+            self.parse_object_binding()
+        } else {
+            Ok(AstNode::identifier(self.consume_identifier(message)?))
+        }
+    }
+
+    /// Parses a binding target with an optional `= default`.
+    fn parse_binding_element(&mut self, message: &str) -> ParseResult<AstNode> {
+        let target = self.parse_binding_target(message)?;
+        if self.match_token(Token::Equal) {
+            return Ok(AstNode::AssignmentPattern {
+                left: Box::new(target),
+                right: Box::new(self.parse_assignment()?),
+            });
+        }
+        Ok(target)
+    }
+
+    fn parse_array_binding(&mut self) -> ParseResult<AstNode> {
+        let mut elements = Vec::new();
+        while !self.check(&Token::RightBracket) {
+            if self.match_token(Token::Comma) {
+                elements.push(None);
+                continue;
+            }
+            if self.match_token(Token::Ellipsis) {
+                let argument = self.parse_binding_target("Expect rest element name.")?;
+                elements.push(Some(AstNode::RestElement {
+                    argument: Box::new(argument),
+                    type_annotation: None,
+                }));
+                self.consume(Token::RightBracket, "Expect ']' after rest element.")?;
+                return Ok(AstNode::ArrayPattern {
+                    elements,
+                    type_annotation: None,
+                    optional: false,
+                });
+            }
+            elements.push(Some(self.parse_binding_element("Expect binding name.")?));
+            if !self.check(&Token::RightBracket) {
+                self.consume(
+                    Token::Comma,
+                    "Expect ',' or ']' after array pattern element.",
+                )?;
+            }
+        }
+        self.consume(Token::RightBracket, "Expect ']' after array pattern.")?;
+        Ok(AstNode::ArrayPattern {
+            elements,
+            type_annotation: None,
+            optional: false,
+        })
+    }
+
+    fn parse_object_binding(&mut self) -> ParseResult<AstNode> {
+        let mut properties = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            if self.match_token(Token::Ellipsis) {
+                self.require_version(self.current - 1, 2018, "object rest properties");
+                let name = self.consume_identifier("Expect rest property name.")?;
+                properties.push(AstNode::RestElement {
+                    argument: Box::new(AstNode::identifier(name)),
+                    type_annotation: None,
+                });
+                self.consume(Token::RightBrace, "Expect '}' after rest property.")?;
+                return Ok(AstNode::ObjectPattern {
+                    properties,
+                    type_annotation: None,
+                    optional: false,
+                });
+            }
+            let is_shorthand = self.is_identifier();
+            let (key, computed) = self.parse_property_key()?;
+            let (value, shorthand) = if self.match_token(Token::Colon) {
+                (self.parse_binding_element("Expect binding name.")?, false)
+            } else if is_shorthand {
+                let value = if self.match_token(Token::Equal) {
+                    AstNode::AssignmentPattern {
+                        left: Box::new(key.clone()),
+                        right: Box::new(self.parse_assignment()?),
+                    }
+                } else {
+                    key.clone()
+                };
+                (value, true)
+            } else {
+                return Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "':'",
+                    "Expect ':' after property name.",
+                ));
+            };
+            properties.push(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(value),
+                kind: "init".to_string(),
+                computed,
+                shorthand,
+                method: false,
+            });
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBrace, "Expect '}' after object pattern.")?;
+        Ok(AstNode::ObjectPattern {
+            properties,
+            type_annotation: None,
+            optional: false,
+        })
+    }
+
+    /// Whether `import` starts a declaration rather than `import(...)` or
+    /// `import.meta`.
+    fn is_import_declaration(&self) -> bool {
+        self.check(&Token::Import) && !matches!(self.peek_next(), Token::LeftParen | Token::Dot)
+    }
+
+    fn statement(&mut self) -> ParseResult<AstNode> {
+        if self.is_import_declaration() || self.check(&Token::Export) {
+            let message = match self.options.source_type {
+                SourceType::Script => "'import' and 'export' may only appear in modules.",
+                SourceType::Module => "'import' and 'export' may only appear at the top level.",
+            };
+            return Err(self.error(ErrorCode::MisplacedImportExport, "statement", message));
+        }
+        if self.match_token(Token::LeftBrace) {
+            self.block()
+        } else if self.match_token(Token::If) {
+            self.if_statement()
+        } else if self.match_token(Token::Return) {
+            self.return_statement()
+        } else if self.match_token(Token::While) {
+            self.while_statement()
+        } else if self.match_token(Token::Do) {
+            self.do_while_statement()
+        } else if self.match_token(Token::For) {
+            self.for_statement()
+        } else if self.match_token(Token::Switch) {
+            self.switch_statement()
+        } else if self.match_token(Token::Try) {
+            self.try_statement()
+        } else if self.match_token(Token::Throw) {
+            self.throw_statement()
+        } else if self.match_token(Token::Break) {
+            let label = self.jump_label("break")?;
+            Ok(AstNode::BreakStatement { label })
+        } else if self.match_token(Token::Continue) {
+            let label = self.jump_label("continue")?;
+            Ok(AstNode::ContinueStatement { label })
+        } else if self.match_token(Token::With) {
+            self.with_statement()
+        } else if self.match_token(Token::Debugger) {
+            self.consume_semicolon("Expect ';' after 'debugger'.")?;
+            Ok(AstNode::DebuggerStatement)
+        } else if self.match_token(Token::Semicolon) {
+            Ok(AstNode::EmptyStatement)
+        } else if self.peek_next() == &Token::Colon && self.is_identifier() {
+            let label = self.consume_identifier("Expect label name.")?;
+            self.advance();
+            let body = self.statement_or_function()?;
+            Ok(AstNode::LabeledStatement {
+                label: Box::new(AstNode::identifier(label)),
+                body: Box::new(body),
+            })
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn block(&mut self) -> ParseResult<AstNode> {
+        self.block_body(false)
+    }
+
+    /// Parses the body of an `if` or a label, which sloppy mode code may
+    /// also make a plain function declaration.
+    fn statement_or_function(&mut self) -> ParseResult<AstNode> {
+        if !self.check(&Token::Function) {
+            return self.statement();
+        }
+        let index = self.current;
+        self.advance();
+        let function = self.function(false, true)?;
+        let message = if self.strict {
+            Some("In strict mode code, functions can only be declared at the top level or inside a block.")
+        } else if matches!(
+            function,
+            AstNode::FunctionDeclaration {
+                generator: true,
+                ..
+            }
+        ) {
+            Some("Generators can only be declared at the top level or inside a block.")
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            let error = self.error_at(index, ErrorCode::UnexpectedToken, "statement", message);
+            self.errors.push(error);
+        }
+        Ok(function)
+    }
+
+    /// Parses a function body after its `{`. Strictness set by its directive
+    /// prologue ends with the body.
+    fn function_body(&mut self) -> ParseResult<AstNode> {
+        let strict = self.strict;
+        let body = self.block_body(true);
+        self.strict = strict;
+        body
+    }
+
+    fn block_body(&mut self, mut in_prologue: bool) -> ParseResult<AstNode> {
+        let mut body = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            let start = self.current;
+            let statement = self.declaration();
+            body.push(self.directive(statement, start, &mut in_prologue));
+        }
+        // A block left open at the end of input keeps what was parsed.
+        if let Err(error) = self.consume(Token::RightBrace, "Expect '}' after block.") {
+            self.errors.push(error);
+        }
+        Ok(AstNode::BlockStatement { body })
+    }
+
+    fn if_statement(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::LeftParen, "Expect '(' after 'if'.")?;
+        let test = self.parse_expression()?;
+        self.consume(Token::RightParen, "Expect ')' after if condition.")?;
+        let consequent = self.statement_or_function()?;
+        let alternate = if self.match_token(Token::Else) {
+            Some(Box::new(self.statement_or_function()?))
+        } else {
+            None
+        };
+        Ok(AstNode::IfStatement {
+            test: Box::new(test),
+            consequent: Box::new(consequent),
+            alternate,
+        })
+    }
+
+    fn return_statement(&mut self) -> ParseResult<AstNode> {
+        let argument = if self.can_insert_semicolon() || self.check(&Token::Semicolon) {
+            None
+        } else {
+            Some(Box::new(self.parse_expression()?))
+        };
+        self.consume_semicolon("Expect ';' after return value.")?;
+        Ok(AstNode::ReturnStatement { argument })
+    }
+
+    fn while_statement(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::LeftParen, "Expect '(' after 'while'.")?;
+        let test = self.parse_expression()?;
+        self.consume(Token::RightParen, "Expect ')' after while condition.")?;
+        let body = self.statement()?;
+        Ok(AstNode::WhileStatement {
+            test: Box::new(test),
+            body: Box::new(body),
+        })
+    }
+
+    fn do_while_statement(&mut self) -> ParseResult<AstNode> {
+        let body = self.statement()?;
+        self.consume(Token::While, "Expect 'while' after do body.")?;
+        self.consume(Token::LeftParen, "Expect '(' after 'while'.")?;
+        let test = self.parse_expression()?;
+        self.consume(Token::RightParen, "Expect ')' after while condition.")?;
+        // A semicolon is always inserted after `do ... while (test)`.
+        self.match_token(Token::Semicolon);
+        Ok(AstNode::DoWhileStatement {
+            body: Box::new(body),
+            test: Box::new(test),
+        })
+    }
+
+    /// Parses a classic `for` loop or a `for`...`in`/`of` loop, which share
+    /// their prefix up to the end of the initialiser.
+    fn for_statement(&mut self) -> ParseResult<AstNode> {
+        let await_index = self.current;
+        let is_await = self.match_token(Token::Await);
+        if is_await && !self.in_async {
+            return Err(self.error_at(
+                await_index,
+                ErrorCode::UnexpectedToken,
+                "'('",
+                "'for await' is only valid in async functions.",
+            ));
+        }
+        if is_await {
+            self.require_version(await_index, 2018, "'for await' loops");
+        }
+        self.consume(Token::LeftParen, "Expect '(' after 'for'.")?;
+
+        let init_start = self.current;
+        let cover_start = self.cover_initializers.len();
+        let init = if self.check(&Token::Semicolon) {
+            None
+        } else {
+            self.allow_in = false;
+            let init = if let Some(kind) = self.match_variable_kind() {
+                self.variable_declarators(kind)
+            } else {
+                self.parse_expression()
+            };
+            self.allow_in = true;
+            Some(init?)
+        };
+
+        let is_of = self.is_word("of");
+        match init {
+            Some(left) if is_of || self.check(&Token::In) => {
+                self.cover_initializers.truncate(cover_start);
+                self.for_in_of_rest(left, init_start, is_of, is_await)
+            }
+            _ if is_await => Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "'of'",
+                "Expect 'of' in 'for await' loop.",
+            )),
+            init => {
+                self.consume(Token::Semicolon, "Expect ';' after for loop initialiser.")?;
+                let test = if self.check(&Token::Semicolon) {
+                    None
+                } else {
+                    Some(Box::new(self.parse_expression()?))
+                };
+                self.consume(Token::Semicolon, "Expect ';' after for loop condition.")?;
+                let update = if self.check(&Token::RightParen) {
+                    None
+                } else {
+                    Some(Box::new(self.parse_expression()?))
+                };
+                self.consume(Token::RightParen, "Expect ')' after for loop clauses.")?;
+                let body = self.statement()?;
+                Ok(AstNode::ForStatement {
+                    init: init.map(Box::new),
+                    test,
+                    update,
+                    body: Box::new(body),
+                })
+            }
+        }
+    }
+
+    /// Parses the rest of a `for`...`in`/`of` loop after its left-hand side.
+    fn for_in_of_rest(
+        &mut self,
+        left: AstNode,
+        left_start: usize,
+        is_of: bool,
+        is_await: bool,
+    ) -> ParseResult<AstNode> {
+        let left = if let AstNode::VariableDeclaration { declarations, .. } = &left {
+            let has_init = declarations.iter().any(|declarator| {
+                matches!(
+                    declarator,
+                    AstNode::VariableDeclarator { init: Some(_), .. }
+                )
+            });
+            if declarations.len() != 1 || has_init {
+                let error = self.error_at(
+                    left_start,
+                    ErrorCode::InvalidForDeclaration,
+                    "single binding",
+                    "A for loop head may only declare one variable, without an initializer.",
+                );
+                self.errors.push(error);
+            }
+            left
+        } else {
+            // `for (async of` could also start an async arrow function.
+            let is_async = self.tokens[left_start].token == Token::Identifier("async".to_string())
+                && self.current == left_start + 1;
+            if is_of && !is_await && is_async {
+                let error = self.error_at(
+                    left_start,
+                    ErrorCode::UnexpectedToken,
+                    "left-hand side other than 'async'",
+                    "The left-hand side of a for-of loop may not be 'async'.",
+                );
+                self.errors.push(error);
+            }
+            self.assignment_target(left, left_start, true)
+        };
+        if is_of {
+            self.require_version(self.current, 2015, "'for'...'of' loops");
+        }
+        self.advance();
+        let right = if is_of {
+            self.parse_assignment()?
+        } else {
+            self.parse_expression()?
+        };
+        self.consume(Token::RightParen, "Expect ')' after for loop head.")?;
+        let body = self.statement()?;
+        Ok(if is_of {
+            AstNode::ForOfStatement {
+                left: Box::new(left),
+                right: Box::new(right),
+                body: Box::new(body),
+                r#await: is_await,
+            }
+        } else {
+            AstNode::ForInStatement {
+                left: Box::new(left),
+                right: Box::new(right),
+                body: Box::new(body),
+            }
+        })
+    }
+
+    fn switch_statement(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::LeftParen, "Expect '(' after 'switch'.")?;
+        let discriminant = self.parse_expression()?;
+        self.consume(Token::RightParen, "Expect ')' after switch discriminant.")?;
+        self.consume(Token::LeftBrace, "Expect '{' before switch body.")?;
+        let mut cases = Vec::new();
+        let mut has_default = false;
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            let test = if self.match_token(Token::Case) {
+                Some(Box::new(self.parse_expression()?))
+            } else if self.check(&Token::Default) {
+                if has_default {
+                    let error = self.error(
+                        ErrorCode::DuplicateDefault,
+                        "'case'",
+                        "More than one default clause in switch statement.",
+                    );
+                    self.errors.push(error);
+                }
+                has_default = true;
+                self.advance();
+                None
+            } else {
+                return Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "'case' or 'default'",
+                    "Expect 'case' or 'default' in switch body.",
+                ));
+            };
+            self.consume(Token::Colon, "Expect ':' after switch case.")?;
+            let mut consequent = Vec::new();
+            while !matches!(
+                self.peek(),
+                Token::Case | Token::Default | Token::RightBrace | Token::EOF
+            ) {
+                self.reject_using(
+                    "'using' declarations are not allowed directly in 'case' or 'default' clauses.",
+                );
+                consequent.push(self.declaration());
+            }
+            cases.push(AstNode::SwitchCase { test, consequent });
+        }
+        self.consume(Token::RightBrace, "Expect '}' after switch body.")?;
+        Ok(AstNode::SwitchStatement {
+            discriminant: Box::new(discriminant),
+            cases,
+        })
+    }
+
+    fn try_statement(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::LeftBrace, "Expect '{' after 'try'.")?;
+        let block = self.block()?;
+        let handler = if self.match_token(Token::Catch) {
+            // The binding is optional, as in `try {} catch {}`.
+            let param = if self.match_token(Token::LeftParen) {
+                let param = self.parse_binding_target("Expect catch parameter name.")?;
+                let param = self.annotate(param, false)?;
+                self.consume(Token::RightParen, "Expect ')' after catch parameter.")?;
+                Some(Box::new(param))
+            } else {
+                self.require_version(self.current - 1, 2019, "optional catch bindings");
+                None
+            };
+            self.consume(Token::LeftBrace, "Expect '{' before catch body.")?;
+            Some(Box::new(AstNode::CatchClause {
+                param,
+                body: Box::new(self.block()?),
+            }))
+        } else {
+            None
+        };
+        let finalizer = if self.match_token(Token::Finally) {
+            self.consume(Token::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(Box::new(self.block()?))
+        } else {
+            None
+        };
+        if handler.is_none() && finalizer.is_none() {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "'catch' or 'finally'",
+                "Expect 'catch' or 'finally' after try block.",
+            ));
+        }
+        Ok(AstNode::TryStatement {
+            block: Box::new(block),
+            handler,
+            finalizer,
+        })
+    }
+
+    fn throw_statement(&mut self) -> ParseResult<AstNode> {
+        if self.tokens[self.current].newline_before {
+            return Err(self.error(
+                ErrorCode::NewlineAfterThrow,
+                "expression",
+                "Illegal newline after 'throw'.",
+            ));
+        }
+        let argument = self.parse_expression()?;
+        self.consume_semicolon("Expect ';' after thrown expression.")?;
+        Ok(AstNode::ThrowStatement {
+            argument: Box::new(argument),
+        })
+    }
+
+    /// Parses the optional label after `break` or `continue`. A label on the
+    /// next line is a new statement.
+    fn jump_label(&mut self, keyword: &str) -> ParseResult<Option<Box<AstNode>>> {
+        let label = if !self.can_insert_semicolon() && self.is_identifier() {
+            let name = self.consume_identifier("Expect label name.")?;
+            Some(Box::new(AstNode::identifier(name)))
+        } else {
+            None
+        };
+        self.consume_semicolon(&format!("Expect ';' after '{}'.", keyword))?;
+        Ok(label)
+    }
+
+    fn with_statement(&mut self) -> ParseResult<AstNode> {
+        if self.strict {
+            let error = self.error_at(
+                self.current - 1,
+                ErrorCode::StrictWith,
+                "statement",
+                "'with' statements are not allowed in strict mode.",
+            );
+            self.errors.push(error);
+        }
+        self.consume(Token::LeftParen, "Expect '(' after 'with'.")?;
+        let object = self.parse_expression()?;
+        self.consume(Token::RightParen, "Expect ')' after with object.")?;
+        let body = self.statement()?;
+        Ok(AstNode::WithStatement {
+            object: Box::new(object),
+            body: Box::new(body),
+        })
+    }
+
+    /// Parses an expression statement. It may not start like a declaration,
+    /// so a declaration where only a statement is allowed, as in
+    /// `while (a) class B {}`, is reported and parsed as a declaration.
+    fn expression_statement(&mut self) -> ParseResult<AstNode> {
+        let message = match self.peek() {
+            Token::Function => {
+                Some("Functions can only be declared at the top level, inside a block or as the body of an 'if' statement.")
+            }
+            Token::Class => Some("Classes can only be declared at the top level or inside a block."),
+            _ if self.is_async_function() => {
+                Some("Async functions can only be declared at the top level or inside a block.")
+            }
+            Token::Identifier(name) if name == "let" && self.peek_next() == &Token::LeftBracket => {
+                Some("Lexical declaration cannot appear in a single-statement context.")
+            }
+            _ => None,
+        };
+        if let Some(message) = message {
+            let error = self.error(ErrorCode::UnexpectedToken, "statement", message);
+            self.errors.push(error);
+            return self.try_declaration();
+        }
+        let expression = self.parse_expression()?;
+        self.consume_semicolon("Expect ';' after expression.")?;
+        Ok(AstNode::ExpressionStatement {
+            expression: Box::new(expression),
+            directive: None,
+        })
+    }
+
+    /// Parses member accesses, calls and `new` expressions. A chain that
+    /// contains `?.` is wrapped in a `ChainExpression`.
+    fn parse_call_expression(&mut self) -> ParseResult<AstNode> {
+        let mut callee = if self.check(&Token::New) {
+            self.parse_new_expression()?
+        } else {
+            self.parse_primary()?
+        };
+        let mut in_chain = false;
+        loop {
+            if self.match_token(Token::Dot) {
+                callee = self.parse_member_property(callee, false)?;
+            } else if self.match_token(Token::LeftBracket) {
+                callee = self.allowing_in(|parser| parser.parse_computed_member(callee, false))?;
+            } else if self.match_token(Token::LeftParen) {
+                callee = AstNode::CallExpression {
+                    callee: Box::new(callee),
+                    arguments: self.allowing_in(Self::parse_arguments)?,
+                    optional: false,
+                    type_arguments: None,
+                };
+            } else if let Some(type_arguments) = self.call_type_arguments() {
+                let type_arguments = Box::new(type_arguments);
+                if !self.match_token(Token::LeftParen) {
+                    callee = AstNode::TSInstantiationExpression {
+                        expression: Box::new(callee),
+                        type_arguments,
+                    };
+                    continue;
+                }
+                callee = AstNode::CallExpression {
+                    callee: Box::new(callee),
+                    arguments: self.allowing_in(Self::parse_arguments)?,
+                    optional: false,
+                    type_arguments: Some(type_arguments),
+                };
+            } else if self.options.typescript
+                && self.check(&Token::Bang)
+                && !self.tokens[self.current].newline_before
+            {
+                self.advance();
+                callee = AstNode::TSNonNullExpression {
+                    expression: Box::new(callee),
+                };
+            } else if self.match_token(Token::QuestionDot) {
+                self.require_version(self.current - 1, 2020, "optional chaining");
+                in_chain = true;
+                let type_arguments = if self.type_syntax() && self.check(&Token::Less) {
+                    let type_arguments = self.type_arguments()?;
+                    self.consume(Token::LeftParen, "Expect '(' after type arguments.")?;
+                    Some(Box::new(type_arguments))
+                } else {
+                    None
+                };
+                callee = if type_arguments.is_some() || self.match_token(Token::LeftParen) {
+                    AstNode::CallExpression {
+                        callee: Box::new(callee),
+                        arguments: self.allowing_in(Self::parse_arguments)?,
+                        optional: true,
+                        type_arguments,
+                    }
+                } else if self.match_token(Token::LeftBracket) {
+                    self.allowing_in(|parser| parser.parse_computed_member(callee, true))?
+                } else {
+                    self.parse_member_property(callee, true)?
+                };
+            } else {
+                break;
+            }
+        }
+        if in_chain {
+            callee = AstNode::ChainExpression {
+                expression: Box::new(callee),
+            };
+        }
+        Ok(callee)
+    }
+
+    /// Parses `new.target`, or `new Callee` with optional arguments. The
+    /// callee may contain member accesses but no calls, so `new a.b()` calls
+    /// the constructor `a.b`.
+    fn parse_new_expression(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::New, "Expect 'new'.")?;
+        if self.match_token(Token::Dot) {
+            let property = self.consume_property_name("Expect 'target' after 'new.'.")?;
+            if property != "target" {
+                return Err(self.error_at(
+                    self.current - 1,
+                    ErrorCode::UnexpectedToken,
+                    "'target'",
+                    "Expect 'target' after 'new.'.",
+                ));
+            }
+            self.require_version(self.current - 3, 2015, "'new.target'");
+            return Ok(AstNode::MetaProperty {
+                meta: Box::new(AstNode::identifier("new".to_string())),
+                property: Box::new(AstNode::identifier(property)),
+            });
+        }
+
+        if self.check(&Token::Import) && self.peek_next() == &Token::LeftParen {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "constructor",
+                "'import()' cannot be called with 'new'.",
+            ));
+        }
+        let mut callee = if self.check(&Token::New) {
+            self.parse_new_expression()?
+        } else {
+            self.parse_primary()?
+        };
+        loop {
+            if self.match_token(Token::Dot) {
+                callee = self.parse_member_property(callee, false)?;
+            } else if self.match_token(Token::LeftBracket) {
+                callee = self.allowing_in(|parser| parser.parse_computed_member(callee, false))?;
+            } else if self.check(&Token::QuestionDot) {
+                return Err(self.error(
+                    ErrorCode::InvalidOptionalChain,
+                    "'(' or end of expression",
+                    "Invalid optional chain from new expression.",
+                ));
+            } else {
+                break;
+            }
+        }
+        let type_arguments = self.call_type_arguments().map(Box::new);
+        let arguments = if self.match_token(Token::LeftParen) {
+            self.allowing_in(Self::parse_arguments)?
+        } else {
+            Vec::new()
+        };
+        Ok(AstNode::NewExpression {
+            callee: Box::new(callee),
+            arguments,
+            type_arguments,
+        })
+    }
+
+    fn parse_member_property(&mut self, object: AstNode, optional: bool) -> ParseResult<AstNode> {
+        let property = if let Token::PrivateIdentifier(name) = self.peek().clone() {
+            self.reference_private_name(name.clone(), self.current);
+            self.advance();
+            AstNode::PrivateIdentifier(name)
+        } else {
+            AstNode::identifier(self.consume_property_name("Expect property name after '.'.")?)
+        };
+        Ok(AstNode::MemberExpression {
+            object: Box::new(object),
+            property: Box::new(property),
+            computed: false,
+            optional,
+        })
+    }
+
+    fn parse_computed_member(&mut self, object: AstNode, optional: bool) -> ParseResult<AstNode> {
+        let property = self.parse_expression()?;
+        self.consume(Token::RightBracket, "Expect ']' after computed property.")?;
+        Ok(AstNode::MemberExpression {
+            object: Box::new(object),
+            property: Box::new(property),
+            computed: true,
+            optional,
+        })
+    }
+
+    /// Parses call arguments after the opening `(`.
+    fn parse_arguments(&mut self) -> ParseResult<Vec<AstNode>> {
+        let mut arguments = vec![];
+        // A trailing comma is allowed, as in `f(a, b,)`.
+        while !self.check(&Token::RightParen) {
+            arguments.push(self.parse_spread_or_assignment()?);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+            if self.check(&Token::RightParen) {
+                self.require_version(self.current - 1, 2017, "trailing commas in argument lists");
+            }
+        }
+        self.consume(Token::RightParen, "Expect ')' after arguments.")?;
+        Ok(arguments)
+    }
+
+    fn parse_spread_or_assignment(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::Ellipsis) {
+            self.require_version(self.current - 1, 2015, "spread elements");
+            Ok(AstNode::SpreadElement {
+                argument: Box::new(self.parse_assignment()?),
+            })
+        } else {
+            self.parse_assignment()
+        }
+    }
+
+    fn parse_expression(&mut self) -> ParseResult<AstNode> {
+        let expression = self.parse_assignment()?;
+        if !self.check(&Token::Comma) {
+            return Ok(expression);
+        }
+        let mut expressions = vec![expression];
+        while self.match_token(Token::Comma) {
+            expressions.push(self.parse_assignment()?);
+        }
+        Ok(AstNode::SequenceExpression { expressions })
+    }
+
+    fn parse_assignment(&mut self) -> ParseResult<AstNode> {
+        if self.is_arrow_function() || self.is_typed_arrow_function() {
+            return self.parse_arrow_function();
+        }
+        if self.in_generator && self.is_word("yield") {
+            return self.parse_yield();
+        }
+        let left_start = self.current;
+        let cover_start = self.cover_initializers.len();
+        let left = self.parse_ternary()?;
+        if self.check(&Token::PipeGreater) {
+            return self.parse_pipeline(left);
+        }
+
+        if is_assignment_operator(self.peek()) {
+            match self.peek() {
+                Token::StarStarEqual => {
+                    self.require_version(self.current, 2016, "exponentiation operator")
+                }
+                Token::AmpersandAmpersandEqual
+                | Token::PipePipeEqual
+                | Token::QuestionQuestionEqual => {
+                    self.require_version(self.current, 2021, "logical assignment operators")
+                }
+                _ => {}
+            }
+            let operator = self.advance().to_string();
+            // Only `=` destructures; compound operators need a simple target.
+            let left = if operator == "=" {
+                self.cover_initializers.truncate(cover_start);
+                self.assignment_target(left, left_start, true)
+            } else {
+                self.assignment_target(left, left_start, false)
+            };
+            let right = self.parse_assignment()?;
+            return Ok(AstNode::AssignmentExpression {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            });
+        }
+
+        Ok(left)
+    }
+
+    /// Parses the `|> body` steps of a pipeline after its first operand. Each
+    /// body refers to the value on its left as `%`, and must do so at least
+    /// once. Bodies bind like conditional expressions, so arrow functions,
+    /// assignments and `yield` need parentheses.
+    fn parse_pipeline(&mut self, mut left: AstNode) -> ParseResult<AstNode> {
+        while self.check(&Token::PipeGreater) {
+            self.require_feature(self.current, Feature::PipelineOperator);
+            self.advance();
+            let body_start = self.current;
+            let topic_references = self.topic_references.replace(0);
+            let body = self.parse_ternary();
+            let used = std::mem::replace(&mut self.topic_references, topic_references);
+            let body = body?;
+            if used == Some(0) {
+                let error = self.error_at(
+                    body_start,
+                    ErrorCode::ExpectedExpression,
+                    "'%'",
+                    "Pipeline body must refer to the topic '%'.",
+                );
+                self.errors.push(error);
+            }
+            left = AstNode::BinaryExpression {
+                operator: "|>".to_string(),
+                left: Box::new(left),
+                right: Box::new(body),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses `yield`, `yield expression` or `yield* expression`. The
+    /// argument is optional and must start on the same line.
+    fn parse_yield(&mut self) -> ParseResult<AstNode> {
+        self.advance();
+        let starts_argument = !self.tokens[self.current].newline_before
+            && !matches!(
+                self.peek(),
+                Token::RightParen
+                    | Token::RightBracket
+                    | Token::RightBrace
+                    | Token::Comma
+                    | Token::Semicolon
+                    | Token::Colon
+                    | Token::In
+                    | Token::Question
+                    | Token::EOF
+            );
+        if starts_argument && self.match_token(Token::Star) {
+            return Ok(AstNode::YieldExpression {
+                argument: Some(Box::new(self.parse_assignment()?)),
+                delegate: true,
+            });
+        }
+        let argument = if starts_argument {
+            Some(Box::new(self.parse_assignment()?))
+        } else {
+            None
+        };
+        Ok(AstNode::YieldExpression {
+            argument,
+            delegate: false,
+        })
+    }
+
+    /// Looks ahead for `=>` after a parameter name or a parenthesised list,
+    /// optionally preceded by `async`. This decides between an arrow
+    /// function and a parenthesised expression before parsing either.
+    fn is_arrow_function(&self) -> bool {
+        let mut index = self.current;
+        if matches!(&self.tokens[index].token, Token::Identifier(name) if name == "async")
+            && !self.tokens[index + 1].newline_before
+            && matches!(
+                self.tokens[index + 1].token,
+                Token::Identifier(_) | Token::Await | Token::LeftParen
+            )
+        {
+            index += 1;
+        }
+        match self.tokens[index].token {
+            Token::Identifier(_) => {}
+            Token::Await if self.await_is_identifier() => {}
+            Token::LeftParen => {
+                let mut depth = 0;
+                loop {
+                    match self.tokens[index].token {
+                        Token::LeftParen | Token::LeftBracket | Token::LeftBrace => depth += 1,
+                        Token::RightParen | Token::RightBracket | Token::RightBrace => depth -= 1,
+                        Token::EOF => return false,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    index += 1;
+                }
+            }
+            _ => return false,
+        }
+        self.tokens[index + 1].token == Token::Arrow
+    }
+
+    fn parse_arrow_function(&mut self) -> ParseResult<AstNode> {
+        let is_async = self.is_word("async") && self.peek_next() != &Token::Arrow;
+        if is_async {
+            self.require_version(self.current, 2017, "async arrow functions");
+        } else {
+            self.require_version(self.current, 2015, "arrow functions");
+        }
+        if is_async {
+            self.advance();
+        }
+        // Arrow functions are never generators, so `yield` is not an
+        // operator inside them.
+        let in_async = std::mem::replace(&mut self.in_async, is_async);
+        let in_generator = std::mem::replace(&mut self.in_generator, false);
+        let result = self.arrow_function_rest(is_async);
+        self.in_async = in_async;
+        self.in_generator = in_generator;
+        result
+    }
+
+    fn arrow_function_rest(&mut self, is_async: bool) -> ParseResult<AstNode> {
+        let type_parameters = self.parse_type_parameters()?;
+        let params = if self.match_token(Token::LeftParen) {
+            self.parse_params()?
+        } else {
+            vec![AstNode::identifier(
+                self.consume_identifier("Expect parameter name.")?,
+            )]
+        };
+        let return_type = self.parse_return_type()?;
+        if self.tokens[self.current].newline_before {
+            let error = self.error(
+                ErrorCode::NewlineBeforeArrow,
+                "'=>'",
+                "Line terminator not permitted before '=>'.",
+            );
+            self.errors.push(error);
+        }
+        self.consume(Token::Arrow, "Expect '=>' after arrow function parameters.")?;
+        let (body, expression) = if self.match_token(Token::LeftBrace) {
+            (self.allowing_in(Self::function_body)?, false)
+        } else {
+            (self.parse_assignment()?, true)
+        };
+        Ok(AstNode::ArrowFunctionExpression {
+            params,
+            body: Box::new(body),
+            expression,
+            r#async: is_async,
+            type_parameters,
+            return_type,
+        })
+    }
+
+    fn parse_ternary(&mut self) -> ParseResult<AstNode> {
+        let (left, _) = self.parse_binary(1)?;
+
+        if self.match_token(Token::Question) {
+            let consequent_start = self.consequent_start.replace(self.current);
+            let middle = self.allowing_in(Self::parse_assignment);
+            self.consequent_start = consequent_start;
+            let middle = middle?;
+            self.consume(Token::Colon, "Expect ':' after '?' in ternary operator.")?;
+            let right = self.parse_assignment()?;
+            return Ok(AstNode::TernaryExpression {
+                left: Box::new(left),
+                middle: Box::new(middle),
+                right: Box::new(right),
+            });
+        }
+
+        Ok(left)
+    }
+
+    /// Precedence climbing over the operators in `binary_precedence`,
+    /// consuming only operators that bind at least as tightly as
+    /// `min_precedence`. Also returns the operator at the root of the parsed
+    /// expression, if that root was not parenthesised, so that callers can
+    /// reject `??` mixed with `||` or `&&`.
+    fn parse_binary(&mut self, min_precedence: u8) -> ParseResult<(AstNode, Option<Token>)> {
+        let operand_start = self.current;
+        let mut left = self.parse_unary()?;
+        let mut left_operator = None;
+
+        // `-a ** b` is ambiguous and has to be written `(-a) ** b`.
+        if self.check(&Token::StarStar)
+            && min_precedence <= binary_precedence(&Token::StarStar).unwrap()
+            && self.tokens[operand_start].token != Token::LeftParen
+            && matches!(
+                left,
+                AstNode::UnaryExpression { .. } | AstNode::AwaitExpression { .. }
+            )
+        {
+            let error = self.error_at(
+                operand_start,
+                ErrorCode::UnaryBeforeExponent,
+                "parenthesized expression",
+                "Unary operator used immediately before '**'.",
+            );
+            self.errors.push(error);
+        }
+
+        loop {
+            // TypeScript's `as` and `satisfies` bind like relational operators.
+            if self.options.typescript
+                && min_precedence <= 7
+                && (self.is_word("as") || self.is_word("satisfies"))
+                && !self.tokens[self.current].newline_before
+            {
+                left = self.ts_as_expression(left)?;
+                left_operator = None;
+                continue;
+            }
+            let Some(precedence) = binary_precedence(self.peek()) else {
+                break;
+            };
+            if precedence < min_precedence || (!self.allow_in && self.check(&Token::In)) {
+                break;
+            }
+            let operator_index = self.current;
+            let operator = self.advance().clone();
+            match operator {
+                Token::StarStar => {
+                    self.require_version(operator_index, 2016, "exponentiation operator")
+                }
+                Token::QuestionQuestion => {
+                    self.require_version(operator_index, 2020, "nullish coalescing operator")
+                }
+                _ => {}
+            }
+            // `**` is right-associative; every other operator is left-associative.
+            let next_precedence = if operator == Token::StarStar {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let (right, right_operator) = self.parse_binary(next_precedence)?;
+
+            if [&left_operator, &right_operator]
+                .into_iter()
+                .flatten()
+                .any(|operand| mixes_coalesce(&operator, operand))
+            {
+                let error = self.error_at(
+                    operator_index,
+                    ErrorCode::MixedCoalesce,
+                    "parentheses",
+                    "Cannot mix '??' with '||' or '&&' without parentheses.",
+                );
+                self.errors.push(error);
+            }
+
+            left = match operator {
+                Token::PipePipe | Token::AmpersandAmpersand | Token::QuestionQuestion => {
+                    AstNode::LogicalExpression {
+                        operator: operator.to_string(),
+                        left: Box::new(left),
+                        right: Box::new(right),
+                    }
+                }
+                _ => AstNode::BinaryExpression {
+                    operator: operator.to_string(),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+            };
+            left_operator = Some(operator);
+        }
+
+        Ok((left, left_operator))
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<AstNode> {
+        match self.peek() {
+            Token::Bang
+            | Token::Tilde
+            | Token::Plus
+            | Token::Minus
+            | Token::Typeof
+            | Token::Void
+            | Token::Delete => {
+                let operator = self.advance().clone();
+                let argument_start = self.current;
+                let argument = self.parse_unary()?;
+                if operator == Token::Delete
+                    && self.strict
+                    && matches!(argument, AstNode::Identifier { .. })
+                {
+                    let error = self.error_at(
+                        argument_start,
+                        ErrorCode::StrictDelete,
+                        "property reference",
+                        "Delete of an unqualified identifier in strict mode.",
+                    );
+                    self.errors.push(error);
+                }
+                if operator == Token::Delete && is_private_reference(&argument) {
+                    let error = self.error_at(
+                        argument_start,
+                        ErrorCode::StrictDelete,
+                        "property reference",
+                        "Private fields cannot be deleted.",
+                    );
+                    self.errors.push(error);
+                }
+                Ok(AstNode::UnaryExpression {
+                    operator: operator.to_string(),
+                    argument: Box::new(argument),
+                })
+            }
+            Token::PlusPlus | Token::MinusMinus => {
+                let operator = self.advance().to_string();
+                let argument_start = self.current;
+                let argument = self.parse_unary()?;
+                self.check_update_target(&argument, argument_start);
+                Ok(AstNode::UpdateExpression {
+                    operator,
+                    prefix: true,
+                    argument: Box::new(argument),
+                })
+            }
+            Token::Await if self.in_async => {
+                self.advance();
+                let argument = self.parse_unary()?;
+                Ok(AstNode::AwaitExpression {
+                    argument: Box::new(argument),
+                })
+            }
+            Token::Throw => {
+                self.require_feature(self.current, Feature::ThrowExpressions);
+                self.advance();
+                let argument = self.parse_unary()?;
+                Ok(AstNode::ThrowExpression {
+                    argument: Box::new(argument),
+                })
+            }
+            // TSX has no `<T>x` assertions, as they would read as tags.
+            Token::Less if self.options.typescript && !self.options.jsx => {
+                self.advance();
+                let type_annotation = if self.match_token(Token::Const) {
+                    const_type_reference()
+                } else {
+                    self.ts_type()?
+                };
+                self.consume_type_closer("Expect '>' after type assertion.")?;
+                let expression = self.parse_unary()?;
+                Ok(AstNode::TSTypeAssertion {
+                    type_annotation: Box::new(type_annotation),
+                    expression: Box::new(expression),
+                })
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult<AstNode> {
+        let argument_start = self.current;
+        let argument = self.parse_call_expression()?;
+        // No line break is allowed before a postfix `++` or `--`.
+        if (self.check(&Token::PlusPlus) || self.check(&Token::MinusMinus))
+            && !self.tokens[self.current].newline_before
+        {
+            let operator = self.advance().to_string();
+            self.check_update_target(&argument, argument_start);
+            return Ok(AstNode::UpdateExpression {
+                operator,
+                prefix: false,
+                argument: Box::new(argument),
+            });
+        }
+        Ok(argument)
+    }
+
+    /// Reinterprets an expression parsed before `=` (or in a `for`...`in`/`of`
+    /// head) as an assignment target, reporting targets that cannot be
+    /// assigned to.
+    fn assignment_target(&mut self, left: AstNode, start: usize, allow_patterns: bool) -> AstNode {
+        if matches!(
+            left,
+            AstNode::ArrayExpression { .. } | AstNode::ObjectExpression { .. }
+        ) {
+            self.require_version(start, 2015, "destructuring assignment");
+        }
+        let target = if allow_patterns {
+            to_assignment_target(left.clone())
+        } else {
+            is_simple_target(&left).then(|| left.clone())
+        };
+        // A literal in parentheses is an expression, not a pattern.
+        let target = target.filter(|target| {
+            is_simple_target(target) || self.tokens[start].token != Token::LeftParen
+        });
+        let is_pattern = target
+            .as_ref()
+            .is_some_and(|target| !is_simple_target(target));
+        if let Some(index) = is_pattern
+            .then(|| self.invalid_pattern_token(start))
+            .flatten()
+        {
+            let error = self.error_at(
+                index,
+                ErrorCode::InvalidAssignmentTarget,
+                "pattern",
+                "Invalid destructuring assignment target.",
+            );
+            self.errors.push(error);
+            return left;
+        }
+        target.unwrap_or_else(|| {
+            let error = self.error_at(
+                start,
+                ErrorCode::InvalidAssignmentTarget,
+                "identifier, property access or pattern",
+                "Invalid left-hand side in assignment.",
+            );
+            self.errors.push(error);
+            left
+        })
+    }
+
+    /// Finds what only the tokens of the array or object literal at `start`
+    /// show to keep it from being a pattern: a nested literal in
+    /// parentheses, or a comma after a rest element. Returns the index of
+    /// the offending token.
+    fn invalid_pattern_token(&self, start: usize) -> Option<usize> {
+        let close = self.matching_bracket(start);
+        let is_array = self.tokens[start].token == Token::LeftBracket;
+        let mut index = start + 1;
+        while index < close {
+            // The element runs up to the next comma outside any brackets.
+            let mut end = index;
+            let mut colon = None;
+            while end < close && self.tokens[end].token != Token::Comma {
+                match self.tokens[end].token {
+                    Token::LeftParen | Token::LeftBracket | Token::LeftBrace => {
+                        end = self.matching_bracket(end)
+                    }
+                    Token::Colon if colon.is_none() => colon = Some(end),
+                    _ => {}
+                }
+                end += 1;
+            }
+            let target = if self.tokens[index].token == Token::Ellipsis {
+                if end < close {
+                    return Some(end);
+                }
+                Some(index + 1)
+            } else if is_array {
+                Some(index)
+            } else {
+                // Only the value after `key:` can be a nested pattern.
+                colon.map(|colon| colon + 1)
+            };
+            if let Some(target) = target.filter(|&target| target < end) {
+                let mut inner = target;
+                while self.tokens[inner].token == Token::LeftParen {
+                    inner += 1;
+                }
+                if matches!(
+                    self.tokens[inner].token,
+                    Token::LeftBracket | Token::LeftBrace
+                ) {
+                    // Whether the brackets at `index` enclose the whole
+                    // element, apart from a default value.
+                    let is_element = |index: usize| {
+                        let after = self.matching_bracket(index) + 1;
+                        after == end || self.tokens[after].token == Token::Equal
+                    };
+                    let inner_close = self.matching_bracket(inner);
+                    if inner > target
+                        && inner_close + (inner - target) == self.matching_bracket(target)
+                        && is_element(target)
+                    {
+                        return Some(target);
+                    }
+                    if inner == target && is_element(inner) {
+                        if let Some(index) = self.invalid_pattern_token(inner) {
+                            return Some(index);
+                        }
+                    }
+                }
+            }
+            index = end + 1;
+        }
+        None
+    }
+
+    fn check_update_target(&mut self, target: &AstNode, start: usize) {
+        if !is_simple_target(target) {
+            let error = self.error_at(
+                start,
+                ErrorCode::InvalidUpdateTarget,
+                "identifier or property access",
+                "Invalid left-hand side expression in update operation.",
+            );
+            self.errors.push(error);
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::False) {
+            Ok(AstNode::BooleanLiteral(false))
+        } else if self.match_token(Token::True) {
+            Ok(AstNode::BooleanLiteral(true))
+        } else if self.match_token(Token::Null) {
+            Ok(AstNode::NullLiteral)
+        } else if self.match_token(Token::This) {
+            Ok(AstNode::ThisExpression)
+        } else if self.match_token(Token::LeftParen) {
+            let mut expression = self.allowing_in(Self::parse_expression)?;
+            if self.options.flow && self.match_token(Token::Colon) {
+                expression = AstNode::TypeCastExpression {
+                    expression: Box::new(expression),
+                    type_annotation: self.flow_type_annotation()?,
+                };
+            }
+            self.consume(Token::RightParen, "Expect ')' after expression.")?;
+            Ok(expression)
+        } else if self.match_token(Token::Function) {
+            self.function(false, false)
+        } else if self.match_token(Token::Class) {
+            self.class(false, Vec::new())
+        } else if self.check(&Token::At) {
+            let decorators = self.decorators()?;
+            self.consume(Token::Class, "Expect 'class' after decorators.")?;
+            self.class(false, decorators)
+        } else if self.check(&Token::Super) {
+            self.parse_super()
+        } else if self.check(&Token::Import) {
+            self.parse_import_expression()
+        } else if self.check(&Token::Do) {
+            self.require_feature(self.current, Feature::DoExpressions);
+            self.advance();
+            self.consume(Token::LeftBrace, "Expect '{' after 'do'.")?;
+            let body = self.allowing_in(Self::block)?;
+            Ok(AstNode::DoExpression {
+                body: Box::new(body),
+            })
+        } else if self.is_async_function() {
+            self.advance();
+            self.advance();
+            self.function(true, false)
+        } else if self.options.jsx && self.check(&Token::Less) {
+            self.relex_jsx_start();
+            self.advance();
+            self.jsx_element()
+        } else if self.match_token(Token::LeftBracket) {
+            self.allowing_in(Self::parse_array_literal)
+        } else if self.match_token(Token::LeftBrace) {
+            self.allowing_in(Self::parse_object_literal)
+        } else {
+            match self.peek().clone() {
+                Token::Number(value) => {
+                    self.advance();
+                    Ok(AstNode::NumberLiteral(value))
+                }
+                Token::LegacyOctal(value) => {
+                    self.legacy_octal();
+                    Ok(AstNode::NumberLiteral(value))
+                }
+                Token::String(value) => {
+                    self.advance();
+                    Ok(AstNode::StringLiteral(value))
+                }
+                // A private name on its own is only valid in `#x in obj`.
+                Token::PrivateIdentifier(name) if self.peek_next() == &Token::In => {
+                    self.reference_private_name(name.clone(), self.current);
+                    self.advance();
+                    Ok(AstNode::PrivateIdentifier(name))
+                }
+                Token::Identifier(name) => {
+                    self.advance();
+                    Ok(AstNode::identifier(name))
+                }
+                Token::Percent if self.topic_references.is_some() => {
+                    self.topic_references = self.topic_references.map(|count| count + 1);
+                    self.advance();
+                    Ok(AstNode::TopicReference)
+                }
+                // Outside async functions `await` is an ordinary identifier,
+                // except in modules where it is reserved.
+                Token::Await if self.options.source_type == SourceType::Module => Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "expression",
+                    "'await' is only valid in async functions and at the top level of modules.",
+                )),
+                Token::Await => {
+                    self.advance();
+                    Ok(AstNode::identifier("await".to_string()))
+                }
+                _ => Err(self.error(
+                    ErrorCode::ExpectedExpression,
+                    "expression",
+                    "Expect expression.",
+                )),
+            }
+        }
+    }
+
+    /// Lexes the tokens from the current `<` again as the start of JSX, if
+    /// the lexer took it for an operator because of the `)`, `]` or `}`
+    /// before it. Lexing stops once a token lines up with the old ones again.
+    fn relex_jsx_start(&mut self) {
+        let (Some(source), Some(previous)) = (&self.source, self.current.checked_sub(1)) else {
+            return;
+        };
+        let Some(state) = self
+            .states
+            .get(previous)
+            .filter(|state| state.after_expression())
+        else {
+            return;
+        };
+        let resume_at = self.tokens[previous].span.end;
+        let state = state.clone().expecting_expression();
+        let mut lexer = Lexer::resuming(source, resume_at, &self.options, state);
+        let mut tokens = Vec::new();
+        let mut states = Vec::new();
+        let mut errors = Vec::new();
+        let end = loop {
+            let token = lexer.next_spanned_token();
+            let index = self
+                .tokens
+                .partition_point(|old| old.span.start < token.span.start);
+            if let Some(old) = self.tokens.get(index) {
+                if old == &token && self.states[index] == lexer.state() {
+                    break index;
+                }
+            }
+            errors.extend(lexer.take_errors());
+            let at_end = token.token == Token::EOF;
+            tokens.push(token);
+            states.push(lexer.state());
+            if at_end {
+                break self.tokens.len();
+            }
+        };
+        // The lexer errors up to the first token that lined up are replaced.
+        let resync_at = self
+            .tokens
+            .get(end)
+            .map_or(usize::MAX, |token| token.span.start);
+        let error_range = self
+            .lexer_errors
+            .partition_point(|error| error.span.start < resume_at)
+            ..self
+                .lexer_errors
+                .partition_point(|error| error.span.start < resync_at);
+        self.replace_tokens(self.current..end, tokens, states, error_range, errors);
+    }
+
+    /// Parses a JSX element or fragment after its `<`.
+    fn jsx_element(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::Greater) {
+            let children = self.jsx_children()?;
+            let index = self.current;
+            if self
+                .jsx_closing_tag("Expect '</>' after JSX fragment.")?
+                .is_some()
+            {
+                let error = self.error_at(
+                    index + 2,
+                    ErrorCode::MismatchedClosingTag,
+                    "'</>'",
+                    "Expected corresponding closing tag for <>.",
+                );
+                self.errors.push(error);
+            }
+            return Ok(AstNode::JSXFragment { children });
+        }
+        let name = self.jsx_element_name()?;
+        let mut attributes = Vec::new();
+        while !self.check(&Token::Slash) && !self.check(&Token::Greater) && !self.is_at_end() {
+            attributes.push(self.jsx_attribute()?);
+        }
+        let self_closing = self.match_token(Token::Slash);
+        self.consume(Token::Greater, "Expect '>' after JSX tag.")?;
+        let opening_element = Box::new(AstNode::JSXOpeningElement {
+            name: Box::new(name.clone()),
+            attributes,
+            self_closing,
+        });
+        if self_closing {
+            return Ok(AstNode::JSXElement {
+                opening_element,
+                children: Vec::new(),
+                closing_element: None,
+            });
+        }
+        let children = self.jsx_children()?;
+        let index = self.current;
+        let tag = jsx_tag_name(&name);
+        let closing_name = self.jsx_closing_tag(&format!("Expect closing tag for <{}>.", tag))?;
+        if closing_name.as_ref() != Some(&name) {
+            let error = self.error_at(
+                index + 2,
+                ErrorCode::MismatchedClosingTag,
+                format!("'</{}>'", tag),
+                &format!("Expected corresponding closing tag for <{}>.", tag),
+            );
+            self.errors.push(error);
+        }
+        Ok(AstNode::JSXElement {
+            opening_element,
+            children,
+            closing_element: Some(Box::new(AstNode::JSXClosingElement {
+                name: Box::new(closing_name.unwrap_or(name)),
+            })),
+        })
+    }
+
+    /// Parses a tag name: an identifier, `namespace:name` or `Member.Name`.
+    fn jsx_element_name(&mut self) -> ParseResult<AstNode> {
+        let mut name = self.jsx_identifier("Expect JSX tag name.")?;
+        if self.match_token(Token::Colon) {
+            return Ok(AstNode::JSXNamespacedName {
+                namespace: Box::new(name),
+                name: Box::new(self.jsx_identifier("Expect name after ':'.")?),
+            });
+        }
+        while self.match_token(Token::Dot) {
+            name = AstNode::JSXMemberExpression {
+                object: Box::new(name),
+                property: Box::new(self.jsx_identifier("Expect name after '.'.")?),
+            };
+        }
+        Ok(name)
+    }
+
+    fn jsx_identifier(&mut self, message: &str) -> ParseResult<AstNode> {
+        match self.peek().clone() {
+            Token::JSXIdentifier(name) => {
+                self.advance();
+                Ok(AstNode::JSXIdentifier(name))
+            }
+            _ => Err(self.error(ErrorCode::ExpectedIdentifier, "JSX identifier", message)),
+        }
+    }
+
+    /// Parses `name`, `name="value"`, `name={expression}`, `name=<element />`
+    /// or `{...spread}` in an opening tag.
+    fn jsx_attribute(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::LeftBrace) {
+            self.consume(Token::Ellipsis, "Expect '...' in JSX spread attribute.")?;
+            let argument = self.allowing_in(Self::parse_assignment)?;
+            self.consume(Token::RightBrace, "Expect '}' after JSX spread attribute.")?;
+            return Ok(AstNode::JSXSpreadAttribute {
+                argument: Box::new(argument),
+            });
+        }
+        let mut name = self.jsx_identifier("Expect JSX attribute name.")?;
+        if self.match_token(Token::Colon) {
+            name = AstNode::JSXNamespacedName {
+                namespace: Box::new(name),
+                name: Box::new(self.jsx_identifier("Expect name after ':'.")?),
+            };
+        }
+        let value = if self.match_token(Token::Equal) {
+            let value = match self.peek().clone() {
+                Token::String(value) => {
+                    self.advance();
+                    AstNode::StringLiteral(value)
+                }
+                Token::LeftBrace if self.peek_next() == &Token::RightBrace => {
+                    return Err(self.error_at(
+                        self.current + 1,
+                        ErrorCode::ExpectedExpression,
+                        "expression",
+                        "JSX attributes must be assigned a non-empty expression.",
+                    ));
+                }
+                Token::LeftBrace => {
+                    self.advance();
+                    self.jsx_expression_container(false)?
+                }
+                Token::Less => {
+                    self.advance();
+                    self.jsx_element()?
+                }
+                _ => {
+                    return Err(self.error(
+                        ErrorCode::ExpectedExpression,
+                        "JSX attribute value",
+                        "Expect string, '{' or element after '='.",
+                    ))
+                }
+            };
+            Some(Box::new(value))
+        } else {
+            None
+        };
+        Ok(AstNode::JSXAttribute {
+            name: Box::new(name),
+            value,
+        })
+    }
+
+    /// Parses text, expression containers and elements up to a closing tag.
+    fn jsx_children(&mut self) -> ParseResult<Vec<AstNode>> {
+        let mut children = Vec::new();
+        loop {
+            match self.peek().clone() {
+                Token::JSXText(text) => {
+                    self.advance();
+                    children.push(AstNode::JSXText(text));
+                }
+                Token::LeftBrace => {
+                    self.advance();
+                    children.push(self.jsx_expression_container(true)?);
+                }
+                Token::Less if self.peek_next() != &Token::Slash => {
+                    self.advance();
+                    children.push(self.jsx_element()?);
+                }
+                _ => return Ok(children),
+            }
+        }
+    }
+
+    /// Parses the rest of a `{}` after its `{`. Children may also be empty
+    /// or spread.
+    fn jsx_expression_container(&mut self, child: bool) -> ParseResult<AstNode> {
+        let node = if self.check(&Token::RightBrace) {
+            AstNode::JSXExpressionContainer {
+                expression: Box::new(AstNode::JSXEmptyExpression),
+            }
+        } else if child && self.match_token(Token::Ellipsis) {
+            AstNode::JSXSpreadChild {
+                expression: Box::new(self.allowing_in(Self::parse_expression)?),
+            }
+        } else {
+            AstNode::JSXExpressionContainer {
+                expression: Box::new(self.allowing_in(Self::parse_expression)?),
+            }
+        };
+        self.consume(Token::RightBrace, "Expect '}' after JSX expression.")?;
+        Ok(node)
+    }
+
+    /// Parses `</name>`, returning the name, or `</>`, returning `None`.
+    fn jsx_closing_tag(&mut self, message: &str) -> ParseResult<Option<AstNode>> {
+        self.consume(Token::Less, message)?;
+        self.consume(Token::Slash, message)?;
+        if self.match_token(Token::Greater) {
+            return Ok(None);
+        }
+        let name = self.jsx_element_name()?;
+        self.consume(Token::Greater, "Expect '>' after JSX closing tag.")?;
+        Ok(Some(name))
+    }
+
+    /// Parses the elements of an array literal after its `[`. A comma with
+    /// no element before it leaves a hole.
+    fn parse_array_literal(&mut self) -> ParseResult<AstNode> {
+        let mut elements = Vec::new();
+        while !self.check(&Token::RightBracket) {
+            if self.match_token(Token::Comma) {
+                elements.push(None);
+                continue;
+            }
+            elements.push(Some(self.parse_spread_or_assignment()?));
+            if !self.check(&Token::RightBracket) {
+                self.consume(Token::Comma, "Expect ',' or ']' after array element.")?;
+            }
+        }
+        self.consume(Token::RightBracket, "Expect ']' after array elements.")?;
+        Ok(AstNode::ArrayExpression { elements })
+    }
+
+    /// Parses the properties of an object literal after its `{`.
+    fn parse_object_literal(&mut self) -> ParseResult<AstNode> {
+        let mut properties = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            properties.push(self.parse_property()?);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBrace, "Expect '}' after object properties.")?;
+        Ok(AstNode::ObjectExpression { properties })
+    }
+
+    fn parse_property(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::Ellipsis) {
+            self.require_version(self.current - 1, 2018, "object spread properties");
+            return Ok(AstNode::SpreadElement {
+                argument: Box::new(self.parse_assignment()?),
+            });
+        }
+
+        // `async` and `*` introduce async and generator methods.
+        let is_async = self.is_word("async")
+            && !self.tokens[self.current + 1].newline_before
+            && (is_property_key_start(self.peek_next()) || self.peek_next() == &Token::Star);
+        if is_async {
+            self.require_version(self.current, 2017, "async methods");
+            self.advance();
+        }
+        let generator = self.match_generator_star(is_async);
+        if is_async || generator {
+            let (key, computed) = self.parse_property_key()?;
+            let type_parameters = self.parse_type_parameters()?;
+            self.consume(Token::LeftParen, "Expect '(' after method name.")?;
+            return Ok(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(self.method(type_parameters, is_async, generator, false, false)?),
+                kind: "init".to_string(),
+                computed,
+                shorthand: false,
+                method: true,
+            });
+        }
+
+        // `get` and `set` only introduce an accessor when a key follows;
+        // `{ get: 1 }` and `{ get() {} }` use them as ordinary names.
+        let accessor = match self.peek() {
+            Token::Identifier(name)
+                if (name == "get" || name == "set") && is_property_key_start(self.peek_next()) =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        };
+        if let Some(kind) = accessor {
+            self.advance();
+            let key_start = self.current;
+            let (key, computed) = self.parse_property_key()?;
+            self.consume(
+                Token::LeftParen,
+                &format!("Expect '(' after {}ter name.", kind),
+            )?;
+            let value = self.method(None, false, false, false, false)?;
+            self.check_accessor_params(&kind, &value, key_start);
+            return Ok(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(value),
+                kind,
+                computed,
+                shorthand: false,
+                method: false,
+            });
+        }
+
+        let is_shorthand = self.is_identifier();
+        let key_start = self.current;
+        let (key, computed) = self.parse_property_key()?;
+        if self.match_token(Token::Colon) {
+            Ok(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(self.parse_assignment()?),
+                kind: "init".to_string(),
+                computed,
+                shorthand: false,
+                method: false,
+            })
+        } else if self.check(&Token::LeftParen) || (self.type_syntax() && self.check(&Token::Less))
+        {
+            self.require_version(key_start, 2015, "shorthand methods");
+            let type_parameters = self.parse_type_parameters()?;
+            self.consume(Token::LeftParen, "Expect '(' after method name.")?;
+            Ok(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(self.method(type_parameters, false, false, false, false)?),
+                kind: "init".to_string(),
+                computed,
+                shorthand: false,
+                method: true,
+            })
+        } else if is_shorthand {
+            // Shorthand `{ a }` is only allowed for names that are valid
+            // identifier references. `{ a = 1 }` is kept as a pattern in case
+            // the literal is destructured.
+            self.require_version(key_start, 2015, "shorthand properties");
+            let value = if self.check(&Token::Equal) {
+                self.cover_initializers.push(self.current);
+                self.advance();
+                AstNode::AssignmentPattern {
+                    left: Box::new(key.clone()),
+                    right: Box::new(self.parse_assignment()?),
+                }
+            } else {
+                key.clone()
+            };
+            Ok(AstNode::Property {
+                key: Box::new(key),
+                value: Box::new(value),
+                kind: "init".to_string(),
+                computed: false,
+                shorthand: true,
+                method: false,
+            })
+        } else {
+            Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "':'",
+                "Expect ':' after property name.",
+            ))
+        }
+    }
+
+    /// Parses a property name: an IdentifierName, a string or number
+    /// literal, or a computed `[expression]`. Also returns whether the key is
+    /// computed.
+    fn parse_property_key(&mut self) -> ParseResult<(AstNode, bool)> {
+        match self.peek().clone() {
+            Token::String(value) => {
+                self.advance();
+                Ok((AstNode::StringLiteral(value), false))
+            }
+            Token::Number(value) => {
+                self.advance();
+                Ok((AstNode::NumberLiteral(value), false))
+            }
+            Token::LegacyOctal(value) => {
+                self.legacy_octal();
+                Ok((AstNode::NumberLiteral(value), false))
+            }
+            Token::LeftBracket => {
+                self.require_version(self.current, 2015, "computed property names");
+                self.advance();
+                let key = self.parse_assignment()?;
+                self.consume(
+                    Token::RightBracket,
+                    "Expect ']' after computed property name.",
+                )?;
+                Ok((key, true))
+            }
+            _ => {
+                let name = self.consume_property_name("Expect property name.")?;
+                Ok((AstNode::identifier(name), false))
+            }
+        }
+    }
+
+    /// Runs `parse` with the `in` operator allowed again, for brackets and
+    /// bodies nested inside a `for` head initialiser.
+    fn allowing_in<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        let allow_in = std::mem::replace(&mut self.allow_in, true);
+        let result = parse(self);
+        self.allow_in = allow_in;
+        result
+    }
+
+    /// Parses an import declaration after the `import` keyword, including
+    /// TypeScript's `import type` and `import x = require("m")`.
+    fn import_declaration(&mut self) -> ParseResult<AstNode> {
+        let import_kind = if self.is_type_import() {
+            self.advance().to_string()
+        } else {
+            "value".to_string()
+        };
+        if self.options.typescript && self.is_identifier() && self.peek_next() == &Token::Equal {
+            return self.ts_import_equals(&import_kind);
+        }
+        let phase = if import_kind == "value" {
+            self.import_phase()
+        } else {
+            None
+        };
+        let mut specifiers = Vec::new();
+        if !matches!(self.peek(), Token::String(_)) {
+            if self.is_identifier() {
+                let local = self.consume_identifier("Expect default import name.")?;
+                specifiers.push(AstNode::ImportDefaultSpecifier {
+                    local: Box::new(AstNode::identifier(local)),
+                });
+            }
+            if specifiers.is_empty() || self.match_token(Token::Comma) {
+                if self.match_token(Token::Star) {
+                    self.consume_word("as", "Expect 'as' after '*'.")?;
+                    let local = self.consume_identifier("Expect namespace name.")?;
+                    specifiers.push(AstNode::ImportNamespaceSpecifier {
+                        local: Box::new(AstNode::identifier(local)),
+                    });
+                } else {
+                    self.consume(Token::LeftBrace, "Expect '{' or '*' in import.")?;
+                    while !self.check(&Token::RightBrace) {
+                        specifiers.push(self.import_specifier()?);
+                        if !self.match_token(Token::Comma) {
+                            break;
+                        }
+                    }
+                    self.consume(Token::RightBrace, "Expect '}' after import specifiers.")?;
+                }
+            }
+            self.consume_word("from", "Expect 'from' after import specifiers.")?;
+        }
+        let source = self.module_source()?;
+        let attributes = self.import_attributes()?;
+        self.consume_semicolon("Expect ';' after import declaration.")?;
+        Ok(AstNode::ImportDeclaration {
+            specifiers,
+            source: Box::new(source),
+            attributes,
+            import_kind,
+            phase,
+        })
+    }
+
+    /// Consumes the phase of `import defer * as ns from "m"` or
+    /// `import source x from "m"`, which only take those forms. In
+    /// `import defer from "m"` the word is the default binding instead.
+    fn import_phase(&mut self) -> Option<String> {
+        let feature = match self.peek() {
+            Token::Identifier(word) if word == "defer" && self.peek_next() == &Token::Star => {
+                Feature::ImportDefer
+            }
+            Token::Identifier(word)
+                if word == "source"
+                    && matches!(self.peek_next(), Token::Identifier(_))
+                    && matches!(&self.tokens[self.current + 2].token, Token::Identifier(next) if next == "from") =>
+            {
+                Feature::SourcePhaseImports
+            }
+            _ => return None,
+        };
+        self.require_feature(self.current, feature);
+        Some(self.advance().to_string())
+    }
+
+    /// Parses `name` or `name as local`. A string or reserved word can only
+    /// be imported under another name.
+    fn import_specifier(&mut self) -> ParseResult<AstNode> {
+        let import_kind = if self.is_type_specifier() {
+            self.advance().to_string()
+        } else {
+            "value".to_string()
+        };
+        let is_binding = self.is_identifier();
+        let imported = self.module_export_name()?;
+        let local = if self.match_word("as") {
+            AstNode::identifier(self.consume_identifier("Expect local name after 'as'.")?)
+        } else if is_binding {
+            imported.clone()
+        } else {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "'as'",
+                "Expect 'as' after imported name.",
+            ));
+        };
+        Ok(AstNode::ImportSpecifier {
+            imported: Box::new(imported),
+            local: Box::new(local),
+            import_kind,
+        })
+    }
+
+    /// Parses an export declaration after the `export` keyword.
+    fn export_declaration(&mut self, decorators: Vec<AstNode>) -> ParseResult<AstNode> {
+        if self.match_token(Token::Default) {
+            return self.export_default(decorators);
+        }
+        if !decorators.is_empty() || self.check(&Token::At) {
+            let decorators = self.exported_class_decorators(decorators)?;
+            return Ok(AstNode::ExportNamedDeclaration {
+                declaration: Some(Box::new(self.class(true, decorators)?)),
+                specifiers: Vec::new(),
+                source: None,
+                attributes: Vec::new(),
+                export_kind: "value".to_string(),
+            });
+        }
+        if self.options.typescript {
+            if let Some(declaration) = self.ts_export_declaration() {
+                return declaration;
+            }
+        }
+        if self.options.flow {
+            if let Some(declaration) = self.flow_export_declaration() {
+                return declaration;
+            }
+        }
+        if self.match_token(Token::Star) {
+            let exported = if self.match_word("as") {
+                self.require_version(self.current - 1, 2020, "'export * as' declarations");
+                Some(Box::new(self.module_export_name()?))
+            } else {
+                None
+            };
+            self.consume_word("from", "Expect 'from' after export '*'.")?;
+            let source = self.module_source()?;
+            let attributes = self.import_attributes()?;
+            self.consume_semicolon("Expect ';' after export declaration.")?;
+            return Ok(AstNode::ExportAllDeclaration {
+                exported,
+                source: Box::new(source),
+                attributes,
+            });
+        }
+        if self.match_token(Token::LeftBrace) {
+            return self.export_specifiers();
+        }
+        let declaration = if let Some(kind) = self.variable_kind() {
+            self.advance();
+            self.var_declaration(kind)?
+        } else if self.match_token(Token::Function) {
+            self.function(false, true)?
+        } else if self.match_token(Token::Class) {
+            self.class(true, Vec::new())?
+        } else if self.is_async_function() {
+            self.advance();
+            self.advance();
+            self.function(true, true)?
+        } else {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "declaration",
+                "Expect declaration, '{' or '*' after 'export'.",
+            ));
+        };
+        Ok(AstNode::ExportNamedDeclaration {
+            declaration: Some(Box::new(declaration)),
+            specifiers: Vec::new(),
+            source: None,
+            attributes: Vec::new(),
+            export_kind: "value".to_string(),
+        })
+    }
+
+    /// Parses what follows `export default`: a function or class, whose
+    /// name is optional, or an expression.
+    fn export_default(&mut self, decorators: Vec<AstNode>) -> ParseResult<AstNode> {
+        let declaration = if !decorators.is_empty() || self.check(&Token::At) {
+            let decorators = self.exported_class_decorators(decorators)?;
+            into_declaration(self.class(false, decorators)?)
+        } else if self.options.typescript
+            && self.is_word("interface")
+            && matches!(self.peek_next(), Token::Identifier(_))
+        {
+            self.advance();
+            self.ts_interface()?
+        } else if self.options.typescript
+            && self.is_word("abstract")
+            && self.peek_next() == &Token::Class
+        {
+            self.advance();
+            self.advance();
+            into_abstract(into_declaration(self.class(false, Vec::new())?))
+        } else if self.match_token(Token::Function) {
+            into_declaration(self.function(false, false)?)
+        } else if self.match_token(Token::Class) {
+            into_declaration(self.class(false, Vec::new())?)
+        } else if self.is_async_function() {
+            self.advance();
+            self.advance();
+            into_declaration(self.function(true, false)?)
+        } else {
+            let expression = self.parse_assignment()?;
+            self.consume_semicolon("Expect ';' after default export.")?;
+            expression
+        };
+        Ok(AstNode::ExportDefaultDeclaration {
+            declaration: Box::new(declaration),
+        })
+    }
+
+    /// Parses `{ a, b as c } [from "m"]` after the `{`. Without `from` the
+    /// local names must refer to bindings, so strings are not allowed.
+    fn export_specifiers(&mut self) -> ParseResult<AstNode> {
+        let mut specifiers = Vec::new();
+        let mut string_locals = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            if matches!(self.peek(), Token::String(_)) {
+                string_locals.push(self.current);
+            }
+            let export_kind = if self.is_type_specifier() {
+                self.advance();
+                "type"
+            } else {
+                "value"
+            };
+            let local = self.module_export_name()?;
+            let exported = if self.match_word("as") {
+                self.module_export_name()?
+            } else {
+                local.clone()
+            };
+            specifiers.push(AstNode::ExportSpecifier {
+                local: Box::new(local),
+                exported: Box::new(exported),
+                export_kind: export_kind.to_string(),
+            });
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBrace, "Expect '}' after export specifiers.")?;
+        let (source, attributes) = if self.match_word("from") {
+            let source = self.module_source()?;
+            (Some(Box::new(source)), self.import_attributes()?)
+        } else {
+            if let Some(&index) = string_locals.first() {
+                return Err(self.error_at(
+                    index,
+                    ErrorCode::ExpectedIdentifier,
+                    "identifier",
+                    "A string can only be exported by name when re-exporting with 'from'.",
+                ));
+            }
+            (None, Vec::new())
+        };
+        self.consume_semicolon("Expect ';' after export declaration.")?;
+        Ok(AstNode::ExportNamedDeclaration {
+            declaration: None,
+            specifiers,
+            source,
+            attributes,
+            export_kind: "value".to_string(),
+        })
+    }
+
+    /// Parses a name in an import or export list: an IdentifierName or a
+    /// string literal.
+    fn module_export_name(&mut self) -> ParseResult<AstNode> {
+        if let Token::String(value) = self.peek().clone() {
+            self.advance();
+            return Ok(AstNode::StringLiteral(value));
+        }
+        Ok(AstNode::identifier(
+            self.consume_property_name("Expect import or export name.")?,
+        ))
+    }
+
+    fn module_source(&mut self) -> ParseResult<AstNode> {
+        match self.peek().clone() {
+            Token::String(value) => {
+                self.advance();
+                Ok(AstNode::StringLiteral(value))
+            }
+            _ => Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "module specifier string",
+                "Expect module specifier.",
+            )),
+        }
+    }
+
+    /// Parses optional import attributes, as in `with { type: "json" }`.
+    fn import_attributes(&mut self) -> ParseResult<Vec<AstNode>> {
+        let mut attributes = Vec::new();
+        if !self.match_token(Token::With) {
+            return Ok(attributes);
+        }
+        self.require_version(self.current - 1, 2025, "import attributes");
+        self.consume(Token::LeftBrace, "Expect '{' after 'with'.")?;
+        while !self.check(&Token::RightBrace) {
+            let key = self.module_export_name()?;
+            self.consume(Token::Colon, "Expect ':' after attribute key.")?;
+            let value = match self.peek().clone() {
+                Token::String(value) => {
+                    self.advance();
+                    AstNode::StringLiteral(value)
+                }
+                _ => {
+                    return Err(self.error(
+                        ErrorCode::UnexpectedToken,
+                        "string",
+                        "Expect string value for import attribute.",
+                    ))
+                }
+            };
+            attributes.push(AstNode::ImportAttribute {
+                key: Box::new(key),
+                value: Box::new(value),
+            });
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBrace, "Expect '}' after import attributes.")?;
+        Ok(attributes)
+    }
+
+    /// Parses `import.meta`, which only modules may use, or a dynamic
+    /// `import(source)` with an optional options argument, also as
+    /// `import.defer(source)` or `import.source(source)`.
+    fn parse_import_expression(&mut self) -> ParseResult<AstNode> {
+        let index = self.current;
+        self.advance();
+        let mut phase = None;
+        if self.match_token(Token::Dot) {
+            let property = self.consume_property_name("Expect 'meta' after 'import.'.")?;
+            let feature = match property.as_str() {
+                "defer" => Some(Feature::ImportDefer),
+                "source" => Some(Feature::SourcePhaseImports),
+                _ => None,
+            };
+            if let Some(feature) = feature.filter(|_| self.check(&Token::LeftParen)) {
+                self.require_feature(self.current - 1, feature);
+                phase = Some(property);
+            } else {
+                return self.import_meta(index, property);
+            }
+        }
+
+        self.require_version(index, 2020, "dynamic 'import()'");
+        self.consume(Token::LeftParen, "Expect '(' or '.' after 'import'.")?;
+        self.allowing_in(|parser| {
+            let source = parser.parse_assignment()?;
+            let mut options = None;
+            // Both arguments may be followed by a trailing comma.
+            if parser.match_token(Token::Comma) && !parser.check(&Token::RightParen) {
+                parser.require_version(parser.current, 2025, "import options");
+                options = Some(Box::new(parser.parse_assignment()?));
+                parser.match_token(Token::Comma);
+            }
+            parser.consume(Token::RightParen, "Expect ')' after import arguments.")?;
+            Ok(AstNode::ImportExpression {
+                source: Box::new(source),
+                options,
+                phase,
+            })
+        })
+    }
+
+    /// Finishes `import.meta` after the property name, which has to be
+    /// `meta`. `import` is token `index`.
+    fn import_meta(&mut self, index: usize, property: String) -> ParseResult<AstNode> {
+        if property != "meta" {
+            return Err(self.error_at(
+                self.current - 1,
+                ErrorCode::UnexpectedToken,
+                "'meta'",
+                "Expect 'meta' after 'import.'.",
+            ));
+        }
+        self.require_version(index, 2020, "'import.meta'");
+        if self.options.source_type == SourceType::Script {
+            let error = self.error_at(
+                index,
+                ErrorCode::MisplacedImportExport,
+                "expression",
+                "'import.meta' may only appear in modules.",
+            );
+            self.errors.push(error);
+        }
+        Ok(AstNode::MetaProperty {
+            meta: Box::new(AstNode::identifier("import".to_string())),
+            property: Box::new(AstNode::identifier(property)),
+        })
+    }
+
+    /// Consumes a number literal with a leading zero, reporting it in strict
+    /// mode code.
+    fn legacy_octal(&mut self) {
+        if self.strict {
+            let error = self.error(
+                ErrorCode::LegacyOctal,
+                "number",
+                "Octal literals are not allowed in strict mode.",
+            );
+            self.errors.push(error);
+        }
+        self.advance();
+    }
+
+    /// Parses `super`, which must be followed by a call or member access.
+    fn parse_super(&mut self) -> ParseResult<AstNode> {
+        let index = self.current;
+        self.advance();
+        let (allowed, message) = match self.peek() {
+            Token::LeftParen => (
+                self.super_call,
+                "'super()' is only valid in derived class constructors.",
+            ),
+            Token::Dot | Token::LeftBracket => (
+                self.super_property,
+                "'super' property access is only valid in methods.",
+            ),
+            _ => (
+                false,
+                "'super' must be followed by a call or property access.",
+            ),
+        };
+        if !allowed {
+            let error = self.error_at(index, ErrorCode::InvalidSuper, "expression", message);
+            self.errors.push(error);
+        }
+        Ok(AstNode::Super)
+    }
+
+    /// Saves the parser state before speculatively parsing TypeScript
+    /// syntax.
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            current: self.current,
+            errors: self.errors.len(),
+            cover_initializers: self.cover_initializers.len(),
+            token_edits: self.token_edits.len(),
+            class_scopes: self.class_scopes.len(),
+            private_references: self
+                .class_scopes
+                .last()
+                .map_or(0, |scope| scope.referenced.len()),
+        }
+    }
+
+    /// Returns to `checkpoint`, dropping the errors reported since and
+    /// undoing any changes made to the tokens since.
+    fn rewind(&mut self, checkpoint: Checkpoint) {
+        let edits = self.token_edits.split_off(checkpoint.token_edits);
+        for edit in edits.into_iter().rev() {
+            let range = edit.index..edit.index + edit.inserted;
+            self.tokens.splice(range.clone(), edit.tokens);
+            if !self.states.is_empty() {
+                self.states.splice(range, edit.states);
+            }
+            let errors = edit.error_index..edit.error_index + edit.inserted_errors;
+            self.lexer_errors.splice(errors, edit.errors);
+        }
+        self.current = checkpoint.current;
+        self.errors.truncate(checkpoint.errors);
+        self.cover_initializers
+            .truncate(checkpoint.cover_initializers);
+        self.class_scopes.truncate(checkpoint.class_scopes);
+        if let Some(scope) = self.class_scopes.last_mut() {
+            scope.referenced.truncate(checkpoint.private_references);
+        }
+    }
+
+    /// Runs `parse` speculatively, rewinding and returning `None` if it
+    /// fails.
+    fn try_parse<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> Option<T> {
+        let checkpoint = self.checkpoint();
+        match parse(self) {
+            Ok(result) => Some(result),
+            Err(_) => {
+                self.rewind(checkpoint);
+                None
+            }
+        }
+    }
+
+    /// Parses a TypeScript-only declaration if one starts at the current
+    /// token: an interface, type alias, enum, namespace or module, an
+    /// abstract class, or any declaration after `declare`.
+    fn ts_declaration(&mut self) -> Option<ParseResult<AstNode>> {
+        if !self.options.typescript || self.is_at_end() {
+            return None;
+        }
+        let next = self.peek_next().clone();
+        let same_line = !self.tokens[self.current + 1].newline_before;
+        let is_enum = |token: &Token| matches!(token, Token::Identifier(name) if name == "enum");
+        if self.check(&Token::Const) && is_enum(&next) {
+            self.advance();
+            self.advance();
+            return Some(self.ts_enum(true));
+        }
+        let Token::Identifier(word) = self.peek().clone() else {
+            return None;
+        };
+        let declaration = match word.as_str() {
+            "enum" if matches!(next, Token::Identifier(_)) => {
+                self.advance();
+                self.ts_enum(false)
+            }
+            "interface" if same_line && matches!(next, Token::Identifier(_)) => {
+                self.advance();
+                self.ts_interface()
+            }
+            "type" if same_line && matches!(next, Token::Identifier(_)) => {
+                self.advance();
+                self.ts_type_alias()
+            }
+            "namespace" | "module"
+                if same_line && matches!(next, Token::Identifier(_) | Token::String(_)) =>
+            {
+                self.advance();
+                self.ts_module(&word)
+            }
+            "global" if self.ambient && next == Token::LeftBrace => {
+                self.advance();
+                self.ts_module(&word)
+            }
+            "abstract" if same_line && next == Token::Class => {
+                self.advance();
+                self.advance();
+                self.class(true, Vec::new()).map(into_abstract)
+            }
+            "declare" if same_line && starts_ambient_declaration(&next) => {
+                self.advance();
+                let ambient = std::mem::replace(&mut self.ambient, true);
+                let declaration = self.try_declaration();
+                self.ambient = ambient;
+                declaration.map(into_ambient)
+            }
+            _ => return None,
+        };
+        Some(declaration)
+    }
+
+    /// Parses TypeScript's forms of `export` after the keyword: `export =`,
+    /// `export as namespace`, `export type { ... }`, `export import` and
+    /// exported TypeScript declarations.
+    fn ts_export_declaration(&mut self) -> Option<ParseResult<AstNode>> {
+        if self.match_token(Token::Equal) {
+            return Some(self.ts_export_assignment());
+        }
+        let next = self.peek_next().clone();
+        if self.is_word("as") && matches!(&next, Token::Identifier(name) if name == "namespace") {
+            self.advance();
+            self.advance();
+            return Some(self.ts_namespace_export());
+        }
+        if self.is_word("type") && next == Token::LeftBrace {
+            self.advance();
+            self.advance();
+            return Some(self.export_specifiers().map(into_type_export));
+        }
+        let declaration = if self.check(&Token::Import) && matches!(next, Token::Identifier(_)) {
+            self.advance();
+            self.import_declaration()
+        } else {
+            self.ts_declaration()?
+        };
+        Some(declaration.map(|declaration| {
+            let export_kind = match declaration {
+                AstNode::TSInterfaceDeclaration { .. } | AstNode::TSTypeAliasDeclaration { .. } => {
+                    "type"
+                }
+                _ => "value",
+            };
+            AstNode::ExportNamedDeclaration {
+                declaration: Some(Box::new(declaration)),
+                specifiers: Vec::new(),
+                source: None,
+                attributes: Vec::new(),
+                export_kind: export_kind.to_string(),
+            }
+        }))
+    }
+
+    /// Parses `export = expression` after the `=`.
+    fn ts_export_assignment(&mut self) -> ParseResult<AstNode> {
+        let expression = self.parse_assignment()?;
+        self.consume_semicolon("Expect ';' after export assignment.")?;
+        Ok(AstNode::TSExportAssignment {
+            expression: Box::new(expression),
+        })
+    }
+
+    /// Parses `export as namespace Name` after `namespace`.
+    fn ts_namespace_export(&mut self) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect namespace name.")?;
+        self.consume_semicolon("Expect ';' after namespace export.")?;
+        Ok(AstNode::TSNamespaceExportDeclaration {
+            id: Box::new(AstNode::identifier(id)),
+        })
+    }
+
+    /// Parses an interface declaration after `interface`.
+    fn ts_interface(&mut self) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect interface name.")?;
+        let type_parameters = self.parse_type_parameters()?;
+        let mut extends = Vec::new();
+        if self.match_token(Token::Extends) {
+            loop {
+                let (expression, type_arguments) = self.ts_heritage()?;
+                extends.push(AstNode::TSInterfaceHeritage {
+                    expression: Box::new(expression),
+                    type_arguments,
+                });
+                if !self.match_token(Token::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(Token::LeftBrace, "Expect '{' before interface body.")?;
+        let body = self.ts_type_members("interface body")?;
+        Ok(AstNode::TSInterfaceDeclaration {
+            id: Box::new(AstNode::identifier(id)),
+            type_parameters,
+            extends,
+            body: Box::new(AstNode::TSInterfaceBody { body }),
+            declare: false,
+        })
+    }
+
+    /// Parses a name with member accesses and optional type arguments, as
+    /// in the `extends` list of an interface or the `implements` list of a
+    /// class.
+    fn ts_heritage(&mut self) -> ParseResult<(AstNode, Option<Box<AstNode>>)> {
+        let mut expression = AstNode::identifier(self.consume_identifier("Expect type name.")?);
+        while self.match_token(Token::Dot) {
+            expression = self.parse_member_property(expression, false)?;
+        }
+        let type_arguments = if self.check(&Token::Less) {
+            Some(Box::new(self.ts_type_arguments()?))
+        } else {
+            None
+        };
+        Ok((expression, type_arguments))
+    }
+
+    /// Parses a type alias after `type`.
+    fn ts_type_alias(&mut self) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect type alias name.")?;
+        let type_parameters = self.parse_type_parameters()?;
+        self.consume(Token::Equal, "Expect '=' after type alias name.")?;
+        let type_annotation = self.ts_type()?;
+        self.consume_semicolon("Expect ';' after type alias.")?;
+        Ok(AstNode::TSTypeAliasDeclaration {
+            id: Box::new(AstNode::identifier(id)),
+            type_parameters,
+            type_annotation: Box::new(type_annotation),
+            declare: false,
+        })
+    }
+
+    /// Parses an enum after `enum` or `const enum`.
+    fn ts_enum(&mut self, is_const: bool) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect enum name.")?;
+        self.consume(Token::LeftBrace, "Expect '{' before enum body.")?;
+        let mut members = Vec::new();
+        while !self.check(&Token::RightBrace) {
+            let member = match self.peek().clone() {
+                Token::String(value) => {
+                    self.advance();
+                    AstNode::StringLiteral(value)
+                }
+                _ => AstNode::identifier(self.consume_property_name("Expect enum member name.")?),
+            };
+            let initializer = if self.match_token(Token::Equal) {
+                Some(Box::new(self.allowing_in(Self::parse_assignment)?))
+            } else {
+                None
+            };
+            members.push(AstNode::TSEnumMember {
+                id: Box::new(member),
+                initializer,
+            });
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBrace, "Expect '}' after enum members.")?;
+        Ok(AstNode::TSEnumDeclaration {
+            id: Box::new(AstNode::identifier(id)),
+            members,
+            r#const: is_const,
+            declare: false,
+        })
+    }
+
+    /// Parses a namespace or module after `namespace`, `module` or `global`.
+    /// A dotted name such as `A.B` becomes a `TSQualifiedName`.
+    fn ts_module(&mut self, kind: &str) -> ParseResult<AstNode> {
+        let id = if kind == "global" {
+            AstNode::identifier(kind.to_string())
+        } else if let Token::String(value) = self.peek().clone() {
+            self.advance();
+            AstNode::StringLiteral(value)
+        } else {
+            self.ts_entity_name("Expect namespace name.")?
+        };
+        let body = if self.match_token(Token::LeftBrace) {
+            Some(Box::new(self.ts_module_block()?))
+        } else if matches!(id, AstNode::StringLiteral(_)) {
+            // `declare module "m";` declares a module without its contents.
+            self.consume_semicolon("Expect ';' after module declaration.")?;
+            None
+        } else {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "'{'",
+                "Expect '{' before namespace body.",
+            ));
+        };
+        Ok(AstNode::TSModuleDeclaration {
+            id: Box::new(id),
+            body,
+            kind: kind.to_string(),
+            declare: false,
+        })
+    }
+
+    fn ts_module_block(&mut self) -> ParseResult<AstNode> {
+        let mut body = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            body.push(self.recovering(Self::ts_module_item));
+        }
+        self.consume(Token::RightBrace, "Expect '}' after namespace body.")?;
+        Ok(AstNode::TSModuleBlock { body })
+    }
+
+    /// Parses a statement in a namespace body, where `import` and `export`
+    /// declarations may also appear.
+    fn ts_module_item(&mut self) -> ParseResult<AstNode> {
+        if self.is_import_declaration() {
+            self.advance();
+            self.import_declaration()
+        } else if self.match_token(Token::Export) {
+            self.export_declaration(Vec::new())
+        } else {
+            self.try_declaration()
+        }
+    }
+
+    /// Parses `x = require("m")` or `x = A.B` after `import`.
+    fn ts_import_equals(&mut self, import_kind: &str) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect import name.")?;
+        self.advance();
+        let module_reference = if self.is_word("require") && self.peek_next() == &Token::LeftParen {
+            self.advance();
+            self.advance();
+            let expression = self.module_source()?;
+            self.consume(Token::RightParen, "Expect ')' after module specifier.")?;
+            AstNode::TSExternalModuleReference {
+                expression: Box::new(expression),
+            }
+        } else {
+            self.ts_entity_name("Expect module reference.")?
+        };
+        self.consume_semicolon("Expect ';' after import declaration.")?;
+        Ok(AstNode::TSImportEqualsDeclaration {
+            id: Box::new(AstNode::identifier(id)),
+            module_reference: Box::new(module_reference),
+            import_kind: import_kind.to_string(),
+        })
+    }
+
+    /// Whether `type`, or in Flow `typeof`, after `import` makes the import
+    /// type-only, as in `import type { A } from "m"`, rather than naming a
+    /// default import.
+    fn is_type_import(&self) -> bool {
+        if !self.type_syntax() || !self.is_import_kind() {
+            return false;
+        }
+        match self.peek_next() {
+            Token::LeftBrace | Token::Star => true,
+            // `import type from "m"` imports a default export named `type`.
+            Token::Identifier(name) if name == "from" => {
+                matches!(self.token_at(self.current + 2), Token::Identifier(name) if name == "from")
+            }
+            Token::Identifier(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether `type`, or in Flow `typeof`, starts a type-only specifier such
+    /// as `{ type A }` in an import or export list.
+    fn is_type_specifier(&self) -> bool {
+        self.type_syntax()
+            && self.is_import_kind()
+            && !matches!(self.peek_next(), Token::Comma | Token::RightBrace)
+            && !matches!(self.peek_next(), Token::Identifier(name) if name == "as")
+    }
+
+    fn is_import_kind(&self) -> bool {
+        self.is_word("type") || (self.options.flow && self.check(&Token::Typeof))
+    }
+
+    /// Parses a possibly dotted name such as `A.B.C`, which becomes nested
+    /// `TSQualifiedName`s.
+    fn ts_entity_name(&mut self, message: &str) -> ParseResult<AstNode> {
+        let mut name = AstNode::identifier(self.consume_identifier(message)?);
+        while self.match_token(Token::Dot) {
+            let right = self.consume_property_name("Expect name after '.'.")?;
+            name = AstNode::TSQualifiedName {
+                left: Box::new(name),
+                right: Box::new(AstNode::identifier(right)),
+            };
+        }
+        Ok(name)
+    }
+
+    /// Parses a TypeScript parameter: a binding with an optional `?`, type
+    /// annotation and default, `this` to type the receiver, or in a
+    /// constructor a parameter property such as `private readonly x: T`.
+    fn typed_param(&mut self, parameter_properties: bool) -> ParseResult<AstNode> {
+        let start = self.current;
+        let mut accessibility = None;
+        let mut readonly = false;
+        let mut r#override = false;
+        while let Token::Identifier(word) = self.peek().clone() {
+            let is_modifier = self.options.typescript
+                && matches!(
+                    word.as_str(),
+                    "public" | "private" | "protected" | "readonly" | "override"
+                )
+                && matches!(
+                    self.peek_next(),
+                    Token::Identifier(_) | Token::Await | Token::LeftBracket | Token::LeftBrace
+                );
+            if !is_modifier {
+                break;
+            }
+            self.advance();
+            match word.as_str() {
+                "readonly" => readonly = true,
+                "override" => r#override = true,
+                _ => accessibility = Some(word),
+            }
+        }
+        let is_property = accessibility.is_some() || readonly || r#override;
+        if is_property && !parameter_properties {
+            let error = self.error_at(
+                start,
+                ErrorCode::UnexpectedToken,
+                "parameter",
+                "Parameter properties are only allowed in constructors.",
+            );
+            self.errors.push(error);
+        }
+        let target = if self.match_token(Token::This) {
+            AstNode::identifier("this".to_string())
+        } else {
+            self.parse_binding_target("Expect parameter name.")?
+        };
+        let optional = self.match_token(Token::Question);
+        let mut parameter = self.annotate(target, optional)?;
+        if self.match_token(Token::Equal) {
+            parameter = AstNode::AssignmentPattern {
+                left: Box::new(parameter),
+                right: Box::new(self.parse_assignment()?),
+            };
+        }
+        if !is_property {
+            return Ok(parameter);
+        }
+        Ok(AstNode::TSParameterProperty {
+            parameter: Box::new(parameter),
+            accessibility,
+            readonly,
+            r#override,
+        })
+    }
+
+    /// Whether type annotations are parsed, as they are in TypeScript and
+    /// Flow.
+    fn type_syntax(&self) -> bool {
+        self.options.typescript || self.options.flow
+    }
+
+    /// Attaches the type annotation that may follow a binding in TypeScript
+    /// or Flow. `is_optional` is set when a `?` came before it.
+    fn annotate(&mut self, mut pattern: AstNode, is_optional: bool) -> ParseResult<AstNode> {
+        let annotation = self.parse_type_annotation()?;
+        if annotation.is_none() && !is_optional {
+            return Ok(pattern);
+        }
+        match &mut pattern {
+            AstNode::Identifier {
+                type_annotation,
+                optional,
+                ..
+            }
+            | AstNode::ObjectPattern {
+                type_annotation,
+                optional,
+                ..
+            }
+            | AstNode::ArrayPattern {
+                type_annotation,
+                optional,
+                ..
+            } => {
+                *type_annotation = annotation;
+                *optional = is_optional;
+            }
+            AstNode::RestElement {
+                type_annotation, ..
+            } => *type_annotation = annotation,
+            _ => {}
+        }
+        Ok(pattern)
+    }
+
+    /// Parses a `: Type` annotation in TypeScript or Flow, if present.
+    fn parse_type_annotation(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if !self.type_syntax() || !self.match_token(Token::Colon) {
+            return Ok(None);
+        }
+        if self.options.flow {
+            return Ok(Some(self.flow_type_annotation()?));
+        }
+        Ok(Some(type_annotation(self.ts_type()?)))
+    }
+
+    /// Parses the `: Type` return type of a function in TypeScript or Flow,
+    /// if present.
+    fn parse_return_type(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if !self.type_syntax() || !self.match_token(Token::Colon) {
+            return Ok(None);
+        }
+        if self.options.flow {
+            return Ok(Some(self.flow_type_annotation()?));
+        }
+        Ok(Some(type_annotation(self.ts_type_or_predicate()?)))
+    }
+
+    /// Parses a return type, which may also be a type predicate: `x is T`,
+    /// `asserts x` or `asserts x is T`.
+    fn ts_type_or_predicate(&mut self) -> ParseResult<AstNode> {
+        let names_parameter = |token: &Token| matches!(token, Token::Identifier(_) | Token::This);
+        let asserts = self.is_word("asserts")
+            && names_parameter(self.peek_next())
+            && !self.tokens[self.current + 1].newline_before;
+        if asserts {
+            self.advance();
+        }
+        let is_predicate = names_parameter(self.peek())
+            && matches!(self.peek_next(), Token::Identifier(name) if name == "is")
+            && !self.tokens[self.current + 1].newline_before;
+        if !asserts && !is_predicate {
+            return self.ts_type();
+        }
+        let parameter_name = if self.match_token(Token::This) {
+            AstNode::TSThisType
+        } else {
+            AstNode::identifier(self.consume_identifier("Expect parameter name.")?)
+        };
+        let type_annotation = if self.match_word("is") {
+            Some(type_annotation(self.ts_type()?))
+        } else {
+            None
+        };
+        Ok(AstNode::TSTypePredicate {
+            parameter_name: Box::new(parameter_name),
+            type_annotation,
+            asserts,
+        })
+    }
+
+    /// Whether a TypeScript arrow function with type parameters or a return
+    /// type starts at the current token, as in `<T>(x: T): T => x`. Unlike
+    /// `is_arrow_function`, this parses ahead and rewinds.
+    fn is_typed_arrow_function(&mut self) -> bool {
+        if !self.type_syntax() {
+            return false;
+        }
+        let mut start = self.current;
+        if self.is_word("async") && !self.tokens[start + 1].newline_before {
+            start += 1;
+        }
+        let worth_trying = match self.token_at(start) {
+            Token::Less => true,
+            // Arrow functions without a return type are left to
+            // `is_arrow_function`.
+            Token::LeftParen => self.token_at(self.matching_bracket(start) + 1) == &Token::Colon,
+            _ => false,
+        };
+        if !worth_trying {
+            return false;
+        }
+        let checkpoint = self.checkpoint();
+        self.current = start;
+        let has_params = self.parse_type_parameters().is_ok()
+            && self.match_token(Token::LeftParen)
+            && self.parse_params().is_ok();
+        let return_type = has_params.then(|| self.parse_return_type());
+        let has_return_type = matches!(return_type, Some(Ok(Some(_))));
+        let mut is_arrow = matches!(return_type, Some(Ok(_))) && self.check(&Token::Arrow);
+        // In `a ? (b) : c => d` the `:` belongs to the conditional, unless
+        // the whole arrow function is followed by another `:`.
+        if is_arrow && has_return_type && self.consequent_start == Some(checkpoint.current) {
+            self.current = checkpoint.current;
+            is_arrow = self.parse_arrow_function().is_ok() && self.check(&Token::Colon);
+        }
+        self.rewind(checkpoint);
+        is_arrow
+    }
+
+    /// Speculatively parses the type arguments of a call such as `f<T>(x)`,
+    /// which are only type arguments when a `(` follows them. TypeScript
+    /// also has instantiation expressions such as `f<T>;`, where the type
+    /// arguments are followed by a line break, a binary operator or a token
+    /// that cannot start an expression, so `a < b > c` stays a comparison.
+    fn call_type_arguments(&mut self) -> Option<AstNode> {
+        if !self.type_syntax() || !self.check(&Token::Less) {
+            return None;
+        }
+        self.try_parse(|parser| {
+            let type_arguments = parser.type_arguments()?;
+            let next = parser.peek();
+            let follows = match next {
+                Token::LeftParen => true,
+                _ if !parser.options.typescript => false,
+                Token::Less | Token::Greater | Token::Plus | Token::Minus => false,
+                _ => {
+                    parser.tokens[parser.current].newline_before
+                        || binary_precedence(next).is_some()
+                        || !starts_expression(next)
+                }
+            };
+            if !follows {
+                return Err(parser.error(
+                    ErrorCode::UnexpectedToken,
+                    "'('",
+                    "Expect '(' after type arguments.",
+                ));
+            }
+            Ok(type_arguments)
+        })
+    }
+
+    /// Parses `as T`, `as const` or `satisfies T` after `expression`.
+    fn ts_as_expression(&mut self, expression: AstNode) -> ParseResult<AstNode> {
+        let operator = self.advance().to_string();
+        let type_annotation = if operator == "as" && self.match_token(Token::Const) {
+            const_type_reference()
+        } else {
+            self.ts_type()?
+        };
+        let expression = Box::new(expression);
+        let type_annotation = Box::new(type_annotation);
+        Ok(if operator == "as" {
+            AstNode::TSAsExpression {
+                expression,
+                type_annotation,
+            }
+        } else {
+            AstNode::TSSatisfiesExpression {
+                expression,
+                type_annotation,
+            }
+        })
+    }
+
+    /// Parses type parameters such as `<T>` in TypeScript or Flow, if
+    /// present.
+    fn parse_type_parameters(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if self.options.flow {
+            self.flow_type_parameters()
+        } else {
+            self.ts_type_parameters()
+        }
+    }
+
+    /// Type arguments such as `<A, B>` in TypeScript or Flow.
+    fn type_arguments(&mut self) -> ParseResult<AstNode> {
+        if self.options.flow {
+            self.flow_type_arguments()
+        } else {
+            self.ts_type_arguments()
+        }
+    }
+
+    /// Parses TypeScript type parameters such as `<const T extends U = V>`,
+    /// if present.
+    fn ts_type_parameters(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if !self.options.typescript || !self.match_token(Token::Less) {
+            return Ok(None);
+        }
+        let mut params = Vec::new();
+        loop {
+            let r#const =
+                self.check(&Token::Const) && matches!(self.peek_next(), Token::Identifier(_));
+            if r#const {
+                self.advance();
+            }
+            let r#in = self.check(&Token::In) && matches!(self.peek_next(), Token::Identifier(_));
+            if r#in {
+                self.advance();
+            }
+            let out = self.is_word("out") && matches!(self.peek_next(), Token::Identifier(_));
+            if out {
+                self.advance();
+            }
+            let name = self.consume_identifier("Expect type parameter name.")?;
+            let constraint = if self.match_token(Token::Extends) {
+                Some(Box::new(self.ts_type()?))
+            } else {
+                None
+            };
+            let default = if self.match_token(Token::Equal) {
+                Some(Box::new(self.ts_type()?))
+            } else {
+                None
+            };
+            params.push(AstNode::TSTypeParameter {
+                name: Box::new(AstNode::identifier(name)),
+                constraint,
+                default,
+                r#in,
+                out,
+                r#const,
+            });
+            // A trailing comma is allowed, as in `<T,>`.
+            if !self.match_token(Token::Comma) || self.check(&Token::Greater) {
+                break;
+            }
+        }
+        self.consume_type_closer("Expect '>' after type parameters.")?;
+        Ok(Some(Box::new(AstNode::TSTypeParameterDeclaration {
+            params,
+        })))
+    }
+
+    /// Parses TypeScript type arguments such as `<A, B>`.
+    fn ts_type_arguments(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::Less, "Expect '<' before type arguments.")?;
+        let mut params = Vec::new();
+        loop {
+            params.push(self.ts_type()?);
+            if !self.match_token(Token::Comma) || self.check(&Token::Greater) {
+                break;
+            }
+        }
+        self.consume_type_closer("Expect '>' after type arguments.")?;
+        Ok(AstNode::TSTypeParameterInstantiation { params })
+    }
+
+    /// Parses the type arguments of a type reference, which must start on
+    /// the line of its name.
+    fn ts_reference_type_arguments(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if !self.check(&Token::Less) || self.tokens[self.current].newline_before {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(self.ts_type_arguments()?)))
+    }
+
+    /// Consumes the `>` that closes type parameters or arguments. A token
+    /// that starts with `>`, such as the `>>` in `A<B<C>>`, is split in two.
+    fn consume_type_closer(&mut self, message: &str) -> ParseResult<()> {
+        let rest = match self.peek() {
+            Token::Greater => {
+                self.advance();
+                return Ok(());
+            }
+            Token::GreaterGreater => Token::Greater,
+            Token::GreaterGreaterGreater => Token::GreaterGreater,
+            Token::GreaterEqual => Token::Equal,
+            Token::GreaterGreaterEqual => Token::GreaterEqual,
+            Token::GreaterGreaterGreaterEqual => Token::GreaterGreaterEqual,
+            _ => return Err(self.error(ErrorCode::UnexpectedToken, "'>'", message)),
+        };
+        let original = &self.tokens[self.current];
+        let start = original.span.start;
+        let halves = vec![
+            SpannedToken {
+                token: Token::Greater,
+                span: Span::new(start, start + 1),
+                newline_before: original.newline_before,
+            },
+            SpannedToken {
+                token: rest,
+                span: Span::new(start + 1, original.span.end),
+                newline_before: false,
+            },
+        ];
+        // `>` leaves the lexer in the same state as the whole token.
+        let states = match self.states.get(self.current) {
+            Some(state) => vec![state.clone(), state.clone()],
+            None => Vec::new(),
+        };
+        self.replace_tokens(
+            self.current..self.current + 1,
+            halves,
+            states,
+            0..0,
+            Vec::new(),
+        );
+        self.advance();
+        Ok(())
+    }
+
+    /// Replaces the tokens in `range` and their states, and the lexer
+    /// errors in `error_range`, recording the change for `rewind`.
+    fn replace_tokens(
+        &mut self,
+        range: Range<usize>,
+        tokens: Vec<SpannedToken>,
+        states: Vec<LexerState>,
+        error_range: Range<usize>,
+        errors: Vec<ParseError>,
+    ) {
+        let edit = TokenEdit {
+            index: range.start,
+            inserted: tokens.len(),
+            tokens: self.tokens.splice(range.clone(), tokens).collect(),
+            states: if self.states.is_empty() {
+                Vec::new()
+            } else {
+                self.states.splice(range, states).collect()
+            },
+            error_index: error_range.start,
+            inserted_errors: errors.len(),
+            errors: self.lexer_errors.splice(error_range, errors).collect(),
+        };
+        self.token_edits.push(edit);
+    }
+
+    /// Parses a TypeScript type.
+    fn ts_type(&mut self) -> ParseResult<AstNode> {
+        let conditional_types = std::mem::replace(&mut self.conditional_types, true);
+        let result = self.ts_conditional_type();
+        self.conditional_types = conditional_types;
+        result
+    }
+
+    /// Parses a function or constructor type, or a union type that may be
+    /// the checked type of a conditional type `T extends U ? X : Y`.
+    fn ts_conditional_type(&mut self) -> ParseResult<AstNode> {
+        if let Some(function_type) = self.ts_function_type()? {
+            return Ok(function_type);
+        }
+        let check_type = self.ts_union_type()?;
+        if !self.conditional_types
+            || !self.check(&Token::Extends)
+            || self.tokens[self.current].newline_before
+        {
+            return Ok(check_type);
+        }
+        self.advance();
+        self.conditional_types = false;
+        let extends_type = self.ts_conditional_type();
+        self.conditional_types = true;
+        let extends_type = extends_type?;
+        self.consume(
+            Token::Question,
+            "Expect '?' after conditional type constraint.",
+        )?;
+        let true_type = self.ts_conditional_type()?;
+        self.consume(Token::Colon, "Expect ':' after conditional type branch.")?;
+        let false_type = self.ts_conditional_type()?;
+        Ok(AstNode::TSConditionalType {
+            check_type: Box::new(check_type),
+            extends_type: Box::new(extends_type),
+            true_type: Box::new(true_type),
+            false_type: Box::new(false_type),
+        })
+    }
+
+    /// Parses a function type such as `<T>(x: T) => U` or a constructor
+    /// type such as `abstract new () => T`, if one starts here.
+    fn ts_function_type(&mut self) -> ParseResult<Option<AstNode>> {
+        let is_abstract = self.is_word("abstract") && self.peek_next() == &Token::New;
+        if is_abstract {
+            self.advance();
+        }
+        let is_constructor = self.match_token(Token::New);
+        let starts_function = self.check(&Token::Less)
+            || (self.check(&Token::LeftParen)
+                && self.token_at(self.matching_bracket(self.current) + 1) == &Token::Arrow);
+        if !is_constructor && !starts_function {
+            return Ok(None);
+        }
+        let type_parameters = self.parse_type_parameters()?;
+        self.consume(
+            Token::LeftParen,
+            "Expect '(' before function type parameters.",
+        )?;
+        let params = self.parse_params()?;
+        self.consume(Token::Arrow, "Expect '=>' after function type parameters.")?;
+        let return_type = type_annotation(self.ts_type_or_predicate()?);
+        Ok(Some(if is_constructor {
+            AstNode::TSConstructorType {
+                type_parameters,
+                params,
+                return_type,
+                r#abstract: is_abstract,
+            }
+        } else {
+            AstNode::TSFunctionType {
+                type_parameters,
+                params,
+                return_type,
+            }
+        }))
+    }
+
+    fn ts_union_type(&mut self) -> ParseResult<AstNode> {
+        self.type_list(Token::Pipe, Self::ts_intersection_type, |types| {
+            AstNode::TSUnionType { types }
+        })
+    }
+
+    fn ts_intersection_type(&mut self) -> ParseResult<AstNode> {
+        self.type_list(Token::Ampersand, Self::ts_type_operator, |types| {
+            AstNode::TSIntersectionType { types }
+        })
+    }
+
+    /// Parses `part`s separated by `separator`, which may also come before
+    /// the first one, as in `| A | B`. A single part is returned as is.
+    fn type_list(
+        &mut self,
+        separator: Token,
+        part: fn(&mut Self) -> ParseResult<AstNode>,
+        build: fn(Vec<AstNode>) -> AstNode,
+    ) -> ParseResult<AstNode> {
+        self.match_token(separator.clone());
+        let mut types = vec![part(self)?];
+        while self.match_token(separator.clone()) {
+            types.push(part(self)?);
+        }
+        Ok(match types.len() {
+            1 => types.remove(0),
+            _ => build(types),
+        })
+    }
+
+    /// Parses `keyof T`, `unique symbol`, `readonly T[]`, `infer U`, or a
+    /// type with any postfix `[]` and `[K]`.
+    fn ts_type_operator(&mut self) -> ParseResult<AstNode> {
+        let is_operator =
+            (self.is_word("keyof") || self.is_word("unique") || self.is_word("readonly"))
+                && starts_type(self.peek_next());
+        if is_operator {
+            let operator = self.advance().to_string();
+            let type_annotation = self.ts_type_operator()?;
+            return Ok(AstNode::TSTypeOperator {
+                operator,
+                type_annotation: Box::new(type_annotation),
+            });
+        }
+        if self.is_word("infer") && matches!(self.peek_next(), Token::Identifier(_)) {
+            self.advance();
+            let name = self.consume_identifier("Expect type parameter name.")?;
+            let constraint = if self.check(&Token::Extends) {
+                self.try_parse(Self::ts_infer_constraint).map(Box::new)
+            } else {
+                None
+            };
+            return Ok(AstNode::TSInferType {
+                type_parameter: Box::new(AstNode::TSTypeParameter {
+                    name: Box::new(AstNode::identifier(name)),
+                    constraint,
+                    default: None,
+                    r#in: false,
+                    out: false,
+                    r#const: false,
+                }),
+            });
+        }
+        self.ts_postfix_type()
+    }
+
+    /// Parses `extends U` after `infer T`. Where a conditional type may
+    /// start, `infer T extends U ? X : Y` is one, so the constraint is only
+    /// taken if no `?` follows it.
+    fn ts_infer_constraint(&mut self) -> ParseResult<AstNode> {
+        self.advance();
+        let conditional_types = std::mem::replace(&mut self.conditional_types, false);
+        let constraint = self.ts_conditional_type();
+        self.conditional_types = conditional_types;
+        let constraint = constraint?;
+        if conditional_types && self.check(&Token::Question) {
+            return Err(self.error(
+                ErrorCode::UnexpectedToken,
+                "type",
+                "Expect constraint of 'infer' type.",
+            ));
+        }
+        Ok(constraint)
+    }
+
+    /// Parses a type followed by any number of `[]` for array types and
+    /// `[K]` for indexed access types.
+    fn ts_postfix_type(&mut self) -> ParseResult<AstNode> {
+        let mut result = self.ts_primary_type()?;
+        // A `[` on a new line starts the next member of a type literal.
+        while self.check(&Token::LeftBracket) && !self.tokens[self.current].newline_before {
+            self.advance();
+            result = if self.match_token(Token::RightBracket) {
+                AstNode::TSArrayType {
+                    element_type: Box::new(result),
+                }
+            } else {
+                let index_type = self.ts_type()?;
+                self.consume(Token::RightBracket, "Expect ']' after indexed access type.")?;
+                AstNode::TSIndexedAccessType {
+                    object_type: Box::new(result),
+                    index_type: Box::new(index_type),
+                }
+            };
+        }
+        Ok(result)
+    }
+
+    fn ts_primary_type(&mut self) -> ParseResult<AstNode> {
+        let literal = match (self.peek().clone(), self.peek_next().clone()) {
+            (Token::Minus, Token::Number(value)) => {
+                self.advance();
+                Some(AstNode::UnaryExpression {
+                    operator: "-".to_string(),
+                    argument: Box::new(AstNode::NumberLiteral(value)),
+                })
+            }
+            (Token::String(value), _) => Some(AstNode::StringLiteral(value)),
+            (Token::Number(value), _) => Some(AstNode::NumberLiteral(value)),
+            (Token::True, _) => Some(AstNode::BooleanLiteral(true)),
+            (Token::False, _) => Some(AstNode::BooleanLiteral(false)),
+            _ => None,
+        };
+        if let Some(literal) = literal {
+            self.advance();
+            return Ok(AstNode::TSLiteralType {
+                literal: Box::new(literal),
+            });
+        }
+        match self.peek().clone() {
+            Token::LeftParen => {
+                self.advance();
+                let inner = self.ts_type()?;
+                self.consume(Token::RightParen, "Expect ')' after type.")?;
+                Ok(inner)
+            }
+            Token::LeftBracket => {
+                self.advance();
+                self.ts_tuple_type()
+            }
+            Token::Template(raw) => {
+                self.advance();
+                Ok(AstNode::TSTemplateLiteralType {
+                    quasis: vec![AstNode::TemplateElement { raw, tail: true }],
+                    types: Vec::new(),
+                })
+            }
+            Token::TemplateHead(raw) => {
+                self.advance();
+                self.ts_template_literal_type(raw)
+            }
+            Token::LeftBrace => {
+                self.advance();
+                if self.ts_starts_mapped_type() {
+                    self.ts_mapped_type()
+                } else {
+                    Ok(AstNode::TSTypeLiteral {
+                        members: self.ts_type_members("type literal")?,
+                    })
+                }
+            }
+            Token::Void => {
+                self.advance();
+                Ok(AstNode::TSVoidKeyword)
+            }
+            Token::Null => {
+                self.advance();
+                Ok(AstNode::TSNullKeyword)
+            }
+            Token::This => {
+                self.advance();
+                Ok(AstNode::TSThisType)
+            }
+            Token::Typeof => {
+                self.advance();
+                let expr_name = if self.check(&Token::Import) {
+                    self.ts_import_type()?
+                } else {
+                    self.ts_entity_name("Expect name after 'typeof'.")?
+                };
+                Ok(AstNode::TSTypeQuery {
+                    expr_name: Box::new(expr_name),
+                    type_arguments: self.ts_reference_type_arguments()?,
+                })
+            }
+            Token::Import => self.ts_import_type(),
+            Token::Identifier(name) => {
+                if self.peek_next() != &Token::Dot {
+                    if let Some(keyword) = keyword_type(&name) {
+                        self.advance();
+                        return Ok(keyword);
+                    }
+                }
+                let type_name = self.ts_entity_name("Expect type name.")?;
+                Ok(AstNode::TSTypeReference {
+                    type_name: Box::new(type_name),
+                    type_arguments: self.ts_reference_type_arguments()?,
+                })
+            }
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "type", "Expect type.")),
+        }
+    }
+
+    /// Parses `import("m")` with an optional `.Name` qualifier and type
+    /// arguments.
+    fn ts_import_type(&mut self) -> ParseResult<AstNode> {
+        self.advance();
+        self.consume(Token::LeftParen, "Expect '(' after 'import'.")?;
+        let argument = AstNode::TSLiteralType {
+            literal: Box::new(self.module_source()?),
+        };
+        self.consume(Token::RightParen, "Expect ')' after module specifier.")?;
+        let mut qualifier = None;
+        while self.match_token(Token::Dot) {
+            let right = AstNode::identifier(self.consume_property_name("Expect name after '.'.")?);
+            qualifier = Some(match qualifier {
+                None => right,
+                Some(left) => AstNode::TSQualifiedName {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+            });
+        }
+        Ok(AstNode::TSImportType {
+            argument: Box::new(argument),
+            qualifier: qualifier.map(Box::new),
+            type_arguments: self.ts_reference_type_arguments()?,
+        })
+    }
+
+    /// Parses the elements of a tuple type after its `[`: types, optional
+    /// `T?`, rest `...T` and named `name: T` members.
+    /// Parses the substitutions and text of a template literal type after
+    /// its head, whose text is `head`.
+    fn ts_template_literal_type(&mut self, head: String) -> ParseResult<AstNode> {
+        let mut quasis = vec![AstNode::TemplateElement {
+            raw: head,
+            tail: false,
+        }];
+        let mut types = Vec::new();
+        loop {
+            types.push(self.ts_type()?);
+            match self.peek().clone() {
+                Token::TemplateMiddle(raw) => {
+                    self.advance();
+                    quasis.push(AstNode::TemplateElement { raw, tail: false });
+                }
+                Token::TemplateTail(raw) => {
+                    self.advance();
+                    quasis.push(AstNode::TemplateElement { raw, tail: true });
+                    return Ok(AstNode::TSTemplateLiteralType { quasis, types });
+                }
+                _ => {
+                    return Err(self.error(
+                        ErrorCode::UnexpectedToken,
+                        "'}'",
+                        "Expect '}' after template literal type substitution.",
+                    ))
+                }
+            }
+        }
+    }
+
+    fn ts_tuple_type(&mut self) -> ParseResult<AstNode> {
+        let mut element_types = Vec::new();
+        while !self.check(&Token::RightBracket) {
+            let rest = self.match_token(Token::Ellipsis);
+            let named = matches!(self.peek(), Token::Identifier(_))
+                && (self.peek_next() == &Token::Colon
+                    || (self.peek_next() == &Token::Question
+                        && self.token_at(self.current + 2) == &Token::Colon));
+            let mut element = if named {
+                let label = self.consume_identifier("Expect tuple member name.")?;
+                let optional = self.match_token(Token::Question);
+                self.advance();
+                AstNode::TSNamedTupleMember {
+                    label: Box::new(AstNode::identifier(label)),
+                    element_type: Box::new(self.ts_type()?),
+                    optional,
+                }
+            } else {
+                let element_type = self.ts_type()?;
+                if self.match_token(Token::Question) {
+                    AstNode::TSOptionalType {
+                        type_annotation: Box::new(element_type),
+                    }
+                } else {
+                    element_type
+                }
+            };
+            if rest {
+                element = AstNode::TSRestType {
+                    type_annotation: Box::new(element),
+                };
+            }
+            element_types.push(element);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(Token::RightBracket, "Expect ']' after tuple element types.")?;
+        Ok(AstNode::TSTupleType { element_types })
+    }
+
+    /// Whether the type literal whose `{` was just consumed is a mapped type
+    /// such as `{ readonly [K in T]: U }`.
+    fn ts_starts_mapped_type(&self) -> bool {
+        let mut index = self.current;
+        if matches!(self.token_at(index), Token::Plus | Token::Minus) {
+            index += 1;
+        }
+        if matches!(self.token_at(index), Token::Identifier(name) if name == "readonly") {
+            index += 1;
+        }
+        self.token_at(index) == &Token::LeftBracket
+            && matches!(self.token_at(index + 1), Token::Identifier(_))
+            && self.token_at(index + 2) == &Token::In
+    }
+
+    /// Parses the rest of a mapped type after its `{`.
+    fn ts_mapped_type(&mut self) -> ParseResult<AstNode> {
+        let readonly = self.ts_mapped_modifier("readonly");
+        self.consume(Token::LeftBracket, "Expect '[' in mapped type.")?;
+        let key = self.consume_identifier("Expect mapped type parameter name.")?;
+        self.consume(Token::In, "Expect 'in' after mapped type parameter.")?;
+        let constraint = self.ts_type()?;
+        let name_type = if self.match_word("as") {
+            Some(Box::new(self.ts_type()?))
+        } else {
+            None
+        };
+        self.consume(
+            Token::RightBracket,
+            "Expect ']' after mapped type parameter.",
+        )?;
+        let optional = self.ts_mapped_modifier("?");
+        let type_annotation = if self.match_token(Token::Colon) {
+            Some(Box::new(self.ts_type()?))
+        } else {
+            None
+        };
+        if !self.match_token(Token::Semicolon) {
+            self.match_token(Token::Comma);
+        }
+        self.consume(Token::RightBrace, "Expect '}' after mapped type.")?;
+        Ok(AstNode::TSMappedType {
+            key: Box::new(AstNode::identifier(key)),
+            constraint: Box::new(constraint),
+            name_type,
+            type_annotation,
+            readonly,
+            optional,
+        })
+    }
+
+    /// Parses the `readonly` or `?` modifier of a mapped type, which may be
+    /// prefixed with `+` or `-`, as "+", "-" or "true".
+    fn ts_mapped_modifier(&mut self, modifier: &str) -> Option<String> {
+        let is_modifier = |token: &Token| match token {
+            Token::Question => modifier == "?",
+            Token::Identifier(name) => name == modifier,
+            _ => false,
+        };
+        if matches!(self.peek(), Token::Plus | Token::Minus) && is_modifier(self.peek_next()) {
+            let sign = self.advance().to_string();
+            self.advance();
+            return Some(sign);
+        }
+        if is_modifier(self.peek()) {
+            self.advance();
+            return Some("true".to_string());
+        }
+        None
+    }
+
+    /// Parses the members of a type literal or interface body after its
+    /// `{`, up to and including the `}`. Members are separated by `;`, `,`
+    /// or a line break.
+    fn ts_type_members(&mut self, description: &str) -> ParseResult<Vec<AstNode>> {
+        let mut members = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            members.push(self.ts_type_member()?);
+            if !self.match_token(Token::Semicolon)
+                && !self.match_token(Token::Comma)
+                && !self.can_insert_semicolon()
+            {
+                return Err(self.error(
+                    ErrorCode::UnexpectedToken,
+                    "';'",
+                    &format!("Expect ';' after {} member.", description),
+                ));
+            }
+        }
+        self.consume(
+            Token::RightBrace,
+            &format!("Expect '}}' after {}.", description),
+        )?;
+        Ok(members)
+    }
+
+    /// Parses a property, method, call, construct or index signature.
+    fn ts_type_member(&mut self) -> ParseResult<AstNode> {
+        if self.check(&Token::LeftParen) || self.check(&Token::Less) {
+            let (type_parameters, params, return_type) = self.ts_signature()?;
+            return Ok(AstNode::TSCallSignatureDeclaration {
+                type_parameters,
+                params,
+                return_type,
+            });
+        }
+        if self.check(&Token::New) && matches!(self.peek_next(), Token::LeftParen | Token::Less) {
+            self.advance();
+            let (type_parameters, params, return_type) = self.ts_signature()?;
+            return Ok(AstNode::TSConstructSignatureDeclaration {
+                type_parameters,
+                params,
+                return_type,
+            });
+        }
+        let readonly = self.is_word("readonly") && is_property_key_start(self.peek_next());
+        if readonly {
+            self.advance();
+        }
+        if self.ts_starts_index_signature() {
+            return self.ts_index_signature(readonly, false);
+        }
+        let kind = if (self.is_word("get") || self.is_word("set"))
+            && is_property_key_start(self.peek_next())
+        {
+            self.advance().to_string()
+        } else {
+            "method".to_string()
+        };
+        let (key, computed) = self.parse_property_key()?;
+        let optional = self.match_token(Token::Question);
+        if kind != "method" || self.check(&Token::LeftParen) || self.check(&Token::Less) {
+            let (type_parameters, params, return_type) = self.ts_signature()?;
+            return Ok(AstNode::TSMethodSignature {
+                key: Box::new(key),
+                computed,
+                optional,
+                kind,
+                type_parameters,
+                params,
+                return_type,
+            });
+        }
+        Ok(AstNode::TSPropertySignature {
+            key: Box::new(key),
+            computed,
+            optional,
+            readonly,
+            type_annotation: self.parse_type_annotation()?,
+        })
+    }
+
+    /// Parses the type parameters, parameters and return type of a
+    /// signature, as in `<T>(x: T): U`.
+    fn ts_signature(&mut self) -> ParseResult<Signature> {
+        let type_parameters = self.parse_type_parameters()?;
+        self.consume(Token::LeftParen, "Expect '(' before signature parameters.")?;
+        let params = self.parse_params()?;
+        let return_type = self.parse_return_type()?;
+        Ok((type_parameters, params, return_type))
+    }
+
+    fn ts_starts_index_signature(&self) -> bool {
+        self.check(&Token::LeftBracket)
+            && matches!(self.peek_next(), Token::Identifier(_))
+            && self.token_at(self.current + 2) == &Token::Colon
+    }
+
+    /// Parses an index signature such as `[key: string]: T`.
+    fn ts_index_signature(&mut self, readonly: bool, is_static: bool) -> ParseResult<AstNode> {
+        self.advance();
+        let name = self.consume_identifier("Expect index signature parameter name.")?;
+        let parameter = self.annotate(AstNode::identifier(name), false)?;
+        self.consume(
+            Token::RightBracket,
+            "Expect ']' after index signature parameter.",
+        )?;
+        Ok(AstNode::TSIndexSignature {
+            parameters: vec![parameter],
+            type_annotation: self.parse_type_annotation()?,
+            readonly,
+            r#static: is_static,
+        })
+    }
+
+    /// Parses a Flow declaration if one starts at the current token: a type
+    /// alias or an opaque type.
+    fn flow_declaration(&mut self) -> Option<ParseResult<AstNode>> {
+        if !self.options.flow || self.is_at_end() || self.tokens[self.current + 1].newline_before {
+            return None;
+        }
+        let next = self.peek_next().clone();
+        if self.is_word("type") && matches!(next, Token::Identifier(_)) {
+            self.advance();
+            return Some(self.flow_type_alias());
+        }
+        if self.is_word("opaque") && matches!(&next, Token::Identifier(word) if word == "type") {
+            self.advance();
+            self.advance();
+            return Some(self.flow_opaque_type(false));
+        }
+        None
+    }
+
+    /// Parses a Flow `declare` statement if one starts at the current token,
+    /// as found in library definitions: `declare var`, `declare function`,
+    /// `declare class`, `declare type`, `declare opaque type`, `declare
+    /// module`, `declare module.exports` and `declare export`.
+    fn flow_declare(&mut self) -> Option<ParseResult<AstNode>> {
+        if !self.options.flow || !self.is_word("declare") || self.is_at_end() {
+            return None;
+        }
+        let next = &self.tokens[self.current + 1];
+        let after = self.token_at(self.current + 2);
+        let starts_declaration = match &next.token {
+            Token::Var | Token::Const | Token::Function | Token::Class | Token::Export => true,
+            Token::Identifier(word) => match word.as_str() {
+                "let" | "type" => matches!(after, Token::Identifier(_)),
+                "opaque" => matches!(after, Token::Identifier(word) if word == "type"),
+                "module" => matches!(after, Token::String(_) | Token::Identifier(_) | Token::Dot),
+                _ => false,
+            },
+            _ => false,
+        };
+        if next.newline_before || !starts_declaration {
+            return None;
+        }
+        self.advance();
+        if self.match_token(Token::Export) {
+            return Some(self.flow_declare_export());
+        }
+        Some(self.flow_declared())
+    }
+
+    /// Parses what follows `declare` or `declare export`.
+    fn flow_declared(&mut self) -> ParseResult<AstNode> {
+        if let Some(kind) = self.variable_kind() {
+            self.advance();
+            let name = self.consume_identifier("Expect variable name.")?;
+            let id = self.annotate(AstNode::identifier(name), false)?;
+            self.consume_semicolon("Expect ';' after variable declaration.")?;
+            return Ok(AstNode::DeclareVariable {
+                id: Box::new(id),
+                kind: kind.to_string(),
+            });
+        }
+        if self.match_token(Token::Function) {
+            let name = self.consume_identifier("Expect function name.")?;
+            let function_type = self.flow_function_type(Token::Colon)?;
+            self.consume_semicolon("Expect ';' after function declaration.")?;
+            return Ok(AstNode::DeclareFunction {
+                id: Box::new(AstNode::Identifier {
+                    name,
+                    type_annotation: Some(Box::new(AstNode::TypeAnnotation {
+                        type_annotation: Box::new(function_type),
+                    })),
+                    optional: false,
+                }),
+            });
+        }
+        if self.match_token(Token::Class) {
+            return self.flow_declare_class();
+        }
+        if self.match_word("type") {
+            let AstNode::TypeAlias {
+                id,
+                type_parameters,
+                right,
+            } = self.flow_type_alias()?
+            else {
+                unreachable!()
+            };
+            return Ok(AstNode::DeclareTypeAlias {
+                id,
+                type_parameters,
+                right,
+            });
+        }
+        if self.match_word("opaque") {
+            self.consume_word("type", "Expect 'type' after 'opaque'.")?;
+            return self.flow_opaque_type(true);
+        }
+        self.consume_word("module", "Expect declaration after 'declare'.")?;
+        if self.match_token(Token::Dot) {
+            self.consume_word("exports", "Expect 'exports' after 'module.'.")?;
+            self.consume(Token::Colon, "Expect ':' after 'module.exports'.")?;
+            let type_annotation = self.flow_type_annotation()?;
+            self.consume_semicolon("Expect ';' after module exports declaration.")?;
+            return Ok(AstNode::DeclareModuleExports { type_annotation });
+        }
+        self.flow_declare_module()
+    }
+
+    /// Parses a declared class after `declare class`. Its body is an object
+    /// type whose properties may be `static`.
+    fn flow_declare_class(&mut self) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect class name.")?;
+        let type_parameters = self.flow_type_parameters()?;
+        let mut extends = Vec::new();
+        if self.match_token(Token::Extends) {
+            let AstNode::GenericTypeAnnotation {
+                id,
+                type_parameters,
+            } = self.flow_generic_type()?
+            else {
+                unreachable!()
+            };
+            extends.push(AstNode::InterfaceExtends {
+                id,
+                type_parameters,
+            });
+        }
+        self.consume(Token::LeftBrace, "Expect '{' before class body.")?;
+        let body = self.flow_object_type(true)?;
+        Ok(AstNode::DeclareClass {
+            id: Box::new(AstNode::identifier(id)),
+            type_parameters,
+            extends,
+            body: Box::new(body),
+        })
+    }
+
+    /// Parses a declared module after `declare module`. Its body holds
+    /// imports and `declare` statements.
+    fn flow_declare_module(&mut self) -> ParseResult<AstNode> {
+        let id = match self.peek().clone() {
+            Token::String(value) => {
+                self.advance();
+                AstNode::StringLiteral(value)
+            }
+            _ => AstNode::identifier(self.consume_identifier("Expect module name.")?),
+        };
+        self.consume(Token::LeftBrace, "Expect '{' before module body.")?;
+        let mut body = Vec::new();
+        while !self.check(&Token::RightBrace) && !self.is_at_end() {
+            body.push(self.recovering(|parser| {
+                if parser.is_import_declaration() {
+                    parser.advance();
+                    return parser.import_declaration();
+                }
+                match parser.flow_declare() {
+                    Some(declaration) => declaration,
+                    None => Err(parser.error(
+                        ErrorCode::UnexpectedToken,
+                        "'declare'",
+                        "Expect import or 'declare' statement in declared module.",
+                    )),
+                }
+            }));
+        }
+        self.consume(Token::RightBrace, "Expect '}' after module body.")?;
+        let is_es_module = body.iter().any(|statement| {
+            matches!(
+                statement,
+                AstNode::DeclareExportDeclaration { .. }
+                    | AstNode::DeclareExportAllDeclaration { .. }
+            )
+        });
+        Ok(AstNode::DeclareModule {
+            id: Box::new(id),
+            body: Box::new(AstNode::BlockStatement { body }),
+            kind: if is_es_module { "ES" } else { "CommonJS" }.to_string(),
+        })
+    }
+
+    /// Parses what follows `declare export`: a declaration, `default`
+    /// followed by a declaration or type, export specifiers, or `*`.
+    fn flow_declare_export(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::Star) {
+            self.consume_word("from", "Expect 'from' after export '*'.")?;
+            let source = self.module_source()?;
+            self.consume_semicolon("Expect ';' after export declaration.")?;
+            return Ok(AstNode::DeclareExportAllDeclaration {
+                source: Box::new(source),
+            });
+        }
+        if self.match_token(Token::LeftBrace) {
+            let AstNode::ExportNamedDeclaration {
+                specifiers, source, ..
+            } = self.export_specifiers()?
+            else {
+                unreachable!()
+            };
+            return Ok(AstNode::DeclareExportDeclaration {
+                declaration: None,
+                specifiers,
+                source,
+                default: false,
+            });
+        }
+        let default = self.match_token(Token::Default);
+        let declaration = if default && !matches!(self.peek(), Token::Function | Token::Class) {
+            let default_type = self.flow_type()?;
+            self.consume_semicolon("Expect ';' after export declaration.")?;
+            default_type
+        } else if self.match_word("type") {
+            self.flow_type_alias()?
+        } else {
+            self.flow_declared()?
+        };
+        Ok(AstNode::DeclareExportDeclaration {
+            declaration: Some(Box::new(declaration)),
+            specifiers: Vec::new(),
+            source: None,
+            default,
+        })
+    }
+
+    /// Parses Flow's `export type { ... }`, `export type A = T` and
+    /// `export opaque type` after `export`.
+    fn flow_export_declaration(&mut self) -> Option<ParseResult<AstNode>> {
+        if self.is_word("type") && self.peek_next() == &Token::LeftBrace {
+            self.advance();
+            self.advance();
+            return Some(self.export_specifiers().map(into_type_export));
+        }
+        let declaration = self.flow_declaration()?;
+        Some(
+            declaration.map(|declaration| AstNode::ExportNamedDeclaration {
+                declaration: Some(Box::new(declaration)),
+                specifiers: Vec::new(),
+                source: None,
+                attributes: Vec::new(),
+                export_kind: "type".to_string(),
+            }),
+        )
+    }
+
+    /// Parses a type alias after `type`.
+    fn flow_type_alias(&mut self) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect type alias name.")?;
+        let type_parameters = self.flow_type_parameters()?;
+        self.consume(Token::Equal, "Expect '=' after type alias name.")?;
+        let right = self.flow_type()?;
+        self.consume_semicolon("Expect ';' after type alias.")?;
+        Ok(AstNode::TypeAlias {
+            id: Box::new(AstNode::identifier(id)),
+            type_parameters,
+            right: Box::new(right),
+        })
+    }
+
+    /// Parses an opaque type after `opaque type`. Outside the defining file
+    /// only the optional supertype after `:` is visible. Declared opaque
+    /// types have no underlying type.
+    fn flow_opaque_type(&mut self, is_declared: bool) -> ParseResult<AstNode> {
+        let id = self.consume_identifier("Expect opaque type name.")?;
+        let type_parameters = self.flow_type_parameters()?;
+        let supertype = if self.match_token(Token::Colon) {
+            Some(Box::new(self.flow_type()?))
+        } else {
+            None
+        };
+        if is_declared {
+            self.consume_semicolon("Expect ';' after opaque type.")?;
+            return Ok(AstNode::DeclareOpaqueType {
+                id: Box::new(AstNode::identifier(id)),
+                type_parameters,
+                supertype,
+            });
+        }
+        self.consume(Token::Equal, "Expect '=' after opaque type name.")?;
+        let impltype = self.flow_type()?;
+        self.consume_semicolon("Expect ';' after opaque type.")?;
+        Ok(AstNode::OpaqueType {
+            id: Box::new(AstNode::identifier(id)),
+            type_parameters,
+            supertype,
+            impltype: Box::new(impltype),
+        })
+    }
+
+    /// Parses a Flow type after the `:` of an annotation.
+    fn flow_type_annotation(&mut self) -> ParseResult<Box<AstNode>> {
+        Ok(Box::new(AstNode::TypeAnnotation {
+            type_annotation: Box::new(self.flow_type()?),
+        }))
+    }
+
+    /// Parses Flow type parameters such as `<+T: Bound = Default>`, if
+    /// present.
+    fn flow_type_parameters(&mut self) -> ParseResult<Option<Box<AstNode>>> {
+        if !self.match_token(Token::Less) {
+            return Ok(None);
+        }
+        let mut params = Vec::new();
+        loop {
+            let variance = self.flow_variance();
+            let name = self.consume_identifier("Expect type parameter name.")?;
+            let bound = if self.match_token(Token::Colon) {
+                Some(self.flow_type_annotation()?)
+            } else {
+                None
+            };
+            let default = if self.match_token(Token::Equal) {
+                Some(Box::new(self.flow_type()?))
+            } else {
+                None
+            };
+            params.push(AstNode::TypeParameter {
+                name,
+                bound,
+                variance,
+                default,
+            });
+            if !self.match_token(Token::Comma) || self.check(&Token::Greater) {
+                break;
+            }
+        }
+        self.consume_type_closer("Expect '>' after type parameters.")?;
+        Ok(Some(Box::new(AstNode::TypeParameterDeclaration { params })))
+    }
+
+    /// Parses Flow type arguments such as `<A, B>`.
+    fn flow_type_arguments(&mut self) -> ParseResult<AstNode> {
+        self.consume(Token::Less, "Expect '<' before type arguments.")?;
+        let mut params = Vec::new();
+        while !self.check(&Token::Greater) {
+            params.push(self.flow_type()?);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume_type_closer("Expect '>' after type arguments.")?;
+        Ok(AstNode::TypeParameterInstantiation { params })
+    }
+
+    /// Parses a Flow variance sigil, `+` for covariant or `-` for
+    /// contravariant, if present.
+    fn flow_variance(&mut self) -> Option<Box<AstNode>> {
+        if !self.options.flow {
+            return None;
+        }
+        let kind = match self.peek() {
+            Token::Plus => "plus",
+            Token::Minus => "minus",
+            _ => return None,
+        };
+        self.advance();
+        Some(Box::new(AstNode::Variance {
+            kind: kind.to_string(),
+        }))
+    }
+
+    /// Parses a Flow type: a union of intersections of prefix types.
+    fn flow_type(&mut self) -> ParseResult<AstNode> {
+        self.match_token(Token::Pipe);
+        let mut types = vec![self.flow_intersection_type()?];
+        // `|}` closes an exact object type.
+        while self.check(&Token::Pipe) && self.peek_next() != &Token::RightBrace {
+            self.advance();
+            types.push(self.flow_intersection_type()?);
+        }
+        Ok(match types.len() {
+            1 => types.remove(0),
+            _ => AstNode::UnionTypeAnnotation { types },
+        })
+    }
+
+    fn flow_intersection_type(&mut self) -> ParseResult<AstNode> {
+        self.type_list(Token::Ampersand, Self::flow_prefix_type, |types| {
+            AstNode::IntersectionTypeAnnotation { types }
+        })
+    }
+
+    /// Parses a type with any `?` that makes it nullable and any `[]` that
+    /// makes it an array type.
+    fn flow_prefix_type(&mut self) -> ParseResult<AstNode> {
+        if self.match_token(Token::Question) {
+            return Ok(AstNode::NullableTypeAnnotation {
+                type_annotation: Box::new(self.flow_prefix_type()?),
+            });
+        }
+        let mut result = self.flow_primary_type()?;
+        while self.check(&Token::LeftBracket)
+            && self.peek_next() == &Token::RightBracket
+            && !self.tokens[self.current].newline_before
+        {
+            self.advance();
+            self.advance();
+            result = AstNode::ArrayTypeAnnotation {
+                element_type: Box::new(result),
+            };
+        }
+        Ok(result)
+    }
+
+    fn flow_primary_type(&mut self) -> ParseResult<AstNode> {
+        let simple = match (self.peek().clone(), self.peek_next().clone()) {
+            (Token::Minus, Token::Number(value)) => {
+                self.advance();
+                Some(AstNode::NumberLiteralTypeAnnotation(-value))
+            }
+            (Token::String(value), _) => Some(AstNode::StringLiteralTypeAnnotation(value)),
+            (Token::Number(value), _) => Some(AstNode::NumberLiteralTypeAnnotation(value)),
+            (Token::True, _) => Some(AstNode::BooleanLiteralTypeAnnotation(true)),
+            (Token::False, _) => Some(AstNode::BooleanLiteralTypeAnnotation(false)),
+            (Token::Null, _) => Some(AstNode::NullLiteralTypeAnnotation),
+            (Token::Void, _) => Some(AstNode::VoidTypeAnnotation),
+            (Token::Star, _) => Some(AstNode::ExistsTypeAnnotation),
+            _ => None,
+        };
+        if let Some(simple) = simple {
+            self.advance();
+            return Ok(simple);
+        }
+        match self.peek().clone() {
+            Token::LeftParen
+                if self.token_at(self.matching_bracket(self.current) + 1) == &Token::Arrow =>
+            {
+                self.flow_function_type(Token::Arrow)
+            }
+            Token::Less => self.flow_function_type(Token::Arrow),
+            Token::LeftParen => {
+                self.advance();
+                let inner = self.flow_type()?;
+                self.consume(Token::RightParen, "Expect ')' after type.")?;
+                Ok(inner)
+            }
+            Token::LeftBracket => {
+                self.advance();
+                let mut types = Vec::new();
+                while !self.check(&Token::RightBracket) {
+                    types.push(self.flow_type()?);
+                    if !self.match_token(Token::Comma) {
+                        break;
+                    }
+                }
+                self.consume(Token::RightBracket, "Expect ']' after tuple element types.")?;
+                Ok(AstNode::TupleTypeAnnotation { types })
+            }
+            Token::LeftBrace => {
+                self.advance();
+                self.flow_object_type(false)
+            }
+            Token::Typeof => {
+                self.advance();
+                Ok(AstNode::TypeofTypeAnnotation {
+                    argument: Box::new(self.flow_generic_type()?),
+                })
+            }
+            Token::Identifier(name) => {
+                if self.peek_next() != &Token::Dot {
+                    if let Some(keyword) = flow_keyword_type(&name) {
+                        self.advance();
+                        return Ok(keyword);
+                    }
+                }
+                self.flow_generic_type()
+            }
+            _ => Err(self.error(ErrorCode::UnexpectedToken, "type", "Expect type.")),
+        }
+    }
+
+    /// Parses a possibly qualified type name such as `A.B`, with type
+    /// arguments if they start on the same line.
+    fn flow_generic_type(&mut self) -> ParseResult<AstNode> {
+        let mut id = AstNode::identifier(self.consume_identifier("Expect type name.")?);
+        while self.match_token(Token::Dot) {
+            let name = self.consume_property_name("Expect name after '.'.")?;
+            id = AstNode::QualifiedTypeIdentifier {
+                qualification: Box::new(id),
+                id: Box::new(AstNode::identifier(name)),
+            };
+        }
+        let type_parameters =
+            if self.check(&Token::Less) && !self.tokens[self.current].newline_before {
+                Some(Box::new(self.flow_type_arguments()?))
+            } else {
+                None
+            };
+        Ok(AstNode::GenericTypeAnnotation {
+            id: Box::new(id),
+            type_parameters,
+        })
+    }
+
+    /// Parses a function type such as `<T>(x: T, ...rest: Array<T>) => U`,
+    /// whose parameters may leave out their names. In object types, methods
+    /// and call properties separate the return type with `:` instead of
+    /// `=>`.
+    fn flow_function_type(&mut self, separator: Token) -> ParseResult<AstNode> {
+        let type_parameters = self.flow_type_parameters()?;
+        self.consume(
+            Token::LeftParen,
+            "Expect '(' before function type parameters.",
+        )?;
+        let mut params = Vec::new();
+        let mut rest = None;
+        while !self.check(&Token::RightParen) {
+            if self.match_token(Token::Ellipsis) {
+                rest = Some(Box::new(self.flow_function_type_param()?));
+                break;
+            }
+            params.push(self.flow_function_type_param()?);
+            if !self.match_token(Token::Comma) {
+                break;
+            }
+        }
+        self.consume(
+            Token::RightParen,
+            "Expect ')' after function type parameters.",
+        )?;
+        let message = format!("Expect '{}' after function type parameters.", separator);
+        self.consume(separator, &message)?;
+        Ok(AstNode::FunctionTypeAnnotation {
+            type_parameters,
+            params,
+            rest,
+            return_type: Box::new(self.flow_type()?),
+        })
+    }
+
+    fn flow_function_type_param(&mut self) -> ParseResult<AstNode> {
+        let named = matches!(self.peek(), Token::Identifier(_))
+            && (self.peek_next() == &Token::Colon
+                || (self.peek_next() == &Token::Question
+                    && self.token_at(self.current + 2) == &Token::Colon));
+        let mut name = None;
+        let mut optional = false;
+        if named {
+            name = Some(Box::new(AstNode::identifier(
+                self.consume_identifier("Expect parameter name.")?,
+            )));
+            optional = self.match_token(Token::Question);
+            self.advance();
+        }
+        Ok(AstNode::FunctionTypeParam {
+            name,
+            type_annotation: Box::new(self.flow_type()?),
+            optional,
+        })
+    }
+
+    /// Parses the rest of an object type after its `{`. `{| ... |}` makes it
+    /// exact, and a trailing `...` makes it explicitly inexact. Properties
+    /// may be `static` when `allow_static` is set, in declared classes.
+    fn flow_object_type(&mut self, allow_static: bool) -> ParseResult<AstNode> {
+        let mut properties = Vec::new();
+        let mut indexers = Vec::new();
+        let mut call_properties = Vec::new();
+        let mut inexact = false;
+        // The bars of an empty `{||}` lex as one `||`.
+        let empty_exact = self.match_token(Token::PipePipe);
+        let exact = empty_exact || self.match_token(Token::Pipe);
+        let closer = if exact {
+            Token::Pipe
+        } else {
+            Token::RightBrace
+        };
+        while !empty_exact && !self.check(&closer) && !self.check(&Token::RightBrace) {
+            if self.match_token(Token::Ellipsis) {
+                if matches!(
+                    self.peek(),
+                    Token::Comma | Token::Semicolon | Token::RightBrace | Token::Pipe
+                ) {
+                    inexact = true;
+                } else {
+                    properties.push(AstNode::ObjectTypeSpreadProperty {
+                        argument: Box::new(self.flow_type()?),
+                    });
+                }
+            } else if self.check(&Token::LeftParen) || self.check(&Token::Less) {
+                call_properties.push(AstNode::ObjectTypeCallProperty {
+                    value: Box::new(self.flow_function_type(Token::Colon)?),
+                });
+            } else {
+                let is_static = allow_static
+                    && self.is_word("static")
+                    && !matches!(
+                        self.peek_next(),
+                        Token::Colon | Token::Question | Token::LeftParen | Token::Less
+                    );
+                if is_static {
+                    self.advance();
+                }
+                let variance = self.flow_variance();
+                if self.check(&Token::LeftBracket) {
+                    indexers.push(self.flow_object_type_indexer(variance)?);
+                } else {
+                    properties.push(self.flow_object_type_property(variance, is_static)?);
+                }
+            }
+            if !self.match_token(Token::Comma) && !self.match_token(Token::Semicolon) {
+                break;
+            }
+        }
+        if exact && !empty_exact {
+            self.consume(Token::Pipe, "Expect '|}' after exact object type.")?;
+        }
+        self.consume(Token::RightBrace, "Expect '}' after object type.")?;
+        Ok(AstNode::ObjectTypeAnnotation {
+            properties,
+            indexers,
+            call_properties,
+            exact,
+            inexact,
+        })
+    }
+
+    /// Parses a property `key?: T` or method `key(x: T): U` of an object
+    /// type.
+    fn flow_object_type_property(
+        &mut self,
+        variance: Option<Box<AstNode>>,
+        is_static: bool,
+    ) -> ParseResult<AstNode> {
+        let key = match self.peek().clone() {
+            Token::String(value) => {
+                self.advance();
+                AstNode::StringLiteral(value)
+            }
+            _ => AstNode::identifier(self.consume_property_name("Expect property name.")?),
+        };
+        let method =
+            variance.is_none() && (self.check(&Token::LeftParen) || self.check(&Token::Less));
+        let optional = !method && self.match_token(Token::Question);
+        let value = if method {
+            self.flow_function_type(Token::Colon)?
+        } else {
+            self.consume(Token::Colon, "Expect ':' after property name.")?;
+            self.flow_type()?
+        };
+        Ok(AstNode::ObjectTypeProperty {
+            key: Box::new(key),
+            value: Box::new(value),
+            optional,
+            method,
+            variance,
+            r#static: is_static,
+        })
+    }
+
+    /// Parses an indexer `[K]: V` or `[name: K]: V` of an object type.
+    fn flow_object_type_indexer(&mut self, variance: Option<Box<AstNode>>) -> ParseResult<AstNode> {
+        self.advance();
+        let id = if matches!(self.peek(), Token::Identifier(_)) && self.peek_next() == &Token::Colon
+        {
+            let name = self.consume_identifier("Expect indexer name.")?;
+            self.advance();
+            Some(Box::new(AstNode::identifier(name)))
+        } else {
+            None
+        };
+        let key = self.flow_type()?;
+        self.consume(Token::RightBracket, "Expect ']' after indexer key.")?;
+        self.consume(Token::Colon, "Expect ':' after indexer key.")?;
+        Ok(AstNode::ObjectTypeIndexer {
+            id,
+            key: Box::new(key),
+            value: Box::new(self.flow_type()?),
+            variance,
+        })
+    }
+
+    /// Index of the token that closes the bracket at `open`, or of the end
+    /// of input if it is never closed.
+    fn matching_bracket(&self, open: usize) -> usize {
+        let mut depth = 0;
+        let mut index = open;
+        loop {
+            match self.tokens[index].token {
+                Token::LeftParen | Token::LeftBracket | Token::LeftBrace => depth += 1,
+                Token::RightParen | Token::RightBracket | Token::RightBrace => depth -= 1,
+                Token::EOF => return index,
+                _ => {}
+            }
+            if depth == 0 {
+                return index;
+            }
+            index += 1;
+        }
+    }
+
+    fn match_token(&mut self, token: Token) -> bool {
+        if self.check(&token) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn check(&self, token: &Token) -> bool {
+        self.peek() == token
+    }
+
+    fn consume(&mut self, token: Token, message: &str) -> ParseResult<&SpannedToken> {
+        if self.check(&token) {
+            self.advance();
+            return Ok(&self.tokens[self.current - 1]);
+        }
+        Err(self.error(ErrorCode::UnexpectedToken, format!("'{}'", token), message))
+    }
+
+    /// Whether the current token is the identifier `word`, for contextual
+    /// keywords such as `async` or `static`.
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Token::Identifier(name) if name == word)
+    }
+
+    fn match_word(&mut self, word: &str) -> bool {
+        if self.is_word(word) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_word(&mut self, word: &str, message: &str) -> ParseResult<()> {
+        if self.match_word(word) {
+            return Ok(());
+        }
+        Err(self.error(ErrorCode::UnexpectedToken, format!("'{}'", word), message))
+    }
+
+    /// Whether the current token can be consumed by `consume_identifier`.
+    fn is_identifier(&self) -> bool {
+        match self.peek() {
+            Token::Identifier(_) => true,
+            Token::Await => self.await_is_identifier(),
+            _ => false,
+        }
+    }
+
+    fn await_is_identifier(&self) -> bool {
+        !self.in_async && self.options.source_type == SourceType::Script
+    }
+
+    fn consume_identifier(&mut self, message: &str) -> ParseResult<String> {
+        match self.peek().clone() {
+            Token::Identifier(name) => {
+                self.advance();
+                return Ok(name);
+            }
+            Token::Await if self.await_is_identifier() => {
+                self.advance();
+                return Ok("await".to_string());
+            }
+            _ => {}
+        }
+        Err(self.error(ErrorCode::ExpectedIdentifier, "identifier", message))
+    }
+
+    /// Consumes an IdentifierName, which unlike an identifier may be a
+    /// reserved word.
+    fn consume_property_name(&mut self, message: &str) -> ParseResult<String> {
+        match self.peek().clone() {
+            Token::Identifier(name) => {
+                self.advance();
+                Ok(name)
+            }
+            token if token.is_keyword() => {
+                self.advance();
+                Ok(token.to_string())
+            }
+            _ => Err(self.error(ErrorCode::ExpectedIdentifier, "property name", message)),
+        }
+    }
+
+    /// Consumes a `;`, applying automatic semicolon insertion before `}`, at
+    /// the end of input, or after a line terminator.
+    fn consume_semicolon(&mut self, message: &str) -> ParseResult<()> {
+        if !self.match_token(Token::Semicolon) && !self.can_insert_semicolon() {
+            return Err(self.error(ErrorCode::MissingSemicolon, "';'", message));
+        }
+        Ok(())
+    }
+
+    fn can_insert_semicolon(&self) -> bool {
+        self.is_at_end()
+            || self.check(&Token::RightBrace)
+            || self.tokens[self.current].newline_before
+    }
+
+    /// Builds an error about the current token. An unknown character is
+    /// always reported as such, whatever was expected in its place.
+    fn error(&self, code: ErrorCode, expected: impl Into<String>, message: &str) -> ParseError {
+        self.error_at(self.current, code, expected, message)
+    }
+
+    fn error_at(
+        &self,
+        index: usize,
+        code: ErrorCode,
+        expected: impl Into<String>,
+        message: &str,
+    ) -> ParseError {
+        let found = &self.tokens[index];
+        let (code, message) = match found.token {
+            Token::Illegal(ch) => (
+                ErrorCode::InvalidCharacter,
+                format!("Unexpected character '{}'.", ch),
+            ),
+            _ => (code, message.to_string()),
+        };
+        ParseError {
+            code,
+            span: found.span,
+            expected: expected.into(),
+            found: found.token.clone(),
+            message,
+        }
+    }
+
+    /// Reports `feature`, used at token `index`, if the targeted ECMAScript
+    /// edition predates `version`, the one that introduced it.
+    fn require_version(&mut self, index: usize, version: u32, feature: &str) {
+        if let Some(message) = self.options.unsupported(feature, version) {
+            let expected = format!("ES{} syntax", self.options.ecma_version);
+            let error = self.error_at(index, ErrorCode::UnsupportedSyntax, expected, &message);
+            self.errors.push(error);
+        }
+    }
+
+    /// Reports the proposal syntax at token `index` unless `feature` is
+    /// enabled. The syntax is parsed either way.
+    fn require_feature(&mut self, index: usize, feature: Feature) {
+        if let Some(message) = self.options.disabled(feature) {
+            let error = self.error_at(
+                index,
+                ErrorCode::FeatureNotEnabled,
+                "enabled feature",
+                &message,
+            );
+            self.errors.push(error);
+        }
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1].token
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current].token
+    }
+
+    fn peek_next(&self) -> &Token {
+        let index = (self.current + 1).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn token_at(&self, index: usize) -> &Token {
+        &self.tokens[index.min(self.tokens.len() - 1)].token
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek() == &Token::EOF
+    }
+}
+
+/// The private name a class member declares, whether the member is static,
+/// and `get` or `set` for accessors. TypeScript overloads without a body
+/// declare nothing.
+fn private_member(member: &AstNode) -> Option<(&str, bool, Option<&str>)> {
+    let (key, is_static, accessor) = match member {
+        AstNode::MethodDefinition {
+            key,
+            value,
+            kind,
+            r#static,
+            ..
+        } if matches!(**value, AstNode::FunctionExpression { .. }) => {
+            let accessor = matches!(kind.as_str(), "get" | "set").then_some(kind.as_str());
+            (key, *r#static, accessor)
+        }
+        AstNode::PropertyDefinition { key, r#static, .. }
+        | AstNode::AccessorProperty { key, r#static, .. } => (key, *r#static, None),
+        _ => return None,
+    };
+    match &**key {
+        AstNode::PrivateIdentifier(name) => Some((name.as_str(), is_static, accessor)),
+        _ => None,
+    }
+}
+
+/// Whether `expression` is a `#name` member access, optionally chained.
+fn is_private_reference(expression: &AstNode) -> bool {
+    match expression {
+        AstNode::MemberExpression { property, .. } => {
+            matches!(**property, AstNode::PrivateIdentifier(_))
+        }
+        AstNode::ChainExpression { expression } => is_private_reference(expression),
+        _ => false,
+    }
+}
+
+/// Whether the directive prologue is still open after the top-level
+/// statements `previous`, and whether it holds a `use strict` directive.
+fn prologue_after(previous: &[AstNode]) -> (bool, bool) {
+    let mut strict = false;
+    for statement in previous {
+        match statement {
+            AstNode::ExpressionStatement {
+                directive: Some(directive),
+                ..
+            } => strict |= directive == "use strict",
+            _ => return (false, strict),
+        }
+    }
+    (true, strict)
+}
+
+/// Writes a JSX tag name the way it appears in the source.
+fn jsx_tag_name(name: &AstNode) -> String {
+    match name {
+        AstNode::JSXIdentifier(name) => name.clone(),
+        AstNode::JSXNamespacedName { namespace, name } => {
+            format!("{}:{}", jsx_tag_name(namespace), jsx_tag_name(name))
+        }
+        AstNode::JSXMemberExpression { object, property } => {
+            format!("{}.{}", jsx_tag_name(object), jsx_tag_name(property))
+        }
+        _ => String::new(),
+    }
+}
+
+/// Turns a named function or class expression into a declaration, as
+/// `export default` allows both forms. Anonymous ones stay expressions.
+fn into_declaration(node: AstNode) -> AstNode {
+    match node {
+        AstNode::FunctionExpression {
+            id: Some(id),
+            params,
+            body,
+            generator,
+            r#async,
+            type_parameters,
+            return_type,
+        } => AstNode::FunctionDeclaration {
+            id,
+            params,
+            body,
+            generator,
+            r#async,
+            type_parameters,
+            return_type,
+        },
+        AstNode::ClassExpression {
+            id: Some(id),
+            super_class,
+            body,
+            decorators,
+            type_parameters,
+            super_type_arguments,
+            implements,
+        } => AstNode::ClassDeclaration {
+            id,
+            super_class,
+            body,
+            decorators,
+            type_parameters,
+            super_type_arguments,
+            implements,
+            r#abstract: false,
+            declare: false,
+        },
+        node => node,
+    }
+}
+
+/// Marks a class declaration written with TypeScript's `abstract`.
+fn into_abstract(mut node: AstNode) -> AstNode {
+    if let AstNode::ClassDeclaration { r#abstract, .. } = &mut node {
+        *r#abstract = true;
+    }
+    node
+}
+
+/// Marks a declaration written after TypeScript's `declare`.
+fn into_ambient(mut node: AstNode) -> AstNode {
+    match &mut node {
+        AstNode::VariableDeclaration { declare, .. }
+        | AstNode::ClassDeclaration { declare, .. }
+        | AstNode::TSDeclareFunction { declare, .. }
+        | AstNode::TSInterfaceDeclaration { declare, .. }
+        | AstNode::TSTypeAliasDeclaration { declare, .. }
+        | AstNode::TSEnumDeclaration { declare, .. }
+        | AstNode::TSModuleDeclaration { declare, .. } => *declare = true,
+        _ => {}
+    }
+    node
+}
+
+/// Wraps a type in the `TSTypeAnnotation` that annotations and return
+/// types hold.
+fn type_annotation(type_annotation: AstNode) -> Box<AstNode> {
+    Box::new(AstNode::TSTypeAnnotation {
+        type_annotation: Box::new(type_annotation),
+    })
+}
+
+/// The node for a Flow keyword type such as `string`.
+fn flow_keyword_type(name: &str) -> Option<AstNode> {
+    Some(match name {
+        "any" => AstNode::AnyTypeAnnotation,
+        "mixed" => AstNode::MixedTypeAnnotation,
+        "empty" => AstNode::EmptyTypeAnnotation,
+        "number" => AstNode::NumberTypeAnnotation,
+        "string" => AstNode::StringTypeAnnotation,
+        "boolean" | "bool" => AstNode::BooleanTypeAnnotation,
+        "symbol" => AstNode::SymbolTypeAnnotation,
+        "bigint" => AstNode::BigIntTypeAnnotation,
+        _ => return None,
+    })
+}
+
+/// Marks the `ExportNamedDeclaration` of `export type { ... }` as exporting
+/// types only.
+fn into_type_export(mut declaration: AstNode) -> AstNode {
+    if let AstNode::ExportNamedDeclaration { export_kind, .. } = &mut declaration {
+        *export_kind = "type".to_string();
+    }
+    declaration
+}
+
+/// Whether `declare` followed by `token` starts an ambient declaration.
+fn starts_ambient_declaration(token: &Token) -> bool {
+    match token {
+        Token::Var | Token::Const | Token::Function | Token::Class => true,
+        Token::Identifier(word) => matches!(
+            word.as_str(),
+            "let"
+                | "enum"
+                | "abstract"
+                | "interface"
+                | "type"
+                | "namespace"
+                | "module"
+                | "global"
+                | "async"
+        ),
+        _ => false,
+    }
+}
+
+/// The node for a TypeScript keyword type such as `string`.
+fn keyword_type(name: &str) -> Option<AstNode> {
+    Some(match name {
+        "any" => AstNode::TSAnyKeyword,
+        "unknown" => AstNode::TSUnknownKeyword,
+        "number" => AstNode::TSNumberKeyword,
+        "string" => AstNode::TSStringKeyword,
+        "boolean" => AstNode::TSBooleanKeyword,
+        "bigint" => AstNode::TSBigIntKeyword,
+        "symbol" => AstNode::TSSymbolKeyword,
+        "object" => AstNode::TSObjectKeyword,
+        "never" => AstNode::TSNeverKeyword,
+        "undefined" => AstNode::TSUndefinedKeyword,
+        _ => return None,
+    })
+}
+
+/// Whether `token` can start a TypeScript type.
+fn starts_type(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::String(_)
+            | Token::Number(_)
+            | Token::Void
+            | Token::Null
+            | Token::This
+            | Token::True
+            | Token::False
+            | Token::Typeof
+            | Token::Import
+            | Token::New
+            | Token::Minus
+            | Token::LeftParen
+            | Token::LeftBracket
+            | Token::LeftBrace
+            | Token::Less
+    )
+}
+
+/// The type of `as const` and `<const>x`, which typescript-estree
+/// represents as a reference to a type named `const`.
+fn const_type_reference() -> AstNode {
+    AstNode::TSTypeReference {
+        type_name: Box::new(AstNode::identifier("const".to_string())),
+        type_arguments: None,
+    }
+}
+
+/// Whether `token` can start an expression.
+fn starts_expression(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::String(_)
+            | Token::Number(_)
+            | Token::LegacyOctal(_)
+            | Token::PrivateIdentifier(_)
+            | Token::Template(_)
+            | Token::TemplateHead(_)
+            | Token::This
+            | Token::Super
+            | Token::Null
+            | Token::True
+            | Token::False
+            | Token::Function
+            | Token::Class
+            | Token::New
+            | Token::Import
+            | Token::Typeof
+            | Token::Void
+            | Token::Delete
+            | Token::Await
+            | Token::LeftParen
+            | Token::LeftBracket
+            | Token::LeftBrace
+            | Token::Bang
+            | Token::Tilde
+            | Token::Plus
+            | Token::Minus
+            | Token::PlusPlus
+            | Token::MinusMinus
+            | Token::Less
+            | Token::At
+    )
+}
+
+/// Whether `token` can start a property name.
+fn is_property_key_start(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::String(_)
+            | Token::Number(_)
+            | Token::LegacyOctal(_)
+            | Token::LeftBracket
+    ) || token.is_keyword()
+}
+
+/// Whether `token` can start a class member name.
+fn is_class_key_start(token: &Token) -> bool {
+    matches!(token, Token::PrivateIdentifier(_)) || is_property_key_start(token)
+}
+
+/// Whether `node` can be assigned to without destructuring: a name or a
+/// property, possibly inside TypeScript's `x!`, `x as T`, `x satisfies T`
+/// or `<T>x`.
+fn is_simple_target(node: &AstNode) -> bool {
+    match node {
+        AstNode::Identifier { .. } | AstNode::MemberExpression { .. } => true,
+        AstNode::TSNonNullExpression { expression }
+        | AstNode::TSAsExpression { expression, .. }
+        | AstNode::TSSatisfiesExpression { expression, .. }
+        | AstNode::TSTypeAssertion { expression, .. } => is_simple_target(expression),
+        _ => false,
+    }
+}
+
+/// Converts an expression into the target of a destructuring assignment:
+/// array and object literals become patterns. Returns `None` if the
+/// expression cannot be assigned to.
+fn to_assignment_target(node: AstNode) -> Option<AstNode> {
+    match node {
+        node if is_simple_target(&node) => Some(node),
+        AstNode::ArrayExpression { elements } => {
+            let count = elements.len();
+            let elements = elements
+                .into_iter()
+                .enumerate()
+                .map(|(index, element)| match element {
+                    None => Some(None),
+                    // Only the last element may be a rest element.
+                    Some(AstNode::SpreadElement { argument }) if index + 1 == count => {
+                        Some(Some(AstNode::RestElement {
+                            argument: Box::new(to_assignment_target(*argument)?),
+                            type_annotation: None,
+                        }))
+                    }
+                    Some(element) => to_assignment_element(element).map(Some),
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(AstNode::ArrayPattern {
+                elements,
+                type_annotation: None,
+                optional: false,
+            })
+        }
+        AstNode::ObjectExpression { properties } => {
+            let count = properties.len();
+            let properties = properties
+                .into_iter()
+                .enumerate()
+                .map(|(index, property)| match property {
+                    AstNode::SpreadElement { argument }
+                        if index + 1 == count
+                            && matches!(
+                                *argument,
+                                AstNode::Identifier { .. } | AstNode::MemberExpression { .. }
+                            ) =>
+                    {
+                        Some(AstNode::RestElement {
+                            argument,
+                            type_annotation: None,
+                        })
+                    }
+                    AstNode::Property {
+                        key,
+                        value,
+                        kind,
+                        computed,
+                        shorthand,
+                        method: false,
+                    } if kind == "init" => Some(AstNode::Property {
+                        key,
+                        value: Box::new(to_assignment_element(*value)?),
+                        kind,
+                        computed,
+                        shorthand,
+                        method: false,
+                    }),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(AstNode::ObjectPattern {
+                properties,
+                type_annotation: None,
+                optional: false,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Converts an element of an array or object literal into a pattern,
+/// turning `target = default` into an `AssignmentPattern`.
+fn to_assignment_element(node: AstNode) -> Option<AstNode> {
+    match node {
+        AstNode::AssignmentExpression {
+            operator,
+            left,
+            right,
+        } if operator == "=" => Some(AstNode::AssignmentPattern { left, right }),
+        AstNode::AssignmentPattern { .. } => Some(node),
+        node => to_assignment_target(node),
+    }
+}
+
+fn is_assignment_operator(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Equal
+            | Token::PlusEqual
+            | Token::MinusEqual
+            | Token::StarEqual
+            | Token::SlashEqual
+            | Token::PercentEqual
+            | Token::StarStarEqual
+            | Token::LessLessEqual
+            | Token::GreaterGreaterEqual
+            | Token::GreaterGreaterGreaterEqual
+            | Token::AmpersandEqual
+            | Token::PipeEqual
+            | Token::CaretEqual
+            | Token::AmpersandAmpersandEqual
+            | Token::PipePipeEqual
+            | Token::QuestionQuestionEqual
+    )
+}
+
+/// Precedence of binary operators, from loosest to tightest binding. `??`
+/// shares the level of `||` but cannot be combined with `||` or `&&`.
+fn binary_precedence(token: &Token) -> Option<u8> {
+    match token {
+        Token::QuestionQuestion | Token::PipePipe => Some(1),
+        Token::AmpersandAmpersand => Some(2),
+        Token::Pipe => Some(3),
+        Token::Caret => Some(4),
+        Token::Ampersand => Some(5),
+        Token::EqualEqual | Token::BangEqual | Token::EqualEqualEqual | Token::BangEqualEqual => {
+            Some(6)
+        }
+        Token::Less
+        | Token::LessEqual
+        | Token::Greater
+        | Token::GreaterEqual
+        | Token::Instanceof
+        | Token::In => Some(7),
+        Token::LessLess | Token::GreaterGreater | Token::GreaterGreaterGreater => Some(8),
+        Token::Plus | Token::Minus => Some(9),
+        Token::Star | Token::Slash | Token::Percent => Some(10),
+        Token::StarStar => Some(11),
+        _ => None,
+    }
+}
+
+fn mixes_coalesce(operator: &Token, operand: &Token) -> bool {
+    let is_logical = |token: &Token| matches!(token, Token::PipePipe | Token::AmpersandAmpersand);
+    (operator == &Token::QuestionQuestion && is_logical(operand))
+        || (is_logical(operator) && operand == &Token::QuestionQuestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer;
+
+    use super::*;
+
+    fn expression_program(expression: AstNode) -> AstNode {
+        AstNode::Program {
+            body: vec![AstNode::ExpressionStatement {
+                expression: Box::new(expression),
+                directive: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parsing_simple_math_expression() {
+        let input = "2 + 2";
+        let expected_output = expression_program(AstNode::BinaryExpression {
+            operator: "+".to_string(),
+            left: Box::new(AstNode::NumberLiteral(2.0)),
+            right: Box::new(AstNode::NumberLiteral(2.0)),
+        });
+        let tokens = lexer::tokenize(input);
+        let ast = Parser::new(tokens).parse().unwrap();
+        assert_eq!(ast, expected_output);
+    }
+
+    #[test]
+    fn test_parsing_function_call() {
+        let input = "add(2, 3)";
+        let expected_output = expression_program(AstNode::CallExpression {
+            type_arguments: None,
+            callee: Box::new(AstNode::identifier("add".to_string())),
+            arguments: vec![AstNode::NumberLiteral(2.0), AstNode::NumberLiteral(3.0)],
+            optional: false,
+        });
+        let tokens = lexer::tokenize(input);
+        let ast = Parser::new(tokens).parse().unwrap();
+        assert_eq!(ast, expected_output);
+    }
+
+    #[test]
     fn test_parsing_variable_assignment() {
         let input = "let x = 5;";
-        let expected_output = AstNode::VariableDeclaration {
-            id: Box::new(AstNode::Identifier("x".to_string())),
-            init: Box::new(AstNode::NumberLiteral(5.0)),
+        let expected_output = AstNode::Program {
+            body: vec![AstNode::VariableDeclaration {
+                declare: false,
+                kind: "let".to_string(),
+                declarations: vec![AstNode::VariableDeclarator {
+                    definite: false,
+                    id: Box::new(AstNode::identifier("x".to_string())),
+                    init: Some(Box::new(AstNode::NumberLiteral(5.0))),
+                }],
+            }],
+        };
+        let tokens = lexer::tokenize(input);
+        let ast = Parser::new(tokens).parse().unwrap();
+        assert_eq!(ast, expected_output);
+    }
+
+    #[test]
+    fn test_parsing_precedence() {
+        let input = "a = b || c && 1 + 2 * 3 < 4";
+        let expected_output = expression_program(AstNode::AssignmentExpression {
+            operator: "=".to_string(),
+            left: Box::new(AstNode::identifier("a".to_string())),
+            right: Box::new(AstNode::LogicalExpression {
+                operator: "||".to_string(),
+                left: Box::new(AstNode::identifier("b".to_string())),
+                right: Box::new(AstNode::LogicalExpression {
+                    operator: "&&".to_string(),
+                    left: Box::new(AstNode::identifier("c".to_string())),
+                    right: Box::new(AstNode::BinaryExpression {
+                        operator: "<".to_string(),
+                        left: Box::new(AstNode::BinaryExpression {
+                            operator: "+".to_string(),
+                            left: Box::new(AstNode::NumberLiteral(1.0)),
+                            right: Box::new(AstNode::BinaryExpression {
+                                operator: "*".to_string(),
+                                left: Box::new(AstNode::NumberLiteral(2.0)),
+                                right: Box::new(AstNode::NumberLiteral(3.0)),
+                            }),
+                        }),
+                        right: Box::new(AstNode::NumberLiteral(4.0)),
+                    }),
+                }),
+            }),
+        });
+        let ast = Parser::new(lexer::tokenize(input)).parse().unwrap();
+        assert_eq!(ast, expected_output);
+    }
+
+    #[test]
+    fn test_parsing_function_and_if() {
+        let input = "function f(a, b) {
+                         if (a) return b
+                         else { return; }
+                     }";
+        let expected_output = AstNode::Program {
+            body: vec![AstNode::FunctionDeclaration {
+                return_type: None,
+                type_parameters: None,
+                id: Box::new(AstNode::identifier("f".to_string())),
+                params: vec![
+                    AstNode::identifier("a".to_string()),
+                    AstNode::identifier("b".to_string()),
+                ],
+                body: Box::new(AstNode::BlockStatement {
+                    body: vec![AstNode::IfStatement {
+                        test: Box::new(AstNode::identifier("a".to_string())),
+                        consequent: Box::new(AstNode::ReturnStatement {
+                            argument: Some(Box::new(AstNode::identifier("b".to_string()))),
+                        }),
+                        alternate: Some(Box::new(AstNode::BlockStatement {
+                            body: vec![AstNode::ReturnStatement { argument: None }],
+                        })),
+                    }],
+                }),
+                generator: false,
+                r#async: false,
+            }],
+        };
+        let ast = Parser::new(lexer::tokenize(input)).parse().unwrap();
+        assert_eq!(ast, expected_output);
+    }
+
+    fn parse_errors(input: &str) -> Vec<ParseError> {
+        Parser::new(lexer::tokenize(input)).parse().unwrap_err()
+    }
+
+    #[test]
+    fn test_missing_closing_paren() {
+        let errors = parse_errors("add(1, 2;");
+        assert_eq!(
+            errors,
+            vec![ParseError {
+                code: ErrorCode::UnexpectedToken,
+                span: Span::new(8, 9),
+                expected: "')'".to_string(),
+                found: Token::Semicolon,
+                message: "Expect ')' after arguments.".to_string(),
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "Expect ')' after arguments. Expected ')', found ';'."
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let error = &parse_errors("var = 1;")[0];
+        assert_eq!(error.code, ErrorCode::ExpectedIdentifier);
+        assert_eq!(error.code.as_str(), "E0002");
+        assert_eq!(error.found, Token::Equal);
+
+        let error = &parse_errors("a = ;")[0];
+        assert_eq!(error.code, ErrorCode::ExpectedExpression);
+        assert_eq!(error.span, Span::new(4, 5));
+
+        let error = &parse_errors("a = 1 b = 2")[0];
+        assert_eq!(error.code, ErrorCode::MissingSemicolon);
+        assert_eq!(error.found, Token::Identifier("b".to_string()));
+
+        let error = &parse_errors("if (a) {")[0];
+        assert_eq!(error.code, ErrorCode::UnexpectedToken);
+        assert_eq!(error.found, Token::EOF);
+        assert_eq!(error.span, Span::new(8, 8));
+        assert_eq!(error.found_description(), "end of input");
+
+        let error = &parse_errors("a = 1 # 2;")[0];
+        assert_eq!(error.code, ErrorCode::InvalidCharacter);
+        assert_eq!(error.found, Token::Illegal('#'));
+        assert_eq!(error.span, Span::new(6, 7));
+        assert_eq!(error.message, "Unexpected character '#'.");
+    }
+
+    #[test]
+    fn test_recovers_at_statement_boundaries() {
+        let input = "foo(1, 2;
+                     var x = 1;
+                     if (x) { x = ; y = 2; }
+                     ) z = 3;";
+        let (ast, errors) = Parser::new(lexer::tokenize(input)).parse_recovering();
+        let codes: Vec<ErrorCode> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                ErrorCode::UnexpectedToken,
+                ErrorCode::ExpectedExpression,
+                ErrorCode::ExpectedExpression,
+            ]
+        );
+        assert_eq!(errors[0].expected, "')'");
+        assert_eq!(
+            ast,
+            AstNode::Program {
+                body: vec![
+                    AstNode::Error,
+                    AstNode::VariableDeclaration {
+                        declare: false,
+                        kind: "var".to_string(),
+                        declarations: vec![AstNode::VariableDeclarator {
+                            definite: false,
+                            id: Box::new(AstNode::identifier("x".to_string())),
+                            init: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                        }],
+                    },
+                    AstNode::IfStatement {
+                        test: Box::new(AstNode::identifier("x".to_string())),
+                        consequent: Box::new(AstNode::BlockStatement {
+                            body: vec![
+                                AstNode::Error,
+                                AstNode::ExpressionStatement {
+                                    expression: Box::new(AstNode::AssignmentExpression {
+                                        operator: "=".to_string(),
+                                        left: Box::new(AstNode::identifier("y".to_string())),
+                                        right: Box::new(AstNode::NumberLiteral(2.0)),
+                                    }),
+                                    directive: None,
+                                },
+                            ],
+                        }),
+                        alternate: None,
+                    },
+                    AstNode::Error,
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_unclosed_block_keeps_body() {
+        let (ast, errors) =
+            Parser::new(lexer::tokenize("function f() { return 1;")).parse_recovering();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].found, Token::EOF);
+        assert_eq!(
+            ast,
+            AstNode::Program {
+                body: vec![AstNode::FunctionDeclaration {
+                    return_type: None,
+                    type_parameters: None,
+                    id: Box::new(AstNode::identifier("f".to_string())),
+                    params: vec![],
+                    body: Box::new(AstNode::BlockStatement {
+                        body: vec![AstNode::ReturnStatement {
+                            argument: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                        }],
+                    }),
+                    generator: false,
+                    r#async: false,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_stray_closing_brace_makes_progress() {
+        let (ast, errors) = Parser::new(lexer::tokenize("} } var a = 1;")).parse_recovering();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            ast,
+            AstNode::Program {
+                body: vec![
+                    AstNode::Error,
+                    AstNode::Error,
+                    AstNode::VariableDeclaration {
+                        declare: false,
+                        kind: "var".to_string(),
+                        declarations: vec![AstNode::VariableDeclarator {
+                            definite: false,
+                            id: Box::new(AstNode::identifier("a".to_string())),
+                            init: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                        }],
+                    },
+                ],
+            }
+        );
+    }
+
+    fn identifier(name: &str) -> AstNode {
+        AstNode::identifier(name.to_string())
+    }
+
+    fn binary(operator: &str, left: AstNode, right: AstNode) -> AstNode {
+        AstNode::BinaryExpression {
+            operator: operator.to_string(),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn logical(operator: &str, left: AstNode, right: AstNode) -> AstNode {
+        AstNode::LogicalExpression {
+            operator: operator.to_string(),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn assignment(operator: &str, left: AstNode, right: AstNode) -> AstNode {
+        AstNode::AssignmentExpression {
+            operator: operator.to_string(),
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn parse_expression(input: &str) -> AstNode {
+        expression_with(&ParseOptions::default(), input)
+    }
+
+    fn expression_with(options: &ParseOptions, input: &str) -> AstNode {
+        match first_statement_with(options, input) {
+            AstNode::ExpressionStatement { expression, .. } => *expression,
+            other => panic!("not an expression statement: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operator_associativity() {
+        assert_eq!(
+            parse_expression("a - b - c"),
+            binary(
+                "-",
+                binary("-", identifier("a"), identifier("b")),
+                identifier("c")
+            )
+        );
+        assert_eq!(
+            parse_expression("a ** b ** c"),
+            binary(
+                "**",
+                identifier("a"),
+                binary("**", identifier("b"), identifier("c"))
+            )
+        );
+        assert_eq!(
+            parse_expression("a = b **= c"),
+            assignment(
+                "=",
+                identifier("a"),
+                assignment("**=", identifier("b"), identifier("c"))
+            )
+        );
+    }
+
+    #[test]
+    fn test_operator_precedence_table() {
+        assert_eq!(
+            parse_expression("a | b ^ c & d == e < f << g + h * i ** j"),
+            binary(
+                "|",
+                identifier("a"),
+                binary(
+                    "^",
+                    identifier("b"),
+                    binary(
+                        "&",
+                        identifier("c"),
+                        binary(
+                            "==",
+                            identifier("d"),
+                            binary(
+                                "<",
+                                identifier("e"),
+                                binary(
+                                    "<<",
+                                    identifier("f"),
+                                    binary(
+                                        "+",
+                                        identifier("g"),
+                                        binary(
+                                            "*",
+                                            identifier("h"),
+                                            binary("**", identifier("i"), identifier("j")),
+                                        ),
+                                    ),
+                                ),
+                            ),
+                        ),
+                    ),
+                ),
+            )
+        );
+        assert_eq!(
+            parse_expression("a in b instanceof c >>> 1 % 2"),
+            binary(
+                "instanceof",
+                binary("in", identifier("a"), identifier("b")),
+                binary(
+                    ">>>",
+                    identifier("c"),
+                    binary(
+                        "%",
+                        AstNode::NumberLiteral(1.0),
+                        AstNode::NumberLiteral(2.0)
+                    ),
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn test_logical_and_coalesce() {
+        assert_eq!(
+            parse_expression("a ?? b ?? c"),
+            logical(
+                "??",
+                logical("??", identifier("a"), identifier("b")),
+                identifier("c")
+            )
+        );
+        assert_eq!(
+            parse_expression("(a || b) ?? c"),
+            logical(
+                "??",
+                logical("||", identifier("a"), identifier("b")),
+                identifier("c")
+            )
+        );
+        assert_eq!(
+            parse_expression("a ?? (b && c)"),
+            logical(
+                "??",
+                identifier("a"),
+                logical("&&", identifier("b"), identifier("c"))
+            )
+        );
+        for input in [
+            "a ?? b || c",
+            "a || b ?? c",
+            "a ?? b && c",
+            "a && b ?? c",
+            "a ?? b || c && d",
+        ] {
+            let errors = parse_errors(input);
+            assert_eq!(errors.len(), 1, "{}", input);
+            assert_eq!(errors[0].code, ErrorCode::MixedCoalesce);
+            assert!(matches!(
+                errors[0].found,
+                Token::QuestionQuestion | Token::PipePipe | Token::AmpersandAmpersand
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sequence_and_compound_assignment() {
+        assert_eq!(
+            parse_expression("a ??= b, c >>>= 1, d"),
+            AstNode::SequenceExpression {
+                expressions: vec![
+                    assignment("??=", identifier("a"), identifier("b")),
+                    assignment(">>>=", identifier("c"), AstNode::NumberLiteral(1.0)),
+                    identifier("d"),
+                ],
+            }
+        );
+        assert_eq!(
+            parse_expression("f((a, b), c)"),
+            AstNode::CallExpression {
+                type_arguments: None,
+                callee: Box::new(identifier("f")),
+                arguments: vec![
+                    AstNode::SequenceExpression {
+                        expressions: vec![identifier("a"), identifier("b")],
+                    },
+                    identifier("c"),
+                ],
+                optional: false,
+            }
+        );
+    }
+
+    fn unary(operator: &str, argument: AstNode) -> AstNode {
+        AstNode::UnaryExpression {
+            operator: operator.to_string(),
+            argument: Box::new(argument),
+        }
+    }
+
+    fn update(operator: &str, prefix: bool, argument: AstNode) -> AstNode {
+        AstNode::UpdateExpression {
+            operator: operator.to_string(),
+            prefix,
+            argument: Box::new(argument),
+        }
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        assert_eq!(
+            parse_expression("!a && -b + ~c"),
+            logical(
+                "&&",
+                unary("!", identifier("a")),
+                binary(
+                    "+",
+                    unary("-", identifier("b")),
+                    unary("~", identifier("c"))
+                ),
+            )
+        );
+        assert_eq!(
+            parse_expression("typeof void delete x"),
+            unary("typeof", unary("void", unary("delete", identifier("x"))))
+        );
+        assert_eq!(
+            parse_expression("void 0 === +1"),
+            binary(
+                "===",
+                unary("void", AstNode::NumberLiteral(0.0)),
+                unary("+", AstNode::NumberLiteral(1.0)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_expressions() {
+        assert_eq!(
+            parse_expression("++i + i--"),
+            binary(
+                "+",
+                update("++", true, identifier("i")),
+                update("--", false, identifier("i"))
+            )
+        );
+        assert_eq!(
+            parse_expression("- --i"),
+            unary("-", update("--", true, identifier("i")))
+        );
+        // A line break before `++` ends the statement instead.
+        let ast = Parser::new(lexer::tokenize("a\n++b")).parse().unwrap();
+        assert_eq!(
+            ast,
+            AstNode::Program {
+                body: vec![
+                    AstNode::ExpressionStatement {
+                        expression: Box::new(identifier("a")),
+                        directive: None,
+                    },
+                    AstNode::ExpressionStatement {
+                        expression: Box::new(update("++", true, identifier("b"))),
+                        directive: None,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_update_targets() {
+        for (input, span) in [
+            ("++f();", Span::new(2, 3)),
+            ("(a + b)--;", Span::new(0, 1)),
+            ("++1;", Span::new(2, 3)),
+        ] {
+            let errors = parse_errors(input);
+            assert_eq!(errors.len(), 1, "{}", input);
+            assert_eq!(errors[0].code, ErrorCode::InvalidUpdateTarget);
+            assert_eq!(errors[0].span, span);
+        }
+    }
+
+    #[test]
+    fn test_strict_delete_identifier() {
+        assert!(Parser::new(lexer::tokenize("delete x;")).parse().is_ok());
+
+        let mut parser = Parser::new(lexer::tokenize("delete x; delete (y);"));
+        parser.strict = true;
+        let errors = parser.parse().unwrap_err();
+        let codes: Vec<ErrorCode> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(
+            codes,
+            vec![ErrorCode::StrictDelete, ErrorCode::StrictDelete]
+        );
+        assert_eq!(errors[0].span, Span::new(7, 8));
+    }
+
+    #[test]
+    fn test_unary_before_exponent() {
+        let errors = parse_errors("-a ** 2;");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::UnaryBeforeExponent);
+        assert_eq!(errors[0].span, Span::new(0, 1));
+
+        assert_eq!(
+            parse_expression("(-a) ** 2"),
+            binary(
+                "**",
+                unary("-", identifier("a")),
+                AstNode::NumberLiteral(2.0)
+            )
+        );
+        assert_eq!(
+            parse_expression("2 ** -a"),
+            binary(
+                "**",
+                AstNode::NumberLiteral(2.0),
+                unary("-", identifier("a"))
+            )
+        );
+        assert_eq!(
+            parse_expression("++a ** 2"),
+            binary(
+                "**",
+                update("++", true, identifier("a")),
+                AstNode::NumberLiteral(2.0)
+            )
+        );
+    }
+
+    #[test]
+    fn test_await() {
+        assert_eq!(
+            parse_expression("await(x)"),
+            AstNode::CallExpression {
+                type_arguments: None,
+                callee: Box::new(identifier("await")),
+                arguments: vec![identifier("x")],
+                optional: false,
+            }
+        );
+
+        let mut parser = Parser::new(lexer::tokenize("await a + await b;"));
+        parser.in_async = true;
+        let await_expression = |name| AstNode::AwaitExpression {
+            argument: Box::new(identifier(name)),
+        };
+        assert_eq!(
+            parser.parse().unwrap(),
+            AstNode::Program {
+                body: vec![AstNode::ExpressionStatement {
+                    expression: Box::new(binary("+", await_expression("a"), await_expression("b"))),
+                    directive: None,
+                }],
+            }
+        );
+    }
+
+    fn member(object: AstNode, property: &str, optional: bool) -> AstNode {
+        AstNode::MemberExpression {
+            object: Box::new(object),
+            property: Box::new(identifier(property)),
+            computed: false,
+            optional,
+        }
+    }
+
+    fn call(callee: AstNode, arguments: Vec<AstNode>, optional: bool) -> AstNode {
+        AstNode::CallExpression {
+            type_arguments: None,
+            callee: Box::new(callee),
+            arguments,
+            optional,
+        }
+    }
+
+    fn chain(expression: AstNode) -> AstNode {
+        AstNode::ChainExpression {
+            expression: Box::new(expression),
+        }
+    }
+
+    #[test]
+    fn test_member_expressions() {
+        assert_eq!(
+            parse_expression("a.b[c + 1].new(x, y)"),
+            call(
+                member(
+                    AstNode::MemberExpression {
+                        object: Box::new(member(identifier("a"), "b", false)),
+                        property: Box::new(binary(
+                            "+",
+                            identifier("c"),
+                            AstNode::NumberLiteral(1.0)
+                        )),
+                        computed: true,
+                        optional: false,
+                    },
+                    "new",
+                    false,
+                ),
+                vec![identifier("x"), identifier("y")],
+                false,
+            )
+        );
+        assert_eq!(
+            parse_expression("this.x++"),
+            update("++", false, member(AstNode::ThisExpression, "x", false))
+        );
+        assert_eq!(
+            parse_expression("f()()"),
+            call(call(identifier("f"), vec![], false), vec![], false)
+        );
+    }
+
+    #[test]
+    fn test_optional_chaining() {
+        assert_eq!(
+            parse_expression("a?.b.c"),
+            chain(member(member(identifier("a"), "b", true), "c", false))
+        );
+        assert_eq!(
+            parse_expression("a?.[b]?.(c)"),
+            chain(call(
+                AstNode::MemberExpression {
+                    object: Box::new(identifier("a")),
+                    property: Box::new(identifier("b")),
+                    computed: true,
+                    optional: true,
+                },
+                vec![identifier("c")],
+                true,
+            ))
+        );
+        // Parentheses end the chain.
+        assert_eq!(
+            parse_expression("(a?.b).c"),
+            member(chain(member(identifier("a"), "b", true)), "c", false)
+        );
+        assert_eq!(
+            parse_expression("x ? .5 : y"),
+            AstNode::TernaryExpression {
+                left: Box::new(identifier("x")),
+                middle: Box::new(AstNode::NumberLiteral(0.5)),
+                right: Box::new(identifier("y")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_new_expressions() {
+        let new = |callee, arguments| AstNode::NewExpression {
+            type_arguments: None,
+            callee: Box::new(callee),
+            arguments,
+        };
+        assert_eq!(parse_expression("new Foo"), new(identifier("Foo"), vec![]));
+        assert_eq!(
+            parse_expression("new Foo(x).bar"),
+            member(new(identifier("Foo"), vec![identifier("x")]), "bar", false)
+        );
+        assert_eq!(
+            parse_expression("new a.b.C()"),
+            new(
+                member(member(identifier("a"), "b", false), "C", false),
+                vec![]
+            )
+        );
+        assert_eq!(
+            parse_expression("new new Foo()()"),
+            new(new(identifier("Foo"), vec![]), vec![])
+        );
+        assert_eq!(
+            parse_expression("new Foo()()"),
+            call(new(identifier("Foo"), vec![]), vec![], false)
+        );
+        assert_eq!(
+            parse_expression("new.target"),
+            AstNode::MetaProperty {
+                meta: Box::new(identifier("new")),
+                property: Box::new(identifier("target")),
+            }
+        );
+        assert_eq!(
+            parse_errors("new a?.b();")[0].code,
+            ErrorCode::InvalidOptionalChain
+        );
+        assert_eq!(parse_errors("new.foo;")[0].expected, "'target'");
+    }
+
+    fn property(key: AstNode, value: AstNode, kind: &str) -> AstNode {
+        AstNode::Property {
+            key: Box::new(key),
+            value: Box::new(value),
+            kind: kind.to_string(),
+            computed: false,
+            shorthand: false,
+            method: false,
+        }
+    }
+
+    fn spread(argument: AstNode) -> AstNode {
+        AstNode::SpreadElement {
+            argument: Box::new(argument),
+        }
+    }
+
+    fn empty_function(params: Vec<AstNode>) -> AstNode {
+        AstNode::FunctionExpression {
+            return_type: None,
+            type_parameters: None,
+            id: None,
+            params,
+            body: Box::new(AstNode::BlockStatement { body: vec![] }),
+            generator: false,
+            r#async: false,
+        }
+    }
+
+    #[test]
+    fn test_array_literals() {
+        assert_eq!(
+            parse_expression("[1, , ...xs]"),
+            AstNode::ArrayExpression {
+                elements: vec![
+                    Some(AstNode::NumberLiteral(1.0)),
+                    None,
+                    Some(spread(identifier("xs"))),
+                ],
+            }
+        );
+        assert_eq!(
+            parse_expression("[a,]"),
+            AstNode::ArrayExpression {
+                elements: vec![Some(identifier("a"))],
+            }
+        );
+        assert_eq!(
+            parse_expression("[, ,]"),
+            AstNode::ArrayExpression {
+                elements: vec![None, None],
+            }
+        );
+        assert_eq!(
+            parse_expression("f(...args, b,)"),
+            call(
+                identifier("f"),
+                vec![spread(identifier("args")), identifier("b")],
+                false
+            )
+        );
+        assert_eq!(parse_errors("[a b];")[0].expected, "','");
+    }
+
+    #[test]
+    fn test_object_literals() {
+        let computed = AstNode::Property {
+            key: Box::new(identifier("k")),
+            value: Box::new(identifier("v")),
+            kind: "init".to_string(),
+            computed: true,
+            shorthand: false,
+            method: false,
+        };
+        let shorthand = AstNode::Property {
+            key: Box::new(identifier("b")),
+            value: Box::new(identifier("b")),
+            kind: "init".to_string(),
+            computed: false,
+            shorthand: true,
+            method: false,
+        };
+        let method = AstNode::Property {
+            key: Box::new(identifier("m")),
+            value: Box::new(empty_function(vec![])),
+            kind: "init".to_string(),
+            computed: false,
+            shorthand: false,
+            method: true,
+        };
+        assert_eq!(
+            parse_expression("({a: 1, b, [k]: v, ...rest, m() {}, get x() {}, set x(v) {},})"),
+            AstNode::ObjectExpression {
+                properties: vec![
+                    property(identifier("a"), AstNode::NumberLiteral(1.0), "init"),
+                    shorthand,
+                    computed,
+                    spread(identifier("rest")),
+                    method,
+                    property(identifier("x"), empty_function(vec![]), "get"),
+                    property(
+                        identifier("x"),
+                        empty_function(vec![identifier("v")]),
+                        "set"
+                    ),
+                ],
+            }
+        );
+        assert_eq!(
+            parse_expression("({'a': 1, 2: b, if: c, get: d})"),
+            AstNode::ObjectExpression {
+                properties: vec![
+                    property(
+                        AstNode::StringLiteral("a".to_string()),
+                        AstNode::NumberLiteral(1.0),
+                        "init"
+                    ),
+                    property(AstNode::NumberLiteral(2.0), identifier("b"), "init"),
+                    property(identifier("if"), identifier("c"), "init"),
+                    property(identifier("get"), identifier("d"), "init"),
+                ],
+            }
+        );
+        // At the start of a statement `{` opens a block.
+        assert!(matches!(
+            Parser::new(lexer::tokenize("{}")).parse().unwrap(),
+            AstNode::Program { body } if body == vec![AstNode::BlockStatement { body: vec![] }]
+        ));
+    }
+
+    #[test]
+    fn test_object_literal_errors() {
+        assert_eq!(parse_errors("({if});")[0].expected, "':'");
+        assert_eq!(parse_errors("({'a'});")[0].expected, "':'");
+        let errors = parse_errors("({get x(a) {}, set y() {}});");
+        assert_eq!(
+            errors.iter().map(|error| error.code).collect::<Vec<_>>(),
+            vec![ErrorCode::InvalidAccessorParameters; 2]
+        );
+        assert_eq!(
+            errors[0].message,
+            "A 'get' accessor must have no parameters."
+        );
+        assert_eq!(errors[1].span, Span::new(19, 20));
+    }
+
+    fn parse_with(options: &ParseOptions, input: &str) -> Result<AstNode, Vec<ParseError>> {
+        let tokens = lexer::tokenize_with_options(input, options);
+        Parser::with_options(tokens, options.clone()).parse()
+    }
+
+    fn first_statement(input: &str) -> AstNode {
+        first_statement_with(&ParseOptions::default(), input)
+    }
+
+    fn first_statement_with(options: &ParseOptions, input: &str) -> AstNode {
+        match parse_with(options, input).unwrap() {
+            AstNode::Program { mut body } => body.remove(0),
+            _ => unreachable!(),
+        }
+    }
+
+    fn statement(expression: AstNode) -> AstNode {
+        AstNode::ExpressionStatement {
+            expression: Box::new(expression),
+            directive: None,
+        }
+    }
+
+    fn block(body: Vec<AstNode>) -> AstNode {
+        AstNode::BlockStatement { body }
+    }
+
+    #[test]
+    fn test_while_and_do_while() {
+        assert_eq!(
+            first_statement("while (a) b;"),
+            AstNode::WhileStatement {
+                test: Box::new(identifier("a")),
+                body: Box::new(statement(identifier("b"))),
+            }
+        );
+        // The semicolon after `do ... while (test)` is optional even on the
+        // same line.
+        let program = Parser::new(lexer::tokenize("do {} while (a) b"))
+            .parse()
+            .unwrap();
+        assert_eq!(
+            program,
+            AstNode::Program {
+                body: vec![
+                    AstNode::DoWhileStatement {
+                        body: Box::new(block(vec![])),
+                        test: Box::new(identifier("a")),
+                    },
+                    statement(identifier("b")),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_for_statements() {
+        assert_eq!(
+            first_statement("for (;;) {}"),
+            AstNode::ForStatement {
+                init: None,
+                test: None,
+                update: None,
+                body: Box::new(block(vec![])),
+            }
+        );
+        assert_eq!(
+            first_statement("for (i = 0; i < n; i++) ;"),
+            AstNode::ForStatement {
+                init: Some(Box::new(assignment(
+                    "=",
+                    identifier("i"),
+                    AstNode::NumberLiteral(0.0)
+                ))),
+                test: Some(Box::new(binary("<", identifier("i"), identifier("n")))),
+                update: Some(Box::new(update("++", false, identifier("i")))),
+                body: Box::new(AstNode::EmptyStatement),
+            }
+        );
+        assert_eq!(
+            first_statement("for (k in o) ;"),
+            AstNode::ForInStatement {
+                left: Box::new(identifier("k")),
+                right: Box::new(identifier("o")),
+                body: Box::new(AstNode::EmptyStatement),
+            }
+        );
+        assert_eq!(
+            first_statement("for (a.b of [x in y]) ;"),
+            AstNode::ForOfStatement {
+                left: Box::new(member(identifier("a"), "b", false)),
+                right: Box::new(AstNode::ArrayExpression {
+                    elements: vec![Some(binary("in", identifier("x"), identifier("y")))],
+                }),
+                body: Box::new(AstNode::EmptyStatement),
+                r#await: false,
+            }
+        );
+        // `in` is allowed again inside parentheses in the initialiser.
+        assert!(matches!(
+            first_statement("for (var x = (a in b); x;) ;"),
+            AstNode::ForStatement { .. }
+        ));
+
+        let mut parser = Parser::new(lexer::tokenize("for await (x of xs) ;"));
+        parser.in_async = true;
+        assert!(matches!(
+            parser.parse().unwrap(),
+            AstNode::Program { body } if matches!(body[0], AstNode::ForOfStatement { r#await: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_for_statement_errors() {
+        assert_eq!(
+            parse_errors("for (a + b in c) ;")[0].code,
+            ErrorCode::InvalidAssignmentTarget
+        );
+        assert_eq!(
+            parse_errors("for await (x of xs) ;")[0].message,
+            "'for await' is only valid in async functions."
+        );
+        assert_eq!(parse_errors("for (a; b) ;")[0].expected, "';'");
+        assert_eq!(
+            parse_errors("for (async of x) ;")[0].message,
+            "The left-hand side of a for-of loop may not be 'async'."
+        );
+        let mut parser = Parser::new(lexer::tokenize(
+            "for ((async) of x) ; for (async.a of x) ; for (async in x) ; for await (async of x) ;",
+        ));
+        parser.in_async = true;
+        assert!(parser.parse().is_ok());
+    }
+
+    #[test]
+    fn test_switch_statement() {
+        assert_eq!(
+            first_statement("switch (x) { case 1: a; b; default: case 2: }"),
+            AstNode::SwitchStatement {
+                discriminant: Box::new(identifier("x")),
+                cases: vec![
+                    AstNode::SwitchCase {
+                        test: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                        consequent: vec![statement(identifier("a")), statement(identifier("b"))],
+                    },
+                    AstNode::SwitchCase {
+                        test: None,
+                        consequent: vec![],
+                    },
+                    AstNode::SwitchCase {
+                        test: Some(Box::new(AstNode::NumberLiteral(2.0))),
+                        consequent: vec![],
+                    },
+                ],
+            }
+        );
+        assert_eq!(
+            parse_errors("switch (x) { default: default: }")[0].code,
+            ErrorCode::DuplicateDefault
+        );
+    }
+
+    #[test]
+    fn test_try_and_throw() {
+        assert_eq!(
+            first_statement("try { a } catch (e) { b } finally { c }"),
+            AstNode::TryStatement {
+                block: Box::new(block(vec![statement(identifier("a"))])),
+                handler: Some(Box::new(AstNode::CatchClause {
+                    param: Some(Box::new(identifier("e"))),
+                    body: Box::new(block(vec![statement(identifier("b"))])),
+                })),
+                finalizer: Some(Box::new(block(vec![statement(identifier("c"))]))),
+            }
+        );
+        assert_eq!(
+            first_statement("try {} catch {}"),
+            AstNode::TryStatement {
+                block: Box::new(block(vec![])),
+                handler: Some(Box::new(AstNode::CatchClause {
+                    param: None,
+                    body: Box::new(block(vec![])),
+                })),
+                finalizer: None,
+            }
+        );
+        assert_eq!(
+            first_statement("throw new Error()"),
+            AstNode::ThrowStatement {
+                argument: Box::new(AstNode::NewExpression {
+                    type_arguments: None,
+                    callee: Box::new(identifier("Error")),
+                    arguments: vec![],
+                }),
+            }
+        );
+        assert_eq!(parse_errors("try {}")[0].expected, "'catch' or 'finally'");
+        assert_eq!(
+            parse_errors("throw\nx;")[0].code,
+            ErrorCode::NewlineAfterThrow
+        );
+    }
+
+    #[test]
+    fn test_labels_and_jumps() {
+        let label = |name: &str| Some(Box::new(identifier(name)));
+        assert_eq!(
+            first_statement("outer: for (;;) { continue outer; break; }"),
+            AstNode::LabeledStatement {
+                label: Box::new(identifier("outer")),
+                body: Box::new(AstNode::ForStatement {
+                    init: None,
+                    test: None,
+                    update: None,
+                    body: Box::new(block(vec![
+                        AstNode::ContinueStatement {
+                            label: label("outer"),
+                        },
+                        AstNode::BreakStatement { label: None },
+                    ])),
+                }),
+            }
+        );
+        // A label on the next line is a separate expression statement.
+        let program = Parser::new(lexer::tokenize("while (a) break\nb"))
+            .parse()
+            .unwrap();
+        assert_eq!(
+            program,
+            AstNode::Program {
+                body: vec![
+                    AstNode::WhileStatement {
+                        test: Box::new(identifier("a")),
+                        body: Box::new(AstNode::BreakStatement { label: None }),
+                    },
+                    statement(identifier("b")),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_declarations_in_statement_positions() {
+        let function = |name: &str| AstNode::FunctionDeclaration {
+            id: Box::new(identifier(name)),
+            params: vec![],
+            body: Box::new(block(vec![])),
+            generator: false,
+            r#async: false,
+            type_parameters: None,
+            return_type: None,
+        };
+        assert_eq!(
+            first_statement("label: function f() {}"),
+            AstNode::LabeledStatement {
+                label: Box::new(identifier("label")),
+                body: Box::new(function("f")),
+            }
+        );
+        assert_eq!(
+            first_statement("if (a) function f() {} else function g() {}"),
+            AstNode::IfStatement {
+                test: Box::new(identifier("a")),
+                consequent: Box::new(function("f")),
+                alternate: Some(Box::new(function("g"))),
+            }
+        );
+        for source in [
+            "'use strict'; if (a) function f() {}",
+            "'use strict'; label: function f() {}",
+            "label: function* g() {}",
+            "label: async function f() {}",
+            "while (a) function f() {}",
+            "if (a) class B {}",
+            "do let [x] = y; while (a);",
+        ] {
+            let errors = parse_errors(source);
+            assert_eq!(errors.len(), 1, "{}", source);
+            assert_eq!(errors[0].code, ErrorCode::UnexpectedToken);
+        }
+        // Declarations are still parsed as such.
+        let (program, _) = Parser::new(lexer::tokenize("while (a) class B {}")).parse_recovering();
+        assert!(matches!(
+            program,
+            AstNode::Program { body } if matches!(
+                &body[0],
+                AstNode::WhileStatement { body, .. } if matches!(**body, AstNode::ClassDeclaration { .. })
+            )
+        ));
+        // `let` followed by anything but `[` is still an identifier.
+        assert!(Parser::new(lexer::tokenize("if (a) let\nx = 1;"))
+            .parse()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_with_debugger_and_empty_statements() {
+        assert_eq!(
+            Parser::new(lexer::tokenize("with (o) x; debugger; ;"))
+                .parse()
+                .unwrap(),
+            AstNode::Program {
+                body: vec![
+                    AstNode::WithStatement {
+                        object: Box::new(identifier("o")),
+                        body: Box::new(statement(identifier("x"))),
+                    },
+                    AstNode::DebuggerStatement,
+                    AstNode::EmptyStatement,
+                ],
+            }
+        );
+        let mut parser = Parser::new(lexer::tokenize("with (o) x;"));
+        parser.strict = true;
+        assert_eq!(parser.parse().unwrap_err()[0].code, ErrorCode::StrictWith);
+    }
+
+    fn declarator(name: &str, init: Option<AstNode>) -> AstNode {
+        AstNode::VariableDeclarator {
+            definite: false,
+            id: Box::new(identifier(name)),
+            init: init.map(Box::new),
+        }
+    }
+
+    #[test]
+    fn test_variable_declarations() {
+        assert_eq!(
+            first_statement("var a, b = 1;"),
+            AstNode::VariableDeclaration {
+                declare: false,
+                kind: "var".to_string(),
+                declarations: vec![
+                    declarator("a", None),
+                    declarator("b", Some(AstNode::NumberLiteral(1.0))),
+                ],
+            }
+        );
+        assert_eq!(
+            first_statement("const c = 1, d = c"),
+            AstNode::VariableDeclaration {
+                declare: false,
+                kind: "const".to_string(),
+                declarations: vec![
+                    declarator("c", Some(AstNode::NumberLiteral(1.0))),
+                    declarator("d", Some(identifier("c"))),
+                ],
+            }
+        );
+        // Without a binding after it `let` is an ordinary identifier.
+        assert_eq!(
+            first_statement("let = 1;"),
+            statement(assignment(
+                "=",
+                identifier("let"),
+                AstNode::NumberLiteral(1.0)
+            ))
+        );
+        assert_eq!(
+            first_statement("for (const x of xs) ;"),
+            AstNode::ForOfStatement {
+                left: Box::new(AstNode::VariableDeclaration {
+                    declare: false,
+                    kind: "const".to_string(),
+                    declarations: vec![declarator("x", None)],
+                }),
+                right: Box::new(identifier("xs")),
+                body: Box::new(AstNode::EmptyStatement),
+                r#await: false,
+            }
+        );
+        assert!(matches!(
+            first_statement("for (let i = 0, n = a.length; i < n; i++) ;"),
+            AstNode::ForStatement { init: Some(init), .. }
+                if matches!(&*init, AstNode::VariableDeclaration { declarations, .. } if declarations.len() == 2)
+        ));
+    }
+
+    #[test]
+    fn test_variable_declaration_errors() {
+        let errors = parse_errors("const x;");
+        assert_eq!(errors[0].code, ErrorCode::MissingInitializer);
+        assert_eq!(errors[0].span, Span::new(7, 8));
+        assert_eq!(parse_errors("let let = 1;")[0].code, ErrorCode::LetBinding);
+        assert_eq!(
+            parse_errors("const let = 1;")[0].code,
+            ErrorCode::LetBinding
+        );
+        assert_eq!(
+            parse_errors("for (let a, b of c) ;")[0].code,
+            ErrorCode::InvalidForDeclaration
+        );
+        assert_eq!(
+            parse_errors("for (const a = 1 in c) ;")[0].code,
+            ErrorCode::InvalidForDeclaration
+        );
+    }
+
+    fn pattern_property(key: &str, value: AstNode, shorthand: bool) -> AstNode {
+        AstNode::Property {
+            key: Box::new(identifier(key)),
+            value: Box::new(value),
+            kind: "init".to_string(),
+            computed: false,
+            shorthand,
+            method: false,
+        }
+    }
+
+    fn default_value(left: AstNode, right: AstNode) -> AstNode {
+        AstNode::AssignmentPattern {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn rest(argument: AstNode) -> AstNode {
+        AstNode::RestElement {
+            type_annotation: None,
+            argument: Box::new(argument),
+        }
+    }
+
+    #[test]
+    fn test_declaration_patterns() {
+        let pattern = AstNode::ObjectPattern {
+            optional: false,
+            type_annotation: None,
+            properties: vec![
+                pattern_property("a", identifier("a"), true),
+                pattern_property(
+                    "b",
+                    AstNode::ObjectPattern {
+                        optional: false,
+                        type_annotation: None,
+                        properties: vec![pattern_property(
+                            "c",
+                            default_value(identifier("c"), AstNode::NumberLiteral(1.0)),
+                            true,
+                        )],
+                    },
+                    false,
+                ),
+                rest(identifier("rest")),
+            ],
+        };
+        assert_eq!(
+            first_statement("const {a, b: {c = 1}, ...rest} = obj;"),
+            AstNode::VariableDeclaration {
+                declare: false,
+                kind: "const".to_string(),
+                declarations: vec![declarator_with(pattern, Some(identifier("obj")))],
+            }
+        );
+        assert_eq!(
+            first_statement("let [x, , [y] = z, ...more] = arr;"),
+            AstNode::VariableDeclaration {
+                declare: false,
+                kind: "let".to_string(),
+                declarations: vec![declarator_with(
+                    AstNode::ArrayPattern {
+                        optional: false,
+                        type_annotation: None,
+                        elements: vec![
+                            Some(identifier("x")),
+                            None,
+                            Some(default_value(
+                                AstNode::ArrayPattern {
+                                    optional: false,
+                                    type_annotation: None,
+                                    elements: vec![Some(identifier("y"))],
+                                },
+                                identifier("z"),
+                            )),
+                            Some(rest(identifier("more"))),
+                        ],
+                    },
+                    Some(identifier("arr")),
+                )],
+            }
+        );
+    }
+
+    fn declarator_with(id: AstNode, init: Option<AstNode>) -> AstNode {
+        AstNode::VariableDeclarator {
+            definite: false,
+            id: Box::new(id),
+            init: init.map(Box::new),
+        }
+    }
+
+    #[test]
+    fn test_parameter_and_catch_patterns() {
+        assert_eq!(
+            first_statement("function f({a}, [b] = [], c = 1, ...d) {}"),
+            AstNode::FunctionDeclaration {
+                return_type: None,
+                type_parameters: None,
+                id: Box::new(identifier("f")),
+                params: vec![
+                    AstNode::ObjectPattern {
+                        optional: false,
+                        type_annotation: None,
+                        properties: vec![pattern_property("a", identifier("a"), true)],
+                    },
+                    default_value(
+                        AstNode::ArrayPattern {
+                            optional: false,
+                            type_annotation: None,
+                            elements: vec![Some(identifier("b"))],
+                        },
+                        AstNode::ArrayExpression { elements: vec![] },
+                    ),
+                    default_value(identifier("c"), AstNode::NumberLiteral(1.0)),
+                    rest(identifier("d")),
+                ],
+                body: Box::new(block(vec![])),
+                generator: false,
+                r#async: false,
+            }
+        );
+        assert!(matches!(
+            first_statement("try {} catch ({message}) {}"),
+            AstNode::TryStatement { handler: Some(handler), .. }
+                if matches!(&*handler, AstNode::CatchClause { param: Some(param), .. }
+                    if matches!(**param, AstNode::ObjectPattern { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_assignment_patterns() {
+        assert_eq!(
+            parse_expression("[x, , y = 2] = arr"),
+            assignment(
+                "=",
+                AstNode::ArrayPattern {
+                    optional: false,
+                    type_annotation: None,
+                    elements: vec![
+                        Some(identifier("x")),
+                        None,
+                        Some(default_value(identifier("y"), AstNode::NumberLiteral(2.0))),
+                    ],
+                },
+                identifier("arr"),
+            )
+        );
+        assert_eq!(
+            parse_expression("({a = 1, b: [c.d], ...e} = obj)"),
+            assignment(
+                "=",
+                AstNode::ObjectPattern {
+                    optional: false,
+                    type_annotation: None,
+                    properties: vec![
+                        pattern_property(
+                            "a",
+                            default_value(identifier("a"), AstNode::NumberLiteral(1.0)),
+                            true,
+                        ),
+                        pattern_property(
+                            "b",
+                            AstNode::ArrayPattern {
+                                optional: false,
+                                type_annotation: None,
+                                elements: vec![Some(member(identifier("c"), "d", false))],
+                            },
+                            false,
+                        ),
+                        rest(identifier("e")),
+                    ],
+                },
+                identifier("obj"),
+            )
+        );
+        assert_eq!(
+            first_statement("for ([k, v] of entries) ;"),
+            AstNode::ForOfStatement {
+                left: Box::new(AstNode::ArrayPattern {
+                    optional: false,
+                    type_annotation: None,
+                    elements: vec![Some(identifier("k")), Some(identifier("v"))],
+                }),
+                right: Box::new(identifier("entries")),
+                body: Box::new(AstNode::EmptyStatement),
+                r#await: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pattern_errors() {
+        for input in [
+            "1 = 2;",
+            "a + b = c;",
+            "[a + 1] = c;",
+            "[...a, b] = c;",
+            "({...{a}} = c);",
+            "({m() {}} = c);",
+            "[a] += 1;",
+            "({a}) = 1;",
+            "([a]) = 1;",
+            "(([a])) = 1;",
+            "[({a})] = 1;",
+            "[(([a])) = 1] = c;",
+            "({ x: ([a]) } = c);",
+            "[...([a])] = c;",
+            "[a, ...b,] = c;",
+            "({...a,} = c);",
+            "[[a, ...b,]] = c;",
+            "for (([a]) of c) ;",
+        ] {
+            assert_eq!(
+                parse_errors(input)[0].code,
+                ErrorCode::InvalidAssignmentTarget,
+                "{}",
+                input
+            );
+        }
+        let errors = parse_errors("f({a = 1});");
+        assert_eq!(errors[0].code, ErrorCode::ShorthandInitializer);
+        assert_eq!(errors[0].span, Span::new(5, 6));
+        assert_eq!(
+            parse_errors("x = {a = 1};")[0].code,
+            ErrorCode::ShorthandInitializer
+        );
+        assert_eq!(
+            parse_errors("let {a};")[0].message,
+            "Missing initializer in destructuring declaration."
+        );
+        assert_eq!(parse_errors("let [...a, b] = c;")[0].expected, "']'");
+        // Parenthesised names and properties, and literals used as values,
+        // are still fine.
+        assert!(Parser::new(lexer::tokenize(
+            "[(a), (b.c) = 1, ([d]).e, ...(f)] = g; ({ x: (y), z: ({w}).v, [({u}).t]: s } = r); [a = ([b])] = c;"
+        ))
+        .parse()
+        .is_ok());
+    }
+
+    fn arrow(params: Vec<AstNode>, body: AstNode, is_async: bool) -> AstNode {
+        AstNode::ArrowFunctionExpression {
+            return_type: None,
+            type_parameters: None,
+            expression: !matches!(body, AstNode::BlockStatement { .. }),
+            params,
+            body: Box::new(body),
+            r#async: is_async,
+        }
+    }
+
+    #[test]
+    fn test_arrow_functions() {
+        assert_eq!(
+            parse_expression("(a, b) => a + b"),
+            arrow(
+                vec![identifier("a"), identifier("b")],
+                binary("+", identifier("a"), identifier("b")),
+                false
+            )
+        );
+        assert_eq!(
+            parse_expression("x => y => x"),
+            arrow(
+                vec![identifier("x")],
+                arrow(vec![identifier("y")], identifier("x"), false),
+                false
+            )
+        );
+        assert_eq!(
+            parse_expression("() => {}"),
+            arrow(vec![], block(vec![]), false)
+        );
+        assert_eq!(
+            parse_expression("({a}, [b] = [], ...c) => a"),
+            arrow(
+                vec![
+                    AstNode::ObjectPattern {
+                        optional: false,
+                        type_annotation: None,
+                        properties: vec![pattern_property("a", identifier("a"), true)],
+                    },
+                    default_value(
+                        AstNode::ArrayPattern {
+                            optional: false,
+                            type_annotation: None,
+                            elements: vec![Some(identifier("b"))],
+                        },
+                        AstNode::ArrayExpression { elements: vec![] },
+                    ),
+                    rest(identifier("c")),
+                ],
+                identifier("a"),
+                false
+            )
+        );
+        // Parenthesised expressions are still expressions.
+        assert_eq!(
+            parse_expression("(a, b) + c"),
+            binary(
+                "+",
+                AstNode::SequenceExpression {
+                    expressions: vec![identifier("a"), identifier("b")],
+                },
+                identifier("c")
+            )
+        );
+        assert_eq!(
+            parse_expression("f(x => x, (y))"),
+            call(
+                identifier("f"),
+                vec![
+                    arrow(vec![identifier("x")], identifier("x"), false),
+                    identifier("y")
+                ],
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn test_async_arrow_functions() {
+        let await_x = AstNode::AwaitExpression {
+            argument: Box::new(identifier("x")),
+        };
+        assert_eq!(
+            parse_expression("async x => await x"),
+            arrow(vec![identifier("x")], await_x.clone(), true)
+        );
+        assert_eq!(
+            parse_expression("async (x) => { await x }"),
+            arrow(vec![identifier("x")], block(vec![statement(await_x)]), true)
+        );
+        // `async` alone is a parameter name or a function being called.
+        assert_eq!(
+            parse_expression("async => async"),
+            arrow(vec![identifier("async")], identifier("async"), false)
+        );
+        assert_eq!(
+            parse_expression("async(x)"),
+            call(identifier("async"), vec![identifier("x")], false)
+        );
+    }
+
+    #[test]
+    fn test_arrow_function_errors() {
+        let errors = parse_errors("(a, b)\n=> a;");
+        assert_eq!(errors[0].code, ErrorCode::NewlineBeforeArrow);
+        assert_eq!(errors[0].span, Span::new(7, 9));
+        assert_eq!(parse_errors("(a + b) => a;")[0].expected, "')'");
+        assert_eq!(
+            parse_errors("(a, ...b, c) => a;")[0].message,
+            "Expect ')' after parameters."
+        );
+    }
+
+    fn yield_expression(argument: Option<AstNode>, delegate: bool) -> AstNode {
+        AstNode::YieldExpression {
+            argument: argument.map(Box::new),
+            delegate,
+        }
+    }
+
+    #[test]
+    fn test_function_expressions() {
+        assert_eq!(parse_expression("(function () {})"), empty_function(vec![]));
+        assert_eq!(
+            parse_expression("(function named(a = 1, ...b) {})"),
+            AstNode::FunctionExpression {
+                return_type: None,
+                type_parameters: None,
+                id: Some(Box::new(identifier("named"))),
+                params: vec![
+                    default_value(identifier("a"), AstNode::NumberLiteral(1.0)),
+                    rest(identifier("b")),
+                ],
+                body: Box::new(block(vec![])),
+                generator: false,
+                r#async: false,
+            }
+        );
+        // A function expression can be called immediately.
+        assert!(matches!(
+            parse_expression("(function () {})()"),
+            AstNode::CallExpression { .. }
+        ));
+        assert_eq!(
+            parse_errors("function () {}")[0].code,
+            ErrorCode::ExpectedIdentifier
+        );
+    }
+
+    #[test]
+    fn test_generators() {
+        assert_eq!(
+            first_statement("function* g() { yield; yield a, b; yield* c; }"),
+            AstNode::FunctionDeclaration {
+                return_type: None,
+                type_parameters: None,
+                id: Box::new(identifier("g")),
+                params: vec![],
+                body: Box::new(block(vec![
+                    statement(yield_expression(None, false)),
+                    statement(AstNode::SequenceExpression {
+                        expressions: vec![
+                            yield_expression(Some(identifier("a")), false),
+                            identifier("b"),
+                        ],
+                    }),
+                    statement(yield_expression(Some(identifier("c")), true)),
+                ])),
+                generator: true,
+                r#async: false,
+            }
+        );
+        // Outside generators, and in arrow functions nested in them, `yield`
+        // is an identifier.
+        assert_eq!(parse_expression("yield"), identifier("yield"));
+        assert!(matches!(
+            parse_expression("(function* () { () => yield })"),
+            AstNode::FunctionExpression { body, .. }
+                if *body == block(vec![statement(arrow(vec![], identifier("yield"), false))])
+        ));
+        // A line break ends a `yield` without an argument.
+        assert!(matches!(
+            first_statement("function* g() { yield\na }"),
+            AstNode::FunctionDeclaration { body, .. }
+                if *body == block(vec![
+                    statement(yield_expression(None, false)),
+                    statement(identifier("a")),
+                ])
+        ));
+    }
+
+    #[test]
+    fn test_async_functions() {
+        let await_x = statement(AstNode::AwaitExpression {
+            argument: Box::new(identifier("x")),
+        });
+        assert_eq!(
+            first_statement("async function f() { await x }"),
+            AstNode::FunctionDeclaration {
+                return_type: None,
+                type_parameters: None,
+                id: Box::new(identifier("f")),
+                params: vec![],
+                body: Box::new(block(vec![await_x.clone()])),
+                generator: false,
+                r#async: true,
+            }
+        );
+        assert_eq!(
+            parse_expression("(async function* () { await x })"),
+            AstNode::FunctionExpression {
+                return_type: None,
+                type_parameters: None,
+                id: None,
+                params: vec![],
+                body: Box::new(block(vec![await_x])),
+                generator: true,
+                r#async: true,
+            }
+        );
+        // Nested ordinary functions treat `await` as an identifier again.
+        assert!(
+            parse_errors("async function f() { function g() { await x } }")
+                .iter()
+                .all(|error| error.code == ErrorCode::MissingSemicolon)
+        );
+        // `async` followed by a line break is an expression statement.
+        let program = Parser::new(lexer::tokenize("async\nfunction f() {}"))
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            program,
+            AstNode::Program { body } if body[0] == statement(identifier("async"))
+        ));
+    }
+
+    #[test]
+    fn test_async_and_generator_methods() {
+        let method = |key: &str, generator, is_async| AstNode::Property {
+            key: Box::new(identifier(key)),
+            value: Box::new(AstNode::FunctionExpression {
+                return_type: None,
+                type_parameters: None,
+                id: None,
+                params: vec![],
+                body: Box::new(block(vec![])),
+                generator,
+                r#async: is_async,
+            }),
+            kind: "init".to_string(),
+            computed: false,
+            shorthand: false,
+            method: true,
+        };
+        assert_eq!(
+            parse_expression("({*a() {}, async b() {}, async *c() {}, async() {}})"),
+            AstNode::ObjectExpression {
+                properties: vec![
+                    method("a", true, false),
+                    method("b", false, true),
+                    method("c", true, true),
+                    method("async", false, false),
+                ],
+            }
+        );
+    }
+
+    fn class_method(key: AstNode, kind: &str, is_static: bool, body: Vec<AstNode>) -> AstNode {
+        AstNode::MethodDefinition {
+            accessibility: None,
+            optional: false,
+            r#override: false,
+            key: Box::new(key),
+            value: Box::new(AstNode::FunctionExpression {
+                return_type: None,
+                type_parameters: None,
+                id: None,
+                params: vec![],
+                body: Box::new(block(body)),
+                generator: false,
+                r#async: false,
+            }),
+            kind: kind.to_string(),
+            computed: false,
+            r#static: is_static,
+            decorators: vec![],
+        }
+    }
+
+    fn private(name: &str) -> AstNode {
+        AstNode::PrivateIdentifier(name.to_string())
+    }
+
+    #[test]
+    fn test_class_declarations() {
+        let input = "class A extends B {
+            #x = 1;
+            static count;
+            constructor() { super(); }
+            get x() { return this.#x; }
+            static #create() {}
+            static { A.count = 0; }
+            has(o) { return #x in o; }
+        }";
+        let this_x = AstNode::MemberExpression {
+            object: Box::new(AstNode::ThisExpression),
+            property: Box::new(private("x")),
+            computed: false,
+            optional: false,
+        };
+        let has = AstNode::MethodDefinition {
+            accessibility: None,
+            optional: false,
+            r#override: false,
+            key: Box::new(identifier("has")),
+            value: Box::new(AstNode::FunctionExpression {
+                return_type: None,
+                type_parameters: None,
+                id: None,
+                params: vec![identifier("o")],
+                body: Box::new(block(vec![AstNode::ReturnStatement {
+                    argument: Some(Box::new(binary("in", private("x"), identifier("o")))),
+                }])),
+                generator: false,
+                r#async: false,
+            }),
+            kind: "method".to_string(),
+            computed: false,
+            r#static: false,
+            decorators: vec![],
+        };
+        assert_eq!(
+            first_statement(input),
+            AstNode::ClassDeclaration {
+                super_type_arguments: None,
+                type_parameters: None,
+                r#abstract: false,
+                declare: false,
+                implements: vec![],
+                id: Box::new(identifier("A")),
+                super_class: Some(Box::new(identifier("B"))),
+                body: Box::new(AstNode::ClassBody {
+                    body: vec![
+                        AstNode::PropertyDefinition {
+                            variance: None,
+                            type_annotation: None,
+                            optional: false,
+                            r#override: false,
+                            readonly: false,
+                            accessibility: None,
+                            declare: false,
+                            definite: false,
+                            key: Box::new(private("x")),
+                            value: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                            computed: false,
+                            r#static: false,
+                            decorators: vec![],
+                        },
+                        AstNode::PropertyDefinition {
+                            variance: None,
+                            type_annotation: None,
+                            optional: false,
+                            r#override: false,
+                            readonly: false,
+                            accessibility: None,
+                            declare: false,
+                            definite: false,
+                            key: Box::new(identifier("count")),
+                            value: None,
+                            computed: false,
+                            r#static: true,
+                            decorators: vec![],
+                        },
+                        class_method(
+                            identifier("constructor"),
+                            "constructor",
+                            false,
+                            vec![statement(call(AstNode::Super, vec![], false))],
+                        ),
+                        class_method(
+                            identifier("x"),
+                            "get",
+                            false,
+                            vec![AstNode::ReturnStatement {
+                                argument: Some(Box::new(this_x)),
+                            }],
+                        ),
+                        class_method(private("create"), "method", true, vec![]),
+                        AstNode::StaticBlock {
+                            body: vec![statement(assignment(
+                                "=",
+                                member(identifier("A"), "count", false),
+                                AstNode::NumberLiteral(0.0),
+                            ))],
+                        },
+                        has,
+                    ],
+                }),
+                decorators: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_class_expressions_and_modifiers_as_names() {
+        assert_eq!(
+            parse_expression("(class {})"),
+            AstNode::ClassExpression {
+                implements: vec![],
+                super_type_arguments: None,
+                type_parameters: None,
+                id: None,
+                super_class: None,
+                body: Box::new(AstNode::ClassBody { body: vec![] }),
+                decorators: vec![],
+            }
+        );
+        // `static`, `get` and `async` are member names when nothing follows.
+        assert_eq!(
+            parse_expression("(class C { static() {} get; async = 1 })"),
+            AstNode::ClassExpression {
+                implements: vec![],
+                super_type_arguments: None,
+                type_parameters: None,
+                id: Some(Box::new(identifier("C"))),
+                super_class: None,
+                body: Box::new(AstNode::ClassBody {
+                    body: vec![
+                        class_method(identifier("static"), "method", false, vec![]),
+                        AstNode::PropertyDefinition {
+                            variance: None,
+                            type_annotation: None,
+                            optional: false,
+                            r#override: false,
+                            readonly: false,
+                            accessibility: None,
+                            declare: false,
+                            definite: false,
+                            key: Box::new(identifier("get")),
+                            value: None,
+                            computed: false,
+                            r#static: false,
+                            decorators: vec![],
+                        },
+                        AstNode::PropertyDefinition {
+                            variance: None,
+                            type_annotation: None,
+                            optional: false,
+                            r#override: false,
+                            readonly: false,
+                            accessibility: None,
+                            declare: false,
+                            definite: false,
+                            key: Box::new(identifier("async")),
+                            value: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                            computed: false,
+                            r#static: false,
+                            decorators: vec![],
+                        },
+                    ],
+                }),
+                decorators: vec![],
+            }
+        );
+        // Object literal methods may use `super` properties.
+        assert!(
+            Parser::new(lexer::tokenize("({ m() { return super.m(); } })"))
+                .parse()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_class_errors() {
+        let codes = |input| {
+            parse_errors(input)
+                .into_iter()
+                .map(|error| error.code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            codes("class A { constructor() {} 'constructor'() {} }"),
+            vec![ErrorCode::DuplicateConstructor]
+        );
+        assert_eq!(
+            codes("class A { constructor() { super(); } }"),
+            vec![ErrorCode::InvalidSuper]
+        );
+        assert_eq!(
+            codes("class A extends B { m() { super(); } }"),
+            vec![ErrorCode::InvalidSuper]
+        );
+        assert_eq!(
+            codes("function f() { super.x; }"),
+            vec![ErrorCode::InvalidSuper]
+        );
+        // Arrow functions see the `super` of the method around them.
+        assert!(Parser::new(lexer::tokenize(
+            "class A extends B { constructor() { () => super(); } }"
+        ))
+        .parse()
+        .is_ok());
+
+        let errors = parse_errors("class A { m() { this.#y; } }");
+        assert_eq!(errors[0].code, ErrorCode::UndeclaredPrivateName);
+        assert_eq!(errors[0].span, Span::new(21, 23));
+        assert_eq!(codes("this.#x;"), vec![ErrorCode::UndeclaredPrivateName]);
+        // Inner classes can use the private names of outer classes, declared
+        // before or after the use.
+        assert!(Parser::new(lexer::tokenize(
+            "class A { m() { class B { n(o) { return o.#x; } } } #x; }"
+        ))
+        .parse()
+        .is_ok());
+
+        let errors = parse_errors("class A { #a; #a; }");
+        assert_eq!(errors[0].code, ErrorCode::Redeclaration);
+        assert_eq!(errors[0].span, Span::new(14, 16));
+        assert_eq!(
+            codes("class A { get #a() {} set #a(v) {} get #a() {} }"),
+            vec![ErrorCode::Redeclaration]
+        );
+        assert_eq!(
+            codes("class A { static get #a() {} set #a(v) {} }"),
+            vec![ErrorCode::Redeclaration]
+        );
+        assert!(Parser::new(lexer::tokenize(
+            "class A { get #a() {} set #a(v) {} static #b() {} }"
+        ))
+        .parse()
+        .is_ok());
+
+        for input in [
+            "class A { get constructor() {} }",
+            "class A { *constructor() {} }",
+            "class A { async constructor() {} }",
+            "class A { constructor = 1; }",
+            "class A { static prototype() {} }",
+            "class A { static 'prototype' = 1; }",
+        ] {
+            assert_eq!(
+                codes(input),
+                vec![ErrorCode::ReservedMemberName],
+                "{}",
+                input
+            );
+        }
+        assert!(Parser::new(lexer::tokenize(
+            "class A { static constructor() {} prototype() {} static ['prototype']() {} }"
+        ))
+        .parse()
+        .is_ok());
+
+        let errors = parse_errors("class A { constructor() { delete this.#a; } #a; }");
+        assert_eq!(errors[0].code, ErrorCode::StrictDelete);
+        assert_eq!(errors[0].span, Span::new(33, 37));
+        assert_eq!(
+            codes("class A { #a; m() { delete this?.#a; } }"),
+            vec![ErrorCode::StrictDelete]
+        );
+    }
+
+    fn string(value: &str) -> AstNode {
+        AstNode::StringLiteral(value.to_string())
+    }
+
+    fn module_options() -> ParseOptions {
+        ParseOptions {
+            source_type: SourceType::Module,
+            ..ParseOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_import_declarations() {
+        let import = |specifiers, source: &str| AstNode::ImportDeclaration {
+            phase: None,
+            import_kind: "value".to_string(),
+            specifiers,
+            source: Box::new(string(source)),
+            attributes: vec![],
+        };
+        let specifier = |imported: AstNode, local: &str| AstNode::ImportSpecifier {
+            import_kind: "value".to_string(),
+            imported: Box::new(imported),
+            local: Box::new(identifier(local)),
+        };
+        assert_eq!(
+            first_statement_with(
+                &module_options(),
+                "import x, {a as b, c, \"d-e\" as f, default as g} from \"m\";"
+            ),
+            import(
+                vec![
+                    AstNode::ImportDefaultSpecifier {
+                        local: Box::new(identifier("x")),
+                    },
+                    specifier(identifier("a"), "b"),
+                    specifier(identifier("c"), "c"),
+                    specifier(string("d-e"), "f"),
+                    specifier(identifier("default"), "g"),
+                ],
+                "m"
+            )
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "import * as ns from 'm'"),
+            import(
+                vec![AstNode::ImportNamespaceSpecifier {
+                    local: Box::new(identifier("ns")),
+                }],
+                "m"
+            )
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "import 'polyfill';"),
+            import(vec![], "polyfill")
+        );
+        assert_eq!(
+            first_statement_with(
+                &module_options(),
+                "import data from './data.json' with { type: 'json' };"
+            ),
+            AstNode::ImportDeclaration {
+                phase: None,
+                import_kind: "value".to_string(),
+                specifiers: vec![AstNode::ImportDefaultSpecifier {
+                    local: Box::new(identifier("data")),
+                }],
+                source: Box::new(string("./data.json")),
+                attributes: vec![AstNode::ImportAttribute {
+                    key: Box::new(identifier("type")),
+                    value: Box::new(string("json")),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_export_declarations() {
+        let specifier = |local: AstNode, exported: AstNode| AstNode::ExportSpecifier {
+            export_kind: "value".to_string(),
+            local: Box::new(local),
+            exported: Box::new(exported),
+        };
+        assert_eq!(
+            first_statement_with(
+                &module_options(),
+                "export {a as b, c, d as \"e f\", g as default};"
+            ),
+            AstNode::ExportNamedDeclaration {
+                export_kind: "value".to_string(),
+                declaration: None,
+                specifiers: vec![
+                    specifier(identifier("a"), identifier("b")),
+                    specifier(identifier("c"), identifier("c")),
+                    specifier(identifier("d"), string("e f")),
+                    specifier(identifier("g"), identifier("default")),
+                ],
+                source: None,
+                attributes: vec![],
+            }
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export {\"a b\"} from \"m\";"),
+            AstNode::ExportNamedDeclaration {
+                export_kind: "value".to_string(),
+                declaration: None,
+                specifiers: vec![specifier(string("a b"), string("a b"))],
+                source: Some(Box::new(string("m"))),
+                attributes: vec![],
+            }
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export * from 'm';"),
+            AstNode::ExportAllDeclaration {
+                exported: None,
+                source: Box::new(string("m")),
+                attributes: vec![],
+            }
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export * as ns from 'm';"),
+            AstNode::ExportAllDeclaration {
+                exported: Some(Box::new(identifier("ns"))),
+                source: Box::new(string("m")),
+                attributes: vec![],
+            }
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export const x = 1;"),
+            AstNode::ExportNamedDeclaration {
+                export_kind: "value".to_string(),
+                declaration: Some(Box::new(AstNode::VariableDeclaration {
+                    declare: false,
+                    kind: "const".to_string(),
+                    declarations: vec![declarator("x", Some(AstNode::NumberLiteral(1.0)))],
+                })),
+                specifiers: vec![],
+                source: None,
+                attributes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_export_default() {
+        let export_default = |declaration| AstNode::ExportDefaultDeclaration {
+            declaration: Box::new(declaration),
+        };
+        assert_eq!(
+            first_statement_with(&module_options(), "export default a + b;"),
+            export_default(binary("+", identifier("a"), identifier("b")))
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export default function () {}"),
+            export_default(empty_function(vec![]))
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "export default class C {}"),
+            export_default(AstNode::ClassDeclaration {
+                super_type_arguments: None,
+                type_parameters: None,
+                r#abstract: false,
+                declare: false,
+                implements: vec![],
+                id: Box::new(identifier("C")),
+                super_class: None,
+                body: Box::new(AstNode::ClassBody { body: vec![] }),
+                decorators: vec![],
+            })
+        );
+        // No semicolon is needed after a default function declaration.
+        let program = parse_with(
+            &module_options(),
+            "export default async function f() {} f()",
+        )
+        .unwrap();
+        assert!(matches!(
+            program,
+            AstNode::Program { body } if matches!(
+                &body[0],
+                AstNode::ExportDefaultDeclaration { declaration }
+                    if matches!(**declaration, AstNode::FunctionDeclaration { r#async: true, .. })
+            )
+        ));
+    }
+
+    #[test]
+    fn test_module_declaration_errors() {
+        assert_eq!(
+            parse_with(&module_options(), "if (a) { import x from 'm'; }").unwrap_err()[0].code,
+            ErrorCode::MisplacedImportExport
+        );
+        assert_eq!(
+            parse_with(&module_options(), "import {'a'} from 'm';").unwrap_err()[0].expected,
+            "'as'"
+        );
+        assert_eq!(
+            parse_with(&module_options(), "import {a} 'm';").unwrap_err()[0].expected,
+            "'from'"
+        );
+        assert_eq!(
+            parse_with(&module_options(), "export {'a'};").unwrap_err()[0].code,
+            ErrorCode::ExpectedIdentifier
+        );
+        assert_eq!(
+            parse_with(&module_options(), "import x from m;").unwrap_err()[0].expected,
+            "module specifier string"
+        );
+    }
+
+    #[test]
+    fn test_script_and_module_goals() {
+        let errors = parse_errors("import x from 'm';");
+        assert_eq!(errors[0].code, ErrorCode::MisplacedImportExport);
+        assert_eq!(
+            errors[0].message,
+            "'import' and 'export' may only appear in modules."
+        );
+        // Module code is strict and allows `await` at the top level.
+        assert_eq!(
+            parse_with(&module_options(), "with (a) {}").unwrap_err()[0].code,
+            ErrorCode::StrictWith
+        );
+        assert_eq!(
+            parse_with(&module_options(), "delete x;").unwrap_err()[0].code,
+            ErrorCode::StrictDelete
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "await a;"),
+            statement(AstNode::AwaitExpression {
+                argument: Box::new(identifier("a")),
+            })
+        );
+        assert!(!parse_errors("await a;").is_empty());
+        assert!(parse_with(&module_options(), "function f() { await; }").is_err());
+    }
+
+    #[test]
+    fn test_html_comments() {
+        let source = "a = 1; <!-- comment\n--> also a comment\nb = 2;";
+        match Parser::new(lexer::tokenize(source)).parse().unwrap() {
+            AstNode::Program { body } => assert_eq!(body.len(), 2),
+            _ => unreachable!(),
+        }
+        // `x-->y` is only a comment at the start of a line.
+        assert_eq!(
+            parse_expression("x-->y"),
+            binary(">", update("--", false, identifier("x")), identifier("y"))
+        );
+        assert!(parse_with(&module_options(), source).is_err());
+    }
+
+    #[test]
+    fn test_directive_prologues() {
+        let directive = |value: &str| AstNode::ExpressionStatement {
+            expression: Box::new(string(value)),
+            directive: Some(value.to_string()),
         };
-        let tokens = lexer::tokenize(input);
-        let ast = Parser::parse(tokens);
-        assert_eq!(ast, expected_output);
+        assert_eq!(
+            Parser::new(lexer::tokenize(
+                "'use strict'; \"other\"; a; 'not a directive';"
+            ))
+            .parse()
+            .unwrap(),
+            AstNode::Program {
+                body: vec![
+                    directive("use strict"),
+                    directive("other"),
+                    statement(identifier("a")),
+                    statement(string("not a directive")),
+                ],
+            }
+        );
+        assert_eq!(
+            first_statement("('use strict');"),
+            statement(string("use strict"))
+        );
+        assert_eq!(
+            parse_errors("'use strict'; with (a) {}")[0].code,
+            ErrorCode::StrictWith
+        );
+        assert!(Parser::new(lexer::tokenize("a; 'use strict'; with (a) {}"))
+            .parse()
+            .is_ok());
+        // A function's prologue only applies to its own body.
+        assert_eq!(
+            parse_errors("function f() { 'use strict'; with (a) {} }")[0].code,
+            ErrorCode::StrictWith
+        );
+        assert!(Parser::new(lexer::tokenize(
+            "function f() { 'use strict'; } with (a) {}"
+        ))
+        .parse()
+        .is_ok());
+        assert_eq!(
+            parse_errors("var f = () => { 'use strict'; delete x; };")[0].code,
+            ErrorCode::StrictDelete
+        );
+    }
+
+    fn unsupported_features(input: &str, ecma_version: u32) -> Vec<String> {
+        let options = ParseOptions {
+            ecma_version,
+            ..ParseOptions::default()
+        };
+        match lexer::parse(input, &options) {
+            Ok(_) => vec![],
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| {
+                    assert_eq!(error.code, ErrorCode::UnsupportedSyntax);
+                    error.message
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_ecma_version_gates() {
+        assert_eq!(
+            unsupported_features("a ?? b; a?.b;", 2017),
+            vec![
+                "ES2020 is required for nullish coalescing operator, but ecma_version is 2017.",
+                "ES2020 is required for optional chaining, but ecma_version is 2017.",
+            ]
+        );
+        assert_eq!(
+            unsupported_features("class A { x = 1; #y; static {} }", 2017),
+            vec![
+                "ES2022 is required for class fields, but ecma_version is 2017.",
+                "ES2022 is required for private class members, but ecma_version is 2017.",
+                "ES2022 is required for class fields, but ecma_version is 2017.",
+                "ES2022 is required for class static blocks, but ecma_version is 2017.",
+            ]
+        );
+        assert_eq!(unsupported_features("a = 1_000;", 2017).len(), 1);
+        assert_eq!(unsupported_features("a ||= b; a ** 2;", 2015).len(), 2);
+        assert_eq!(
+            unsupported_features("try {} catch {} f(a,); ({...a});", 2017).len(),
+            2
+        );
+        assert_eq!(
+            unsupported_features("let [a] = b; const f = (...c) => c; class C {}", 5).len(),
+            6
+        );
+        assert_eq!(
+            unsupported_features("async function* f() { for await (x of y); }", 2017).len(),
+            2
+        );
+        assert_eq!(
+            unsupported_features(
+                "function f(a, b = 1) { return new.target; } o = { a, b() {}, [c]: 1 };",
+                5
+            ),
+            vec![
+                "ES2015 is required for default parameters, but ecma_version is 5.",
+                "ES2015 is required for 'new.target', but ecma_version is 5.",
+                "ES2015 is required for shorthand properties, but ecma_version is 5.",
+                "ES2015 is required for shorthand methods, but ecma_version is 5.",
+                "ES2015 is required for computed property names, but ecma_version is 5.",
+            ]
+        );
+        // Getters, setters and quoted or numeric keys are ES5.
+        assert!(unsupported_features(
+            "o = { get a() { return 1; }, set a(v) {}, 'b': 2, 3: 4 };",
+            5
+        )
+        .is_empty());
+        // Everything up to the targeted edition is accepted.
+        assert!(unsupported_features("async function f(a,) { (await a) ** 2; }", 2017).is_empty());
+        let error = &lexer::parse(
+            "a ?? b",
+            &ParseOptions {
+                ecma_version: 2019,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap_err()[0];
+        assert_eq!(error.expected, "ES2019 syntax");
+        assert_eq!(error.found, Token::QuestionQuestion);
+    }
+
+    #[test]
+    fn test_dynamic_import_and_import_meta() {
+        let import = |source: &str, options: Option<AstNode>| AstNode::ImportExpression {
+            phase: None,
+            source: Box::new(string(source)),
+            options: options.map(Box::new),
+        };
+        // Dynamic import is an expression in scripts as well as modules.
+        assert_eq!(
+            first_statement("import(\"./page.js\").then(show);"),
+            statement(call(
+                member(import("./page.js", None), "then", false),
+                vec![identifier("show")],
+                false
+            ))
+        );
+        assert_eq!(
+            first_statement_with(
+                &module_options(),
+                "await import('./data.json', { with: { type: 'json' } },);"
+            ),
+            statement(AstNode::AwaitExpression {
+                argument: Box::new(import(
+                    "./data.json",
+                    Some(AstNode::ObjectExpression {
+                        properties: vec![property(
+                            identifier("with"),
+                            AstNode::ObjectExpression {
+                                properties: vec![property(
+                                    identifier("type"),
+                                    string("json"),
+                                    "init"
+                                )],
+                            },
+                            "init"
+                        )],
+                    })
+                )),
+            })
+        );
+        assert_eq!(
+            first_statement_with(&module_options(), "import.meta.url;"),
+            statement(member(
+                AstNode::MetaProperty {
+                    meta: Box::new(identifier("import")),
+                    property: Box::new(identifier("meta")),
+                },
+                "url",
+                false
+            ))
+        );
+        assert!(parse_with(&module_options(), "for await (const chunk of stream) {}").is_ok());
+
+        assert_eq!(
+            parse_errors("import.meta;")[0].code,
+            ErrorCode::MisplacedImportExport
+        );
+        assert_eq!(parse_errors("import.target;")[0].expected, "'meta'");
+        assert_eq!(
+            parse_errors("import();")[0].code,
+            ErrorCode::ExpectedExpression
+        );
+        assert_eq!(
+            parse_errors("new import('a');")[0].message,
+            "'import()' cannot be called with 'new'."
+        );
+        assert_eq!(
+            unsupported_features("import('a', {});", 2020),
+            vec!["ES2025 is required for import options, but ecma_version is 2020."]
+        );
+    }
+
+    #[test]
+    fn test_legacy_octal_literals() {
+        assert_eq!(parse_expression("010"), AstNode::NumberLiteral(8.0));
+        assert_eq!(parse_expression("09"), AstNode::NumberLiteral(9.0));
+        assert_eq!(
+            parse_errors("'use strict'; x = 010;")[0].code,
+            ErrorCode::LegacyOctal
+        );
+        assert_eq!(
+            parse_with(&module_options(), "({ 07: a });").unwrap_err()[0].code,
+            ErrorCode::LegacyOctal
+        );
+        assert_eq!(parse_expression("0.5"), AstNode::NumberLiteral(0.5));
+    }
+
+    fn decorator_options(syntax: Option<DecoratorSyntax>) -> ParseOptions {
+        ParseOptions {
+            decorators: syntax,
+            ..module_options()
+        }
+    }
+
+    fn decorator(expression: AstNode) -> AstNode {
+        AstNode::Decorator {
+            expression: Box::new(expression),
+        }
+    }
+
+    #[test]
+    fn test_decorators() {
+        let input = "@Component({ selector: 'x' }) class X { @Input() name; }";
+        assert_eq!(
+            first_statement_with(
+                &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+                input
+            ),
+            AstNode::ClassDeclaration {
+                super_type_arguments: None,
+                type_parameters: None,
+                r#abstract: false,
+                declare: false,
+                implements: vec![],
+                id: Box::new(identifier("X")),
+                super_class: None,
+                body: Box::new(AstNode::ClassBody {
+                    body: vec![AstNode::PropertyDefinition {
+                        variance: None,
+                        type_annotation: None,
+                        optional: false,
+                        r#override: false,
+                        readonly: false,
+                        accessibility: None,
+                        declare: false,
+                        definite: false,
+                        key: Box::new(identifier("name")),
+                        value: None,
+                        computed: false,
+                        r#static: false,
+                        decorators: vec![decorator(call(identifier("Input"), vec![], false))],
+                    }],
+                }),
+                decorators: vec![decorator(call(
+                    identifier("Component"),
+                    vec![AstNode::ObjectExpression {
+                        properties: vec![property(identifier("selector"), string("x"), "init",)],
+                    }],
+                    false,
+                ))],
+            }
+        );
+        // Member access and parenthesised forms, on methods and accessors.
+        let class = first_statement_with(
+            &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+            "class A { @a.b.c() m() {} @(x[0]) static get y() {} }",
+        );
+        let AstNode::ClassDeclaration { body, .. } = class else {
+            unreachable!()
+        };
+        let AstNode::ClassBody { body } = *body else {
+            unreachable!()
+        };
+        let decorators: Vec<_> = body
+            .into_iter()
+            .map(|member| match member {
+                AstNode::MethodDefinition { decorators, .. } => decorators,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            decorators,
+            vec![
+                vec![decorator(call(
+                    member(member(identifier("a"), "b", false), "c", false),
+                    vec![],
+                    false,
+                ))],
+                vec![decorator(AstNode::MemberExpression {
+                    object: Box::new(identifier("x")),
+                    property: Box::new(AstNode::NumberLiteral(0.0)),
+                    computed: true,
+                    optional: false,
+                })],
+            ]
+        );
+        // A decorated class expression.
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Legacy)),
+            "x = @dec class {};"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_decorator_syntax_options() {
+        // Only the legacy syntax allows calling a decorator's result.
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Legacy)),
+            "@a().b() class A {}"
+        )
+        .is_ok());
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+            "@a().b() class A {}"
+        )
+        .is_err());
+        // Decorators may follow `export` only in the 2023 syntax.
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+            "export @dec class A {}"
+        )
+        .is_ok());
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Legacy)),
+            "@dec export class A {}"
+        )
+        .is_ok());
+        assert_eq!(
+            parse_with(
+                &decorator_options(Some(DecoratorSyntax::Legacy)),
+                "export default @dec class {}"
+            )
+            .unwrap_err()[0]
+                .message,
+            "Legacy decorators must come before 'export'."
+        );
+        assert_eq!(
+            parse_with(
+                &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+                "@a export @b class A {}"
+            )
+            .unwrap_err()[0]
+                .message,
+            "Decorators may not appear both before and after 'export'."
+        );
+        // Only the legacy syntax allows computed member accesses; otherwise
+        // `[` starts the computed key of the decorated member.
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Legacy)),
+            "@a[0] class A {}"
+        )
+        .is_ok());
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+            "@a[0] class A {}"
+        )
+        .is_err());
+        assert!(parse_with(
+            &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+            "class A { @dec [key]() {} }"
+        )
+        .is_ok());
+        // Constructors cannot be decorated in either syntax.
+        for syntax in [DecoratorSyntax::Legacy, DecoratorSyntax::Proposal2023] {
+            assert_eq!(
+                parse_with(
+                    &decorator_options(Some(syntax)),
+                    "class A { @dec constructor() {} }"
+                )
+                .unwrap_err()[0]
+                    .message,
+                "Decorators are not valid on constructors."
+            );
+        }
+        // Without the option, decorators are parsed but reported.
+        let errors = parse_with(&decorator_options(None), "@dec class A {}").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::FeatureNotEnabled);
+    }
+
+    #[test]
+    fn test_auto_accessors() {
+        assert_eq!(
+            first_statement_with(
+                &decorator_options(Some(DecoratorSyntax::Proposal2023)),
+                "class A { static accessor x = 1; accessor; }"
+            ),
+            AstNode::ClassDeclaration {
+                super_type_arguments: None,
+                type_parameters: None,
+                r#abstract: false,
+                declare: false,
+                implements: vec![],
+                id: Box::new(identifier("A")),
+                super_class: None,
+                body: Box::new(AstNode::ClassBody {
+                    body: vec![
+                        AstNode::AccessorProperty {
+                            accessibility: None,
+                            r#override: false,
+                            type_annotation: None,
+                            key: Box::new(identifier("x")),
+                            value: Some(Box::new(AstNode::NumberLiteral(1.0))),
+                            computed: false,
+                            r#static: true,
+                            decorators: vec![],
+                        },
+                        AstNode::PropertyDefinition {
+                            variance: None,
+                            type_annotation: None,
+                            optional: false,
+                            r#override: false,
+                            readonly: false,
+                            accessibility: None,
+                            declare: false,
+                            definite: false,
+                            key: Box::new(identifier("accessor")),
+                            value: None,
+                            computed: false,
+                            r#static: false,
+                            decorators: vec![],
+                        },
+                    ],
+                }),
+                decorators: vec![],
+            }
+        );
+    }
+
+    fn jsx_options() -> ParseOptions {
+        ParseOptions {
+            jsx: true,
+            ..ParseOptions::default()
+        }
+    }
+
+    fn jsx_name(name: &str) -> AstNode {
+        AstNode::JSXIdentifier(name.to_string())
+    }
+
+    fn jsx_element(name: AstNode, attributes: Vec<AstNode>, children: Vec<AstNode>) -> AstNode {
+        AstNode::JSXElement {
+            opening_element: Box::new(AstNode::JSXOpeningElement {
+                name: Box::new(name.clone()),
+                attributes,
+                self_closing: false,
+            }),
+            children,
+            closing_element: Some(Box::new(AstNode::JSXClosingElement {
+                name: Box::new(name),
+            })),
+        }
+    }
+
+    fn jsx_text(text: &str) -> AstNode {
+        AstNode::JSXText(text.to_string())
+    }
+
+    #[test]
+    fn test_jsx_elements() {
+        assert_eq!(
+            expression_with(
+                &jsx_options(),
+                "<div id=\"main\" hidden {...props}>Hi {name}!</div>;"
+            ),
+            jsx_element(
+                jsx_name("div"),
+                vec![
+                    AstNode::JSXAttribute {
+                        name: Box::new(jsx_name("id")),
+                        value: Some(Box::new(string("main"))),
+                    },
+                    AstNode::JSXAttribute {
+                        name: Box::new(jsx_name("hidden")),
+                        value: None,
+                    },
+                    AstNode::JSXSpreadAttribute {
+                        argument: Box::new(identifier("props")),
+                    },
+                ],
+                vec![
+                    jsx_text("Hi "),
+                    AstNode::JSXExpressionContainer {
+                        expression: Box::new(identifier("name")),
+                    },
+                    jsx_text("!"),
+                ],
+            )
+        );
+        // Fragments, self-closing elements, empty expressions and entities.
+        assert_eq!(
+            expression_with(&jsx_options(), "<><br />{/* note */}&copy; &amp;c</>"),
+            AstNode::JSXFragment {
+                children: vec![
+                    AstNode::JSXElement {
+                        opening_element: Box::new(AstNode::JSXOpeningElement {
+                            name: Box::new(jsx_name("br")),
+                            attributes: vec![],
+                            self_closing: true,
+                        }),
+                        children: vec![],
+                        closing_element: None,
+                    },
+                    AstNode::JSXExpressionContainer {
+                        expression: Box::new(AstNode::JSXEmptyExpression),
+                    },
+                    jsx_text("© &c"),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_jsx_names_and_attribute_values() {
+        let member = AstNode::JSXMemberExpression {
+            object: Box::new(AstNode::JSXMemberExpression {
+                object: Box::new(jsx_name("UI")),
+                property: Box::new(jsx_name("Form")),
+            }),
+            property: Box::new(jsx_name("Field")),
+        };
+        assert_eq!(
+            expression_with(&jsx_options(), "<UI.Form.Field></UI.Form.Field>"),
+            jsx_element(member, vec![], vec![])
+        );
+        let namespaced = AstNode::JSXNamespacedName {
+            namespace: Box::new(jsx_name("svg")),
+            name: Box::new(jsx_name("rect")),
+        };
+        let icon = AstNode::JSXElement {
+            opening_element: Box::new(AstNode::JSXOpeningElement {
+                name: Box::new(jsx_name("Icon")),
+                attributes: vec![],
+                self_closing: true,
+            }),
+            children: vec![],
+            closing_element: None,
+        };
+        assert_eq!(
+            expression_with(
+                &jsx_options(),
+                "<svg:rect xlink:href='a' icon=<Icon/> on={() => {}}></svg:rect>"
+            ),
+            jsx_element(
+                namespaced,
+                vec![
+                    AstNode::JSXAttribute {
+                        name: Box::new(AstNode::JSXNamespacedName {
+                            namespace: Box::new(jsx_name("xlink")),
+                            name: Box::new(jsx_name("href")),
+                        }),
+                        value: Some(Box::new(string("a"))),
+                    },
+                    AstNode::JSXAttribute {
+                        name: Box::new(jsx_name("icon")),
+                        value: Some(Box::new(icon)),
+                    },
+                    AstNode::JSXAttribute {
+                        name: Box::new(jsx_name("on")),
+                        value: Some(Box::new(AstNode::JSXExpressionContainer {
+                            expression: Box::new(AstNode::ArrowFunctionExpression {
+                                return_type: None,
+                                type_parameters: None,
+                                params: vec![],
+                                body: Box::new(block(vec![])),
+                                expression: false,
+                                r#async: false,
+                            }),
+                        })),
+                    },
+                ],
+                vec![],
+            )
+        );
+        // Nested elements inside expression containers, and `<` as an
+        // operator after an operand.
+        assert!(parse_with(
+            &jsx_options(),
+            "x = <ul>{items.map(i => <li key={i}>{i}</li>)}</ul>;"
+        )
+        .is_ok());
+        assert_eq!(
+            expression_with(&jsx_options(), "a < b"),
+            binary("<", identifier("a"), identifier("b"))
+        );
+        // After `)`, `]` or `}` the parser decides whether `<` starts a tag.
+        for source in ["if (x) <div/>;", "while (c) <a>it's</a>;", "{} <a/>;"] {
+            assert!(lexer::parse(source, &jsx_options()).is_ok(), "{}", source);
+        }
+        let program = lexer::parse("(a) < b; [a] < b > c;", &jsx_options()).unwrap();
+        let AstNode::Program { body } = program else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            statement(binary("<", identifier("a"), identifier("b")))
+        );
+    }
+
+    #[test]
+    fn test_jsx_errors() {
+        let errors = parse_with(&jsx_options(), "<a><b></a></b>;").unwrap_err();
+        assert_eq!(errors[0].code, ErrorCode::MismatchedClosingTag);
+        assert_eq!(
+            errors[0].message,
+            "Expected corresponding closing tag for <b>."
+        );
+        assert_eq!(
+            parse_with(&jsx_options(), "<>x</div>;").unwrap_err()[0].code,
+            ErrorCode::MismatchedClosingTag
+        );
+        assert_eq!(
+            parse_with(&jsx_options(), "<div>x").unwrap_err()[0].message,
+            "Expect closing tag for <div>."
+        );
+        assert_eq!(
+            parse_with(&jsx_options(), "<a b={} />;").unwrap_err()[0].message,
+            "JSX attributes must be assigned a non-empty expression."
+        );
+        for source in ["<a>}</a>;", "<a>></a>;"] {
+            let errors = lexer::parse(source, &jsx_options()).unwrap_err();
+            assert_eq!(errors[0].code, ErrorCode::InvalidCharacter, "{}", source);
+        }
+        // Without the option, `<` is always an operator.
+        assert_eq!(
+            parse_errors("<div />;")[0].code,
+            ErrorCode::ExpectedExpression
+        );
+    }
+
+    fn typescript_options() -> ParseOptions {
+        ParseOptions {
+            typescript: true,
+            ..module_options()
+        }
+    }
+
+    fn type_reference(name: &str) -> AstNode {
+        AstNode::TSTypeReference {
+            type_name: Box::new(AstNode::identifier(name.to_string())),
+            type_arguments: None,
+        }
+    }
+
+    #[test]
+    fn test_typescript_annotations() {
+        assert_eq!(
+            first_statement_with(
+                &typescript_options(),
+                "function f<T>(a: T, b?: string): T {}"
+            ),
+            AstNode::FunctionDeclaration {
+                id: Box::new(AstNode::identifier("f".to_string())),
+                params: vec![
+                    AstNode::Identifier {
+                        name: "a".to_string(),
+                        type_annotation: Some(type_annotation(type_reference("T"))),
+                        optional: false,
+                    },
+                    AstNode::Identifier {
+                        name: "b".to_string(),
+                        type_annotation: Some(type_annotation(AstNode::TSStringKeyword)),
+                        optional: true,
+                    },
+                ],
+                body: Box::new(AstNode::BlockStatement { body: vec![] }),
+                generator: false,
+                r#async: false,
+                type_parameters: Some(Box::new(AstNode::TSTypeParameterDeclaration {
+                    params: vec![AstNode::TSTypeParameter {
+                        name: Box::new(AstNode::identifier("T".to_string())),
+                        constraint: None,
+                        default: None,
+                        r#in: false,
+                        out: false,
+                        r#const: false,
+                    }],
+                })),
+                return_type: Some(type_annotation(type_reference("T"))),
+            }
+        );
+        assert_eq!(
+            first_statement_with(&typescript_options(), "let x!: number;"),
+            AstNode::VariableDeclaration {
+                kind: "let".to_string(),
+                declarations: vec![AstNode::VariableDeclarator {
+                    id: Box::new(AstNode::Identifier {
+                        name: "x".to_string(),
+                        type_annotation: Some(type_annotation(AstNode::TSNumberKeyword)),
+                        optional: false,
+                    }),
+                    init: None,
+                    definite: true,
+                }],
+                declare: false,
+            }
+        );
+        let AstNode::FunctionDeclaration { params, .. } = first_statement_with(
+            &typescript_options(),
+            "function f(this: W, [a]?: T[], ...r: T[]) {}",
+        ) else {
+            panic!("expected a function declaration");
+        };
+        assert_eq!(
+            params,
+            vec![
+                AstNode::Identifier {
+                    name: "this".to_string(),
+                    type_annotation: Some(type_annotation(type_reference("W"))),
+                    optional: false,
+                },
+                AstNode::ArrayPattern {
+                    elements: vec![Some(AstNode::identifier("a".to_string()))],
+                    type_annotation: Some(type_annotation(AstNode::TSArrayType {
+                        element_type: Box::new(type_reference("T")),
+                    })),
+                    optional: true,
+                },
+                AstNode::RestElement {
+                    argument: Box::new(AstNode::identifier("r".to_string())),
+                    type_annotation: Some(type_annotation(AstNode::TSArrayType {
+                        element_type: Box::new(type_reference("T")),
+                    })),
+                },
+            ]
+        );
+        assert!(matches!(
+            expression_with(&typescript_options(), "({ a }: T) => a;"),
+            AstNode::ArrowFunctionExpression { params, .. } if matches!(
+                &params[0],
+                AstNode::ObjectPattern { type_annotation: Some(_), optional: false, .. }
+            )
+        ));
+        // Without the option, annotations are syntax errors.
+        assert_eq!(
+            parse_errors("let x: number;")[0].code,
+            ErrorCode::MissingSemicolon
+        );
+    }
+
+    #[test]
+    fn test_typescript_types() {
+        assert_eq!(
+            first_statement_with(
+                &typescript_options(),
+                "type A<T> = T extends string ? 'a' | 'b' : T[];"
+            ),
+            AstNode::TSTypeAliasDeclaration {
+                id: Box::new(AstNode::identifier("A".to_string())),
+                type_parameters: Some(Box::new(AstNode::TSTypeParameterDeclaration {
+                    params: vec![AstNode::TSTypeParameter {
+                        name: Box::new(AstNode::identifier("T".to_string())),
+                        constraint: None,
+                        default: None,
+                        r#in: false,
+                        out: false,
+                        r#const: false,
+                    }],
+                })),
+                type_annotation: Box::new(AstNode::TSConditionalType {
+                    check_type: Box::new(type_reference("T")),
+                    extends_type: Box::new(AstNode::TSStringKeyword),
+                    true_type: Box::new(AstNode::TSUnionType {
+                        types: vec![
+                            AstNode::TSLiteralType {
+                                literal: Box::new(AstNode::StringLiteral("a".to_string())),
+                            },
+                            AstNode::TSLiteralType {
+                                literal: Box::new(AstNode::StringLiteral("b".to_string())),
+                            },
+                        ],
+                    }),
+                    false_type: Box::new(AstNode::TSArrayType {
+                        element_type: Box::new(type_reference("T")),
+                    }),
+                }),
+                declare: false,
+            }
+        );
+        assert_eq!(
+            first_statement_with(&typescript_options(), "type T = `a${B}c`;"),
+            AstNode::TSTypeAliasDeclaration {
+                id: Box::new(AstNode::identifier("T".to_string())),
+                type_parameters: None,
+                type_annotation: Box::new(AstNode::TSTemplateLiteralType {
+                    quasis: vec![
+                        AstNode::TemplateElement {
+                            raw: "a".to_string(),
+                            tail: false,
+                        },
+                        AstNode::TemplateElement {
+                            raw: "c".to_string(),
+                            tail: true,
+                        },
+                    ],
+                    types: vec![type_reference("B")],
+                }),
+                declare: false,
+            }
+        );
+        for source in [
+            "type F = <T>(this: Window, x: T) => x is T;",
+            "type C = abstract new (...args: any[]) => object;",
+            "type M<T> = { -readonly [K in keyof T as Uppercase<K>]+?: T[K] };",
+            "type I<T> = T extends [infer H extends string, ...infer R] ? H : never;",
+            "type T = [a: string, b?: number, ...rest: boolean[]];",
+            "type Q = typeof x.y | import('./m').Foo<string> | unique symbol;",
+            "let m: Map<string, Array<Array<number>>>= new Map();",
+            "type O = { (x: number): void; new (): O; get a(): number; [k: string]: any; m?<U>(): U };",
+            "type E = `on${Capitalize<`${K}-${V}`>}`;",
+        ] {
+            assert!(parse_with(&typescript_options(), source).is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_typescript_declarations() {
+        assert_eq!(
+            first_statement_with(&typescript_options(), "const enum E { A, 'B' = 2 }"),
+            AstNode::TSEnumDeclaration {
+                id: Box::new(AstNode::identifier("E".to_string())),
+                members: vec![
+                    AstNode::TSEnumMember {
+                        id: Box::new(AstNode::identifier("A".to_string())),
+                        initializer: None,
+                    },
+                    AstNode::TSEnumMember {
+                        id: Box::new(AstNode::StringLiteral("B".to_string())),
+                        initializer: Some(Box::new(AstNode::NumberLiteral(2.0))),
+                    },
+                ],
+                r#const: true,
+                declare: false,
+            }
+        );
+        assert_eq!(
+            first_statement_with(&typescript_options(), "declare namespace A.B {}"),
+            AstNode::TSModuleDeclaration {
+                id: Box::new(AstNode::TSQualifiedName {
+                    left: Box::new(AstNode::identifier("A".to_string())),
+                    right: Box::new(AstNode::identifier("B".to_string())),
+                }),
+                body: Some(Box::new(AstNode::TSModuleBlock { body: vec![] })),
+                kind: "namespace".to_string(),
+                declare: true,
+            }
+        );
+        for source in [
+            "interface A<T> extends B<T>, C.D { a: string; b?: number\n readonly c: T[] }",
+            "declare module 'm' { export function f(): void; }",
+            "declare global { interface Window { x: number } }",
+            "function f(x: string): void; function f(x: any) {}",
+            "import type { A } from 'a'; import { type B, C } from 'b'; import type from 'c';",
+            "import fs = require('fs'); import X = A.B; export type { A }; export = x;",
+            "export interface I {} export declare const y: number; export default interface Q {}",
+            "export as namespace Lib;",
+        ] {
+            assert!(
+                parse_with(&typescript_options(), source).is_ok(),
+                "{}",
+                source
+            );
+        }
+        // `type` and `interface` are only contextual keywords.
+        assert!(parse_with(&typescript_options(), "type = interface;\ntype\nFoo = 1;").is_ok());
+    }
+
+    #[test]
+    fn test_typescript_classes() {
+        let source = "abstract class A<T> extends B<T> implements I { \
+            private readonly x?: number; protected abstract m(): void; \
+            constructor(public y: T) { super(); } [key: string]: any; }";
+        let AstNode::ClassDeclaration {
+            body,
+            r#abstract,
+            super_type_arguments,
+            implements,
+            ..
+        } = first_statement_with(&typescript_options(), source)
+        else {
+            panic!("expected a class declaration");
+        };
+        assert!(r#abstract);
+        assert!(super_type_arguments.is_some());
+        assert_eq!(implements.len(), 1);
+        let AstNode::ClassBody { body } = *body else {
+            unreachable!()
+        };
+        assert_eq!(
+            body[0],
+            AstNode::PropertyDefinition {
+                variance: None,
+                key: Box::new(AstNode::identifier("x".to_string())),
+                value: None,
+                computed: false,
+                r#static: false,
+                decorators: vec![],
+                type_annotation: Some(type_annotation(AstNode::TSNumberKeyword)),
+                accessibility: Some("private".to_string()),
+                optional: true,
+                definite: false,
+                readonly: true,
+                declare: false,
+                r#override: false,
+            }
+        );
+        assert!(matches!(
+            body[1],
+            AstNode::TSAbstractMethodDefinition { .. }
+        ));
+        let AstNode::MethodDefinition { value, .. } = &body[2] else {
+            unreachable!()
+        };
+        assert!(matches!(
+            value.as_ref(),
+            AstNode::FunctionExpression { params, .. }
+                if matches!(params[0], AstNode::TSParameterProperty { .. })
+        ));
+        assert!(matches!(body[3], AstNode::TSIndexSignature { .. }));
+        assert_eq!(
+            parse_with(&typescript_options(), "function f(private x) {}").unwrap_err()[0].message,
+            "Parameter properties are only allowed in constructors."
+        );
+    }
+
+    #[test]
+    fn test_typescript_expressions() {
+        let expression = |source: &str| expression_with(&typescript_options(), source);
+        assert_eq!(
+            expression("x! as const;"),
+            AstNode::TSAsExpression {
+                expression: Box::new(AstNode::TSNonNullExpression {
+                    expression: Box::new(AstNode::identifier("x".to_string())),
+                }),
+                type_annotation: Box::new(type_reference("const")),
+            }
+        );
+        assert_eq!(
+            expression("<T>x satisfies U;"),
+            AstNode::TSSatisfiesExpression {
+                expression: Box::new(AstNode::TSTypeAssertion {
+                    type_annotation: Box::new(type_reference("T")),
+                    expression: Box::new(AstNode::identifier("x".to_string())),
+                }),
+                type_annotation: Box::new(type_reference("U")),
+            }
+        );
+        assert_eq!(
+            expression("f<T>(x);"),
+            AstNode::CallExpression {
+                callee: Box::new(AstNode::identifier("f".to_string())),
+                arguments: vec![AstNode::identifier("x".to_string())],
+                optional: false,
+                type_arguments: Some(Box::new(AstNode::TSTypeParameterInstantiation {
+                    params: vec![type_reference("T")],
+                })),
+            }
+        );
+        assert_eq!(
+            expression("<const>['a'];"),
+            AstNode::TSTypeAssertion {
+                type_annotation: Box::new(type_reference("const")),
+                expression: Box::new(AstNode::ArrayExpression {
+                    elements: vec![Some(AstNode::StringLiteral("a".to_string()))],
+                }),
+            }
+        );
+        assert_eq!(
+            expression("f<T>;"),
+            AstNode::TSInstantiationExpression {
+                expression: Box::new(AstNode::identifier("f".to_string())),
+                type_arguments: Box::new(AstNode::TSTypeParameterInstantiation {
+                    params: vec![type_reference("T")],
+                }),
+            }
+        );
+        assert!(matches!(
+            expression("a.b<T> === c;"),
+            AstNode::BinaryExpression { operator, left, .. }
+                if operator == "===" && matches!(*left, AstNode::TSInstantiationExpression { .. })
+        ));
+        // When an expression follows the `>`, `<` and `>` are comparisons.
+        assert!(matches!(
+            expression("x < y > z;"),
+            AstNode::BinaryExpression { operator, .. } if operator == ">"
+        ));
+        assert!(matches!(
+            expression("async <T>(x: T): Promise<T> => x;"),
+            AstNode::ArrowFunctionExpression {
+                r#async: true,
+                type_parameters: Some(_),
+                return_type: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            expression("a ? (b) : c;"),
+            AstNode::TernaryExpression { .. }
+        ));
+    }
+
+    #[test]
+    fn test_typescript_arrow_functions_in_conditionals() {
+        // `(b) : c => d` cannot be an arrow function with a return type when
+        // no `:` is left for the conditional.
+        let source = "x = a ? (b) : c => d;";
+        assert_eq!(
+            expression_with(&typescript_options(), source),
+            parse_expression(source)
+        );
+        let AstNode::AssignmentExpression { right, .. } =
+            expression_with(&typescript_options(), "x = a ? (b): T => d : e;")
+        else {
+            panic!("expected an assignment");
+        };
+        assert!(matches!(
+            *right,
+            AstNode::TernaryExpression { middle, .. }
+                if matches!(*middle, AstNode::ArrowFunctionExpression { return_type: Some(_), .. })
+        ));
+        assert!(parse_with(&typescript_options(), "x = a ? f((b): c => d) : e;").is_ok());
+    }
+
+    #[test]
+    fn test_tsx_generic_arrow_functions() {
+        let options = ParseOptions {
+            jsx: true,
+            typescript: true,
+            ..ParseOptions::default()
+        };
+        let parse = |source: &str| parse_with(&options, source);
+        assert!(parse("f = <T,>(x: T) => <div>{x}</div>;").is_ok());
+        assert!(parse("f = <T extends object>(x: T) => x;").is_ok());
+        // In TSX, `<T>` starts an element rather than a type assertion.
+        assert!(parse("x = <T>y</T>;").is_ok());
+    }
+
+    #[test]
+    fn test_typescript_declaration_files() {
+        let options = ParseOptions {
+            declaration_file: true,
+            ..typescript_options()
+        };
+        let parse = |source: &str| parse_with(&options, source);
+        assert!(
+            parse("export function f(): void;\nlet x: number;\nclass C { m(): void; }").is_ok()
+        );
+        assert_eq!(
+            parse("f();").unwrap_err()[0].message,
+            "Statements are not allowed in ambient contexts."
+        );
+        assert_eq!(
+            parse("function g() {}").unwrap_err()[0].message,
+            "An implementation cannot be declared in ambient contexts."
+        );
+    }
+
+    fn flow_options() -> ParseOptions {
+        ParseOptions {
+            flow: true,
+            ..module_options()
+        }
+    }
+
+    fn flow_annotation(type_annotation: AstNode) -> Option<Box<AstNode>> {
+        Some(Box::new(AstNode::TypeAnnotation {
+            type_annotation: Box::new(type_annotation),
+        }))
+    }
+
+    fn generic_type(name: &str) -> AstNode {
+        AstNode::GenericTypeAnnotation {
+            id: Box::new(AstNode::identifier(name.to_string())),
+            type_parameters: None,
+        }
+    }
+
+    #[test]
+    fn test_flow_annotations() {
+        assert_eq!(
+            first_statement_with(
+                &flow_options(),
+                "function f<+T: Object>(a: ?T, b?: string): void {}"
+            ),
+            AstNode::FunctionDeclaration {
+                id: Box::new(AstNode::identifier("f".to_string())),
+                params: vec![
+                    AstNode::Identifier {
+                        name: "a".to_string(),
+                        type_annotation: flow_annotation(AstNode::NullableTypeAnnotation {
+                            type_annotation: Box::new(generic_type("T")),
+                        }),
+                        optional: false,
+                    },
+                    AstNode::Identifier {
+                        name: "b".to_string(),
+                        type_annotation: flow_annotation(AstNode::StringTypeAnnotation),
+                        optional: true,
+                    },
+                ],
+                body: Box::new(AstNode::BlockStatement { body: vec![] }),
+                generator: false,
+                r#async: false,
+                type_parameters: Some(Box::new(AstNode::TypeParameterDeclaration {
+                    params: vec![AstNode::TypeParameter {
+                        name: "T".to_string(),
+                        bound: flow_annotation(generic_type("Object")),
+                        variance: Some(Box::new(AstNode::Variance {
+                            kind: "plus".to_string(),
+                        })),
+                        default: None,
+                    }],
+                })),
+                return_type: flow_annotation(AstNode::VoidTypeAnnotation),
+            }
+        );
+        assert!(matches!(
+            expression_with(&flow_options(), "({ a }: T, ...r: Array<T>) => a;"),
+            AstNode::ArrowFunctionExpression { params, .. } if matches!(
+                &params[..],
+                [
+                    AstNode::ObjectPattern { type_annotation: Some(_), .. },
+                    AstNode::RestElement { type_annotation: Some(_), .. },
+                ]
+            )
+        ));
+        assert_eq!(
+            first_statement_with(&flow_options(), "(x: any);"),
+            AstNode::ExpressionStatement {
+                expression: Box::new(AstNode::TypeCastExpression {
+                    expression: Box::new(AstNode::identifier("x".to_string())),
+                    type_annotation: Box::new(AstNode::TypeAnnotation {
+                        type_annotation: Box::new(AstNode::AnyTypeAnnotation),
+                    }),
+                }),
+                directive: None,
+            }
+        );
+        for source in [
+            "const g = <T>(x: T): T => x; const h = async (x: number): Promise<void> => {};",
+            "class C<+T, -U> extends B<T> { +x: T; -y: U = 1; static +z: number; m(): void {} }",
+            "let m: Map<string, Array<?number>>= new Map(); f<T>(x); x < y > z;",
+            "let a = b ? (c) : d; type = 1; opaque = 2;",
+        ] {
+            assert!(parse_with(&flow_options(), source).is_ok(), "{}", source);
+        }
+        assert_eq!(
+            parse_with(&flow_options(), "class C { +m() {} }").unwrap_err()[0].message,
+            "Variance is only allowed on fields."
+        );
+    }
+
+    #[test]
+    fn test_flow_types() {
+        assert_eq!(
+            first_statement_with(&flow_options(), "type A = {| +a?: string, [k: K]: V |};"),
+            AstNode::TypeAlias {
+                id: Box::new(AstNode::identifier("A".to_string())),
+                type_parameters: None,
+                right: Box::new(AstNode::ObjectTypeAnnotation {
+                    properties: vec![AstNode::ObjectTypeProperty {
+                        key: Box::new(AstNode::identifier("a".to_string())),
+                        value: Box::new(AstNode::StringTypeAnnotation),
+                        optional: true,
+                        method: false,
+                        variance: Some(Box::new(AstNode::Variance {
+                            kind: "plus".to_string(),
+                        })),
+                        r#static: false,
+                    }],
+                    indexers: vec![AstNode::ObjectTypeIndexer {
+                        id: Some(Box::new(AstNode::identifier("k".to_string()))),
+                        key: Box::new(generic_type("K")),
+                        value: Box::new(generic_type("V")),
+                        variance: None,
+                    }],
+                    call_properties: vec![],
+                    exact: true,
+                    inexact: false,
+                }),
+            }
+        );
+        assert!(matches!(
+            first_statement_with(&flow_options(), "type I = { a: string, ... };"),
+            AstNode::TypeAlias { right, .. }
+                if matches!(*right, AstNode::ObjectTypeAnnotation { inexact: true, .. })
+        ));
+        for source in [
+            "type E = {||}; type S = { ...A, b: number, (x: number): string, m<T>(x: T): U };",
+            "type F = (x: number, y?: string, ...rest: Array<any>) => void;",
+            "type G = <T>(T) => T; type H = (string, number) => boolean;",
+            "type U = | 'a' | -1 | true | null | void | *; type X = A & B;",
+            "type T = [number, string]; type Q = typeof x; type R = A.B.C<D>; type N = ?number[][];",
+        ] {
+            assert!(parse_with(&flow_options(), source).is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_flow_declarations() {
+        assert_eq!(
+            first_statement_with(
+                &flow_options(),
+                "export opaque type Token: string = string;"
+            ),
+            AstNode::ExportNamedDeclaration {
+                declaration: Some(Box::new(AstNode::OpaqueType {
+                    id: Box::new(AstNode::identifier("Token".to_string())),
+                    type_parameters: None,
+                    supertype: Some(Box::new(AstNode::StringTypeAnnotation)),
+                    impltype: Box::new(AstNode::StringTypeAnnotation),
+                })),
+                specifiers: vec![],
+                source: None,
+                attributes: vec![],
+                export_kind: "type".to_string(),
+            }
+        );
+        let AstNode::ImportDeclaration {
+            specifiers,
+            import_kind,
+            ..
+        } = first_statement_with(&flow_options(), "import typeof { a, type B } from 'm';")
+        else {
+            panic!("expected an import declaration");
+        };
+        assert_eq!(import_kind, "typeof");
+        assert!(matches!(
+            &specifiers[1],
+            AstNode::ImportSpecifier { import_kind, .. } if import_kind == "type"
+        ));
+        assert!(parse_with(
+            &flow_options(),
+            "import type A from 'a'; export type { A };"
+        )
+        .is_ok());
+        // Without the option, `type` is a plain identifier.
+        assert!(Parser::new(lexer::tokenize("type = 1;")).parse().is_ok());
+        assert!(!parse_errors("type A = number;").is_empty());
+    }
+
+    #[test]
+    fn test_flow_declare() {
+        assert_eq!(
+            first_statement_with(&flow_options(), "declare var x: number;"),
+            AstNode::DeclareVariable {
+                id: Box::new(AstNode::Identifier {
+                    name: "x".to_string(),
+                    type_annotation: flow_annotation(AstNode::NumberTypeAnnotation),
+                    optional: false,
+                }),
+                kind: "var".to_string(),
+            }
+        );
+        assert_eq!(
+            first_statement_with(&flow_options(), "declare function f(x: number): void;"),
+            AstNode::DeclareFunction {
+                id: Box::new(AstNode::Identifier {
+                    name: "f".to_string(),
+                    type_annotation: flow_annotation(AstNode::FunctionTypeAnnotation {
+                        type_parameters: None,
+                        params: vec![AstNode::FunctionTypeParam {
+                            name: Some(Box::new(AstNode::identifier("x".to_string()))),
+                            type_annotation: Box::new(AstNode::NumberTypeAnnotation),
+                            optional: false,
+                        }],
+                        rest: None,
+                        return_type: Box::new(AstNode::VoidTypeAnnotation),
+                    }),
+                    optional: false,
+                }),
+            }
+        );
+        let AstNode::DeclareClass { extends, body, .. } = first_statement_with(
+            &flow_options(),
+            "declare class C<T> extends B<T> { static x: T; m(): void }",
+        ) else {
+            panic!("expected a declared class");
+        };
+        assert!(matches!(
+            &extends[..],
+            [AstNode::InterfaceExtends {
+                type_parameters: Some(_),
+                ..
+            }]
+        ));
+        assert!(matches!(
+            *body,
+            AstNode::ObjectTypeAnnotation { properties, .. } if matches!(
+                &properties[..],
+                [
+                    AstNode::ObjectTypeProperty { r#static: true, method: false, .. },
+                    AstNode::ObjectTypeProperty { r#static: false, method: true, .. },
+                ]
+            )
+        ));
+        let AstNode::DeclareModule { id, body, kind } = first_statement_with(
+            &flow_options(),
+            "declare module 'm' { import type { A } from 'a'; declare export default A; }",
+        ) else {
+            panic!("expected a declared module");
+        };
+        assert_eq!(*id, AstNode::StringLiteral("m".to_string()));
+        assert_eq!(kind, "ES");
+        assert!(matches!(
+            *body,
+            AstNode::BlockStatement { body } if matches!(
+                body[1],
+                AstNode::DeclareExportDeclaration { default: true, .. }
+            )
+        ));
+        for source in [
+            "declare let a: string; declare const b: ?number;",
+            "declare type T = number; declare opaque type O: string; declare opaque type P;",
+            "declare module M { declare module.exports: { f(): void }; }",
+            "declare export function f(): void; declare export class C {}",
+            "declare export var v: number; declare export opaque type Q;",
+            "declare export { a, b as c } from 'm'; declare export * from 'n';",
+            "declare export default class D {} declare var x: number; var x = 1;",
+        ] {
+            assert!(parse_with(&flow_options(), source).is_ok(), "{}", source);
+        }
+        // `declare` is still an identifier when no declaration follows it.
+        assert!(parse_with(&flow_options(), "declare(x); declare = 1;\ndeclare\nvar y;").is_ok());
+        assert!(parse_with(&flow_options(), "declare module 'm' { var x; }").is_err());
+    }
+
+    #[test]
+    fn test_flow_arrow_functions_in_conditionals() {
+        let source = "x = a ? (b) : c => d;";
+        assert_eq!(
+            expression_with(&flow_options(), source),
+            parse_expression(source)
+        );
+        let AstNode::AssignmentExpression { right, .. } =
+            expression_with(&flow_options(), "x = a ? (b): T => d : e;")
+        else {
+            panic!("expected an assignment");
+        };
+        assert!(matches!(
+            *right,
+            AstNode::TernaryExpression { middle, .. }
+                if matches!(*middle, AstNode::ArrowFunctionExpression { return_type: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn test_flow_pragma() {
+        let options = ParseOptions::default();
+        assert!(lexer::parse("/* @flow */\nlet x: number = 1;", &options).is_ok());
+        assert!(lexer::parse("let x: number = 1;", &options).is_err());
+    }
+
+    fn feature_options(features: &[Feature]) -> ParseOptions {
+        ParseOptions {
+            features: features.iter().copied().collect(),
+            ..module_options()
+        }
+    }
+
+    #[test]
+    fn test_explicit_resource_management() {
+        let feature = Feature::ExplicitResourceManagement;
+        assert_eq!(
+            first_statement_with(
+                &feature_options(&[feature]),
+                "await using res = open(), other = null;"
+            ),
+            AstNode::VariableDeclaration {
+                kind: "await using".to_string(),
+                declarations: vec![
+                    AstNode::VariableDeclarator {
+                        id: Box::new(AstNode::identifier("res".to_string())),
+                        init: Some(Box::new(AstNode::CallExpression {
+                            callee: Box::new(AstNode::identifier("open".to_string())),
+                            arguments: vec![],
+                            optional: false,
+                            type_arguments: None,
+                        })),
+                        definite: false,
+                    },
+                    AstNode::VariableDeclarator {
+                        id: Box::new(AstNode::identifier("other".to_string())),
+                        init: Some(Box::new(AstNode::NullLiteral)),
+                        definite: false,
+                    },
+                ],
+                declare: false,
+            }
+        );
+        for source in [
+            "{ using x = a; } for (using x of xs); for (await using x of xs);",
+            // `using` is still an identifier where no binding follows it.
+            "using(x); using = 1; using[x] = y; using\nx; await using; for (using of xs);",
+        ] {
+            assert!(
+                parse_with(&feature_options(&[feature]), source).is_ok(),
+                "{}",
+                source
+            );
+        }
+        let errors = parse_with(&feature_options(&[feature]), "using x;").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Missing initializer in using declaration."
+        );
+        // A declaration must be inside a block, function or module.
+        let script = ParseOptions {
+            source_type: SourceType::Script,
+            ..feature_options(&[feature])
+        };
+        let errors = parse_with(&script, "using x = a;").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "'using' declarations are not allowed at the top level of a script."
+        );
+        assert!(parse_with(&script, "{ using x = a; } function f() { using y = b; }").is_ok());
+        for source in [
+            "switch (a) { case 1: using x = b; }",
+            "switch (a) { default: await using x = b; }",
+        ] {
+            let errors = parse_with(&feature_options(&[feature]), source).unwrap_err();
+            assert_eq!(
+                errors[0].message,
+                "'using' declarations are not allowed directly in 'case' or 'default' clauses.",
+                "{}",
+                source
+            );
+        }
+        assert!(parse_with(
+            &feature_options(&[feature]),
+            "switch (a) { case 1: { using x = b; } }"
+        )
+        .is_ok());
+        let errors = parse_with(&feature_options(&[]), "using x = y;").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::FeatureNotEnabled);
+        assert_eq!(
+            errors[0].message,
+            "Support for 'using' declarations is not enabled; add \
+             Feature::ExplicitResourceManagement to ParseOptions.features."
+        );
+    }
+
+    #[test]
+    fn test_phase_imports() {
+        assert_eq!(
+            first_statement_with(
+                &feature_options(&[Feature::ImportDefer]),
+                "import defer * as ns from 'm';"
+            ),
+            AstNode::ImportDeclaration {
+                specifiers: vec![AstNode::ImportNamespaceSpecifier {
+                    local: Box::new(AstNode::identifier("ns".to_string())),
+                }],
+                source: Box::new(AstNode::StringLiteral("m".to_string())),
+                attributes: vec![],
+                import_kind: "value".to_string(),
+                phase: Some("defer".to_string()),
+            }
+        );
+        assert!(matches!(
+            first_statement_with(&feature_options(&[Feature::SourcePhaseImports]), "import source wasm from './m.wasm';"),
+            AstNode::ImportDeclaration { phase: Some(phase), .. } if phase == "source"
+        ));
+        assert!(matches!(
+            first_statement_with(&feature_options(&[Feature::SourcePhaseImports]), "import.source('./m.wasm');"),
+            AstNode::ExpressionStatement { expression, .. }
+                if matches!(&*expression, AstNode::ImportExpression { phase: Some(phase), .. } if phase == "source")
+        ));
+        // Without a binding after it, the word is the default import.
+        for source in ["import defer from 'm';", "import source from 'm';"] {
+            assert!(matches!(
+                first_statement_with(&feature_options(&[Feature::SourcePhaseImports]), source),
+                AstNode::ImportDeclaration { phase: None, .. }
+            ));
+        }
+        assert!(matches!(
+            first_statement_with(
+                &feature_options(&[Feature::SourcePhaseImports]),
+                "import source from from 'm';"
+            ),
+            AstNode::ImportDeclaration { phase: Some(_), .. }
+        ));
+        for source in ["import defer * as ns from 'm';", "import.defer('m');"] {
+            let errors =
+                parse_with(&feature_options(&[Feature::SourcePhaseImports]), source).unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].code, ErrorCode::FeatureNotEnabled);
+        }
+    }
+
+    #[test]
+    fn test_expression_proposals() {
+        assert_eq!(
+            first_statement_with(
+                &feature_options(&[Feature::PipelineOperator]),
+                "x |> f(%) |> % + 1;"
+            ),
+            AstNode::ExpressionStatement {
+                expression: Box::new(AstNode::BinaryExpression {
+                    operator: "|>".to_string(),
+                    left: Box::new(AstNode::BinaryExpression {
+                        operator: "|>".to_string(),
+                        left: Box::new(AstNode::identifier("x".to_string())),
+                        right: Box::new(AstNode::CallExpression {
+                            callee: Box::new(AstNode::identifier("f".to_string())),
+                            arguments: vec![AstNode::TopicReference],
+                            optional: false,
+                            type_arguments: None,
+                        }),
+                    }),
+                    right: Box::new(AstNode::BinaryExpression {
+                        operator: "+".to_string(),
+                        left: Box::new(AstNode::TopicReference),
+                        right: Box::new(AstNode::NumberLiteral(1.0)),
+                    }),
+                }),
+                directive: None,
+            }
+        );
+        assert_eq!(
+            first_statement_with(
+                &feature_options(&[Feature::DoExpressions]),
+                "let y = do { 1; };"
+            ),
+            AstNode::VariableDeclaration {
+                kind: "let".to_string(),
+                declarations: vec![AstNode::VariableDeclarator {
+                    id: Box::new(AstNode::identifier("y".to_string())),
+                    init: Some(Box::new(AstNode::DoExpression {
+                        body: Box::new(AstNode::BlockStatement {
+                            body: vec![AstNode::ExpressionStatement {
+                                expression: Box::new(AstNode::NumberLiteral(1.0)),
+                                directive: None,
+                            }],
+                        }),
+                    })),
+                    definite: false,
+                }],
+                declare: false,
+            }
+        );
+        assert!(matches!(
+            first_statement_with(&feature_options(&[Feature::ThrowExpressions]), "f(x ?? throw err);"),
+            AstNode::ExpressionStatement { expression, .. }
+                if matches!(&*expression, AstNode::CallExpression { arguments, .. }
+                    if matches!(&arguments[0], AstNode::LogicalExpression { right, .. }
+                        if matches!(&**right, AstNode::ThrowExpression { .. })))
+        ));
+        let pipeline = [Feature::PipelineOperator];
+        assert!(parse_with(
+            &feature_options(&pipeline),
+            "x |> (% |> [%]) |> (y => y + %);"
+        )
+        .is_ok());
+        assert_eq!(
+            parse_with(&feature_options(&pipeline), "x |> f;").unwrap_err()[0].message,
+            "Pipeline body must refer to the topic '%'."
+        );
+        assert!(parse_with(&feature_options(&pipeline), "f(%);").is_err());
+        // Each proposal needs its own feature.
+        for source in ["x |> f(%);", "let y = do {};", "f(throw err);"] {
+            let errors = parse_with(&feature_options(&[Feature::ImportDefer]), source).unwrap_err();
+            assert_eq!(errors.len(), 1, "{}", source);
+            assert_eq!(errors[0].code, ErrorCode::FeatureNotEnabled);
+        }
     }
 }
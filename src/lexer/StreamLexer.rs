@@ -0,0 +1,288 @@
+use std::io::{self, Read};
+
+use crate::lexer::Lexer::{Lexer, LexerState};
+use crate::lexer::ParseError::ParseError;
+use crate::lexer::ParseOptions::ParseOptions;
+use crate::lexer::Token::{Span, SpannedToken, Token};
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+// How many characters past the end of a token the lexer may look at to
+// decide where the token ends, as with the `!--` after `<`.
+const LOOKAHEAD: usize = 3;
+
+/// Lexes source text read incrementally from any `io::Read`.
+///
+/// Only the text from the end of the last returned token up to the end of
+/// the current chunk is kept in memory. A token that ends near the end of the
+/// buffered text may continue in the next chunk, as `.` does in `...`, so it
+/// is only returned once `LOOKAHEAD` more characters have been read or the
+/// reader is exhausted. Such a token is lexed again only once the text that
+/// has not been lexed yet has doubled, which keeps lexing a token larger than
+/// many chunks, such as a long comment or string, linear in its length.
+/// Spans are byte offsets from the start of the stream.
+pub struct StreamLexer<R> {
+    reader: R,
+    chunk_size: usize,
+    options: ParseOptions,
+    buffer: String,
+    // Bytes of a UTF-8 sequence split across reads.
+    pending: Vec<u8>,
+    // Offset of `buffer` in the stream and the buffered position at which
+    // lexing resumes.
+    base: usize,
+    start: usize,
+    at_end_of_input: bool,
+    finished: bool,
+    // The lexer state after the last returned token, which the lexer of
+    // the next token resumes in.
+    state: LexerState,
+    // How much text past `start` must be buffered before lexing again.
+    wanted: usize,
+    // Errors reported for the returned tokens, and whether the `@flow`
+    // pragma was found.
+    errors: Vec<ParseError>,
+    flow_pragma: bool,
+}
+
+impl<R: Read> StreamLexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        Self::build(reader, chunk_size, &ParseOptions::default())
+    }
+
+    /// Creates a stream lexer for the goal symbol and syntax given by
+    /// `options`, as `Lexer::with_options` does.
+    pub fn with_options(reader: R, options: &ParseOptions) -> Self {
+        Self::build(reader, DEFAULT_CHUNK_SIZE, options)
+    }
+
+    fn build(reader: R, chunk_size: usize, options: &ParseOptions) -> Self {
+        Self {
+            reader,
+            chunk_size: chunk_size.max(1),
+            options: options.clone(),
+            buffer: String::new(),
+            pending: Vec::new(),
+            base: 0,
+            start: 0,
+            at_end_of_input: false,
+            finished: false,
+            state: LexerState::default(),
+            wanted: 0,
+            errors: Vec::new(),
+            flow_pragma: false,
+        }
+    }
+
+    /// Returns the errors reported for the tokens yielded since the last
+    /// call, with spans from the start of the stream.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Whether a comment before the first token holds the `@flow` pragma.
+    pub fn flow_pragma(&self) -> bool {
+        self.flow_pragma
+    }
+
+    fn next_spanned_token(&mut self) -> io::Result<SpannedToken> {
+        loop {
+            if self.buffer.len() - self.start >= self.wanted || self.at_end_of_input {
+                let mut lexer =
+                    Lexer::resuming(&self.buffer, self.start, &self.options, self.state.clone());
+                let token = lexer.next_spanned_token();
+                let lookahead = self.buffer[token.span.end..].chars().nth(LOOKAHEAD - 1);
+                if lookahead.is_some() || self.at_end_of_input {
+                    self.start = token.span.end;
+                    self.state = lexer.state();
+                    self.wanted = 0;
+                    self.flow_pragma |= lexer.flow_pragma();
+                    for mut error in lexer.take_errors() {
+                        error.span =
+                            Span::new(self.base + error.span.start, self.base + error.span.end);
+                        self.errors.push(error);
+                    }
+                    return Ok(SpannedToken {
+                        span: Span::new(self.base + token.span.start, self.base + token.span.end),
+                        ..token
+                    });
+                }
+                self.wanted = 2 * (self.buffer.len() - self.start);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Drops the text before `start` and appends the next chunk of input.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.start);
+        self.base += self.start;
+        self.start = 0;
+
+        let mut chunk = vec![0; self.chunk_size];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        if read == 0 {
+            self.at_end_of_input = true;
+            if !self.pending.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+
+        self.pending.extend_from_slice(&chunk[..read]);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        // The prefix was just validated, so this cannot fail.
+        self.buffer
+            .push_str(std::str::from_utf8(&self.pending[..valid]).unwrap());
+        self.pending.drain(..valid);
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for StreamLexer<R> {
+    type Item = io::Result<SpannedToken>;
+
+    /// Yields tokens up to and including `Token::EOF`.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.next_spanned_token();
+        match &token {
+            Ok(SpannedToken {
+                token: Token::EOF, ..
+            })
+            | Err(_) => self.finished = true,
+            Ok(_) => {}
+        }
+        Some(token)
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer;
+
+    #[test]
+    fn test_matches_whole_source_lexing_for_every_chunk_size() {
+        let source = "var héllo = \"日本語 string\"; // comment ✓\n\
+                      /* block\n comment */ if (héllo >= 10.5) { x += y === z; }\n\
+                      a <<= b >>= c !== d || e && f";
+        let expected = lexer::tokenize(source);
+        for chunk_size in 1..=16 {
+            let tokens: Vec<SpannedToken> =
+                StreamLexer::with_chunk_size(source.as_bytes(), chunk_size)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_tokens_cut_at_chunk_edges() {
+        let source = "f(...xs, 1_000, a >>>= b);\n<!-- html comment\nx = 日本 ... 1_0\n\
+                      t = `a${ { b: `c${d}` }.b }e`;";
+        let expected = lexer::tokenize(source);
+        for chunk_size in 1..=8 {
+            let tokens: Vec<SpannedToken> =
+                StreamLexer::with_chunk_size(source.as_bytes(), chunk_size)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_html_close_comment_only_at_line_start() {
+        let source = "while (i-->0) { x = a-->b; }\n  --> comment\ny = a\n-->b";
+        let expected = lexer::tokenize(source);
+        for chunk_size in 1..=source.len() {
+            let tokens: Vec<SpannedToken> =
+                StreamLexer::with_chunk_size(source.as_bytes(), chunk_size)
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_options_and_errors() {
+        let options = ParseOptions {
+            jsx: true,
+            ..ParseOptions::default()
+        };
+        let source = "// @flow\nx = <a b=\"c\">d {e}</a> + 1__0;";
+        let mut expected_lexer = Lexer::with_options(source, &options);
+        let expected = expected_lexer.tokenize();
+        for chunk_size in [1, 2, 5, 64] {
+            let mut stream = StreamLexer::build(source.as_bytes(), chunk_size, &options);
+            let tokens: Vec<SpannedToken> = stream.by_ref().collect::<io::Result<_>>().unwrap();
+            assert_eq!(tokens, expected, "chunk size {}", chunk_size);
+            assert!(stream.flow_pragma());
+            let errors = stream.take_errors();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].span.start, source.find("__").unwrap());
+            assert!(stream.take_errors().is_empty());
+        }
+        assert!(!expected_lexer.take_errors().is_empty());
+    }
+
+    #[test]
+    fn test_long_token_spanning_many_chunks() {
+        let source = format!(
+            "a /* {} */ b '{}' c",
+            "x".repeat(100_000),
+            "y".repeat(100_000)
+        );
+        let tokens: Vec<SpannedToken> = StreamLexer::with_chunk_size(source.as_bytes(), 16)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(tokens, lexer::tokenize(&source));
+    }
+
+    #[test]
+    fn test_ends_after_eof_token() {
+        let mut stream = StreamLexer::new("x".as_bytes());
+        assert_eq!(
+            stream.next().unwrap().unwrap().token,
+            Token::Identifier("x".to_string())
+        );
+        let eof = stream.next().unwrap().unwrap();
+        assert_eq!(eof.token, Token::EOF);
+        assert_eq!(eof.span, Span::new(1, 1));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let bytes: &[u8] = &[b'a', b' ', 0xff, b'b'];
+        let result: io::Result<Vec<SpannedToken>> =
+            StreamLexer::with_chunk_size(bytes, 2).collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // A multi-byte sequence cut off by the end of the stream.
+        let bytes: &[u8] = &[b'a', 0xe6, 0x97];
+        let result: io::Result<Vec<SpannedToken>> = StreamLexer::new(bytes).collect();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
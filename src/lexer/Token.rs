@@ -1,9 +1,11 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     // Single-character tokens
     LeftParen, RightParen, LeftBrace, RightBrace,
     LeftBracket, RightBracket,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    Comma, Dot, Minus, Plus, Semicolon, Slash, Star, Percent,
 
     // One or two character tokens
     Bang, BangEqual,
@@ -13,15 +15,31 @@ pub enum Token {
     GreaterGreater, GreaterGreaterEqual,
     LessLess, LessLessEqual,
     PlusPlus, MinusMinus,
+    PlusEqual, MinusEqual, StarEqual, SlashEqual, PercentEqual,
+    StarStar, StarStarEqual,
+    GreaterGreaterGreater, GreaterGreaterGreaterEqual,
     EqualEqualEqual, BangEqualEqual,
-    Ampersand, AmpersandAmpersand,
-    Pipe, PipePipe,
-    Caret, Tilde,
-    Question, Colon,
+    Ampersand, AmpersandAmpersand, AmpersandEqual, AmpersandAmpersandEqual,
+    Pipe, PipePipe, PipeEqual, PipePipeEqual, PipeGreater,
+    Caret, CaretEqual, Tilde,
+    Question, QuestionQuestion, QuestionQuestionEqual, QuestionDot, Colon,
+    Ellipsis, Arrow, At,
 
 
     // Literals
     Identifier(String), String(String), Number(f64),
+    // A number with a leading zero, such as the octal `010` or `08`, which
+    // strict mode code forbids
+    LegacyOctal(f64),
+    // `#name` in a class body or member access
+    PrivateIdentifier(String),
+    // A tag or attribute name in JSX, which may contain `-`
+    JSXIdentifier(String),
+    // Text between JSX tags, with HTML entities decoded
+    JSXText(String),
+    // The raw text of a template literal: `` `a` `` without substitutions,
+    // or the parts of `` `a${b}c${d}e` `` before, between and after them
+    Template(String), TemplateHead(String), TemplateMiddle(String), TemplateTail(String),
 
     // Keywords
     Break, Case, Catch, Class, Const, Continue,
@@ -44,5 +62,209 @@ pub enum Token {
     Get, Set,
     Of,
 
+    // A character that does not start any token
+    Illegal(char),
+
     EOF
-}
\ No newline at end of file
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Minus => "-",
+            Token::Plus => "+",
+            Token::Semicolon => ";",
+            Token::Slash => "/",
+            Token::Star => "*",
+            Token::Percent => "%",
+            Token::Bang => "!",
+            Token::BangEqual => "!=",
+            Token::Equal => "=",
+            Token::EqualEqual => "==",
+            Token::Greater => ">",
+            Token::GreaterEqual => ">=",
+            Token::Less => "<",
+            Token::LessEqual => "<=",
+            Token::GreaterGreater => ">>",
+            Token::GreaterGreaterEqual => ">>=",
+            Token::LessLess => "<<",
+            Token::LessLessEqual => "<<=",
+            Token::PlusPlus => "++",
+            Token::MinusMinus => "--",
+            Token::PlusEqual => "+=",
+            Token::MinusEqual => "-=",
+            Token::StarEqual => "*=",
+            Token::SlashEqual => "/=",
+            Token::PercentEqual => "%=",
+            Token::StarStar => "**",
+            Token::StarStarEqual => "**=",
+            Token::GreaterGreaterGreater => ">>>",
+            Token::GreaterGreaterGreaterEqual => ">>>=",
+            Token::EqualEqualEqual => "===",
+            Token::BangEqualEqual => "!==",
+            Token::Ampersand => "&",
+            Token::AmpersandAmpersand => "&&",
+            Token::AmpersandEqual => "&=",
+            Token::AmpersandAmpersandEqual => "&&=",
+            Token::Pipe => "|",
+            Token::PipePipe => "||",
+            Token::PipeEqual => "|=",
+            Token::PipePipeEqual => "||=",
+            Token::PipeGreater => "|>",
+            Token::Caret => "^",
+            Token::CaretEqual => "^=",
+            Token::Tilde => "~",
+            Token::Question => "?",
+            Token::QuestionQuestion => "??",
+            Token::QuestionQuestionEqual => "??=",
+            Token::QuestionDot => "?.",
+            Token::Colon => ":",
+            Token::Ellipsis => "...",
+            Token::Arrow => "=>",
+            Token::At => "@",
+            Token::Identifier(name) => return write!(f, "{}", name),
+            Token::PrivateIdentifier(name) => return write!(f, "#{}", name),
+            Token::JSXIdentifier(name) | Token::JSXText(name) => return write!(f, "{}", name),
+            Token::String(value) => return write!(f, "{:?}", value),
+            Token::Template(text) => return write!(f, "`{}`", text),
+            Token::TemplateHead(text) => return write!(f, "`{}${{", text),
+            Token::TemplateMiddle(text) => return write!(f, "}}{}${{", text),
+            Token::TemplateTail(text) => return write!(f, "}}{}`", text),
+            Token::Number(value) | Token::LegacyOctal(value) => return write!(f, "{}", value),
+            Token::Break => "break",
+            Token::Case => "case",
+            Token::Catch => "catch",
+            Token::Class => "class",
+            Token::Const => "const",
+            Token::Continue => "continue",
+            Token::Debugger => "debugger",
+            Token::Default => "default",
+            Token::Delete => "delete",
+            Token::Do => "do",
+            Token::Else => "else",
+            Token::Export => "export",
+            Token::Extends => "extends",
+            Token::Finally => "finally",
+            Token::For => "for",
+            Token::Function => "function",
+            Token::If => "if",
+            Token::Import => "import",
+            Token::In => "in",
+            Token::Instanceof => "instanceof",
+            Token::New => "new",
+            Token::Return => "return",
+            Token::Super => "super",
+            Token::Switch => "switch",
+            Token::This => "this",
+            Token::Throw => "throw",
+            Token::Try => "try",
+            Token::Typeof => "typeof",
+            Token::Var => "var",
+            Token::Void => "void",
+            Token::While => "while",
+            Token::With => "with",
+            Token::Enum => "enum",
+            Token::Null => "null",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Async => "async",
+            Token::Await => "await",
+            Token::Get => "get",
+            Token::Set => "set",
+            Token::Of => "of",
+            Token::Illegal(ch) => return write!(f, "{}", ch),
+            Token::EOF => "end of input",
+        };
+        f.write_str(text)
+    }
+}
+
+impl Token {
+    /// Whether this is a reserved or contextual keyword. Keywords can still
+    /// be used as property names, e.g. `a.new` or `{ if: 1 }`.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            Token::Break
+                | Token::Case
+                | Token::Catch
+                | Token::Class
+                | Token::Const
+                | Token::Continue
+                | Token::Debugger
+                | Token::Default
+                | Token::Delete
+                | Token::Do
+                | Token::Else
+                | Token::Export
+                | Token::Extends
+                | Token::Finally
+                | Token::For
+                | Token::Function
+                | Token::If
+                | Token::Import
+                | Token::In
+                | Token::Instanceof
+                | Token::New
+                | Token::Return
+                | Token::Super
+                | Token::Switch
+                | Token::This
+                | Token::Throw
+                | Token::Try
+                | Token::Typeof
+                | Token::Var
+                | Token::Void
+                | Token::While
+                | Token::With
+                | Token::Enum
+                | Token::Null
+                | Token::True
+                | Token::False
+                | Token::Async
+                | Token::Await
+                | Token::Get
+                | Token::Set
+                | Token::Of
+        )
+    }
+}
+
+/// Byte range of a token or node in the source text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A token together with its location and whether a line terminator
+/// separates it from the previous token (needed for automatic semicolon
+/// insertion and the `[no LineTerminator here]` restrictions).
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+    pub newline_before: bool,
+}
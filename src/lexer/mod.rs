@@ -1,4 +1,64 @@
-mod Lexer;
-mod Token;
-mod Parser;
-mod AstNode;
\ No newline at end of file
+#![allow(non_snake_case)]
+
+pub mod Lexer;
+pub mod Token;
+pub mod Parser;
+pub mod AstNode;
+pub mod Document;
+pub mod StreamLexer;
+pub mod ParseError;
+pub mod DiagnosticRenderer;
+pub mod ParseOptions;
+pub mod EarlyErrors;
+
+use self::Token::SpannedToken;
+
+/// Scans `source` into spanned tokens terminated by `Token::EOF`.
+pub fn tokenize(source: &str) -> Vec<SpannedToken> {
+    Lexer::Lexer::new(source).tokenize()
+}
+
+/// Like `tokenize`, but lexes for the goal symbol given by `options`.
+pub fn tokenize_with_options(source: &str, options: &ParseOptions::ParseOptions) -> Vec<SpannedToken> {
+    Lexer::Lexer::with_options(source, options).tokenize()
+}
+
+/// Lexes and parses `source` for `options`, returning the program or the
+/// errors reported by the lexer and the parser in source order. A program
+/// without syntax errors is also checked for early errors, which are
+/// reported with the span of the top-level statement they were found in.
+pub fn parse(
+    source: &str,
+    options: &ParseOptions::ParseOptions,
+) -> Result<AstNode::AstNode, Vec<ParseError::ParseError>> {
+    let mut lexer = Lexer::Lexer::with_options(source, options);
+    let (tokens, states) = lexer.tokenize_with_states();
+    let lexer_errors = lexer.take_errors();
+    let mut options = options.clone();
+    options.flow |= lexer.flow_pragma() && !options.typescript;
+    let mut parser =
+        Parser::Parser::with_source(source.to_string(), tokens, states, lexer_errors, options.clone());
+    let mut body = Vec::new();
+    let mut starts = Vec::new();
+    loop {
+        let start = parser.position();
+        let Some(statement) = parser.parse_statement() else {
+            break;
+        };
+        body.push(statement);
+        starts.push(start);
+    }
+    let parse_errors = parser.take_errors();
+    let end = parser.position();
+    let (_, tokens, _, mut errors) = parser.into_parts();
+    let program = AstNode::AstNode::Program { body };
+    if parse_errors.is_empty() {
+        errors.extend(EarlyErrors::validate_spanned(&program, &options, &tokens, &starts, end));
+    }
+    errors.extend(parse_errors);
+    if errors.is_empty() {
+        return Ok(program);
+    }
+    errors.sort_by_key(|error| error.span.start);
+    Err(errors)
+}
@@ -1 +1 @@
-mod lexer;
\ No newline at end of file
+pub mod lexer;